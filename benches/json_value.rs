@@ -0,0 +1,136 @@
+//! Measures the serialization of an already parsed `serde_json::Value`,
+//! comparing `to_json_writer` with a writer specialized for `Value` and with various writers.
+//!
+//! Run with `cargo bench --bench json_value`.
+extern crate serde_json;
+extern crate serdeconv;
+
+use serde_json::ser::{CompactFormatter, Formatter};
+use serde_json::Value;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 20;
+
+fn measure<F: FnMut()>(mut f: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+/// A writer specialized for `serde_json::Value`, which bypasses the `Serialize` machinery.
+fn write_value<W: Write>(w: &mut W, value: &Value) -> io::Result<()> {
+    let f = &mut CompactFormatter;
+    match *value {
+        Value::Null => f.write_null(w),
+        Value::Bool(b) => f.write_bool(w, b),
+        Value::Number(ref n) => {
+            if let Some(n) = n.as_u64() {
+                f.write_u64(w, n)
+            } else if let Some(n) = n.as_i64() {
+                f.write_i64(w, n)
+            } else {
+                f.write_f64(w, n.as_f64().unwrap_or(0.0))
+            }
+        }
+        Value::String(ref s) => write_str(w, s),
+        Value::Array(ref values) => {
+            w.write_all(b"[")?;
+            for (i, v) in values.iter().enumerate() {
+                if i > 0 {
+                    w.write_all(b",")?;
+                }
+                write_value(w, v)?;
+            }
+            w.write_all(b"]")
+        }
+        Value::Object(ref map) => {
+            w.write_all(b"{")?;
+            for (i, (k, v)) in map.iter().enumerate() {
+                if i > 0 {
+                    w.write_all(b",")?;
+                }
+                write_str(w, k)?;
+                w.write_all(b":")?;
+                write_value(w, v)?;
+            }
+            w.write_all(b"}")
+        }
+    }
+}
+
+fn write_str<W: Write>(w: &mut W, s: &str) -> io::Result<()> {
+    w.write_all(b"\"")?;
+    let mut start = 0;
+    for (i, &b) in s.as_bytes().iter().enumerate() {
+        if b != b'"' && b != b'\\' && b >= 0x20 {
+            continue;
+        }
+        w.write_all(&s.as_bytes()[start..i])?;
+        match b {
+            b'"' => w.write_all(b"\\\"")?,
+            b'\\' => w.write_all(b"\\\\")?,
+            _ => write!(w, "\\u{:04x}", b)?,
+        }
+        start = i + 1;
+    }
+    w.write_all(&s.as_bytes()[start..])?;
+    w.write_all(b"\"")
+}
+
+fn main() {
+    let json = (0..100_000)
+        .map(|i| {
+            format!(
+                r#"{{"id":{},"name":"item-{}","tags":["a","b"],"ratio":1.5}}"#,
+                i, i
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    let value: serde_json::Value = serdeconv::from_json_str(&format!("[{}]", json)).unwrap();
+    let path = env::temp_dir().join(format!("serdeconv-bench-{}.json", std::process::id()));
+
+    let elapsed = measure(|| {
+        let mut buf = Vec::new();
+        serdeconv::to_json_writer(&value, &mut buf).unwrap();
+    });
+    println!("to_json_writer (Vec):             {:?}", elapsed);
+
+    let elapsed = measure(|| {
+        let mut buf = Vec::new();
+        write_value(&mut buf, &value).unwrap();
+    });
+    println!("specialized write_value (Vec):    {:?}", elapsed);
+
+    let mut expected = Vec::new();
+    serdeconv::to_json_writer(&value, &mut expected).unwrap();
+    let mut actual = Vec::new();
+    write_value(&mut actual, &value).unwrap();
+    assert_eq!(actual, expected);
+
+    let elapsed = measure(|| {
+        let mut buf = Vec::new();
+        let bytes = serdeconv::to_json_vec(&value).unwrap();
+        buf.write_all(&bytes).unwrap();
+    });
+    println!("to_json_vec + write_all (Vec):    {:?}", elapsed);
+
+    let elapsed = measure(|| {
+        let f = File::create(&path).unwrap();
+        serdeconv::to_json_writer(&value, f).unwrap();
+    });
+    println!("to_json_writer (File):            {:?}", elapsed);
+
+    let elapsed = measure(|| {
+        let f = BufWriter::new(File::create(&path).unwrap());
+        serdeconv::to_json_writer(&value, f).unwrap();
+    });
+    println!("to_json_writer (BufWriter<File>): {:?}", elapsed);
+
+    fs::remove_file(path).unwrap();
+}
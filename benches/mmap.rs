@@ -0,0 +1,54 @@
+//! Compares the read-based and the mmap-based file deserialization.
+//!
+//! Run with `cargo bench --features mmap`.
+extern crate serdeconv;
+
+use std::env;
+use std::fs;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 20;
+
+fn measure<F: FnMut()>(mut f: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    let data = (0..1_000_000u64).collect::<Vec<_>>();
+    let dir = env::temp_dir();
+    let json_path = dir.join(format!("serdeconv-bench-{}.json", std::process::id()));
+    let msgpack_path = dir.join(format!("serdeconv-bench-{}.msgpack", std::process::id()));
+    serdeconv::to_json_file(&data, &json_path).unwrap();
+    serdeconv::to_msgpack_file(&data, &msgpack_path).unwrap();
+
+    let elapsed = measure(|| {
+        let v: Vec<u64> = serdeconv::from_json_file(&json_path).unwrap();
+        assert_eq!(v.len(), data.len());
+    });
+    println!("from_json_file:     {:?}", elapsed);
+
+    let elapsed = measure(|| {
+        let v: Vec<u64> = unsafe { serdeconv::from_json_mmap(&json_path).unwrap() };
+        assert_eq!(v.len(), data.len());
+    });
+    println!("from_json_mmap:     {:?}", elapsed);
+
+    let elapsed = measure(|| {
+        let v: Vec<u64> = serdeconv::from_msgpack_file(&msgpack_path).unwrap();
+        assert_eq!(v.len(), data.len());
+    });
+    println!("from_msgpack_file:  {:?}", elapsed);
+
+    let elapsed = measure(|| {
+        let v: Vec<u64> = unsafe { serdeconv::from_msgpack_mmap(&msgpack_path).unwrap() };
+        assert_eq!(v.len(), data.len());
+    });
+    println!("from_msgpack_mmap:  {:?}", elapsed);
+
+    fs::remove_file(json_path).unwrap();
+    fs::remove_file(msgpack_path).unwrap();
+}
@@ -0,0 +1,46 @@
+//! Measures the overhead of the error tracking in a tight decode loop.
+//!
+//! Run with `cargo bench --bench track` and `cargo bench --bench track --features no-trace`,
+//! and compare the results.
+extern crate serdeconv;
+
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 1_000_000;
+
+fn measure<F: FnMut()>(mut f: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    let valid = [0x93, 0x01, 0x02, 0x03];
+    let invalid = [0x93, 0x01, 0x02];
+
+    let elapsed = measure(|| {
+        let v: (u8, u8, u8) = serdeconv::from_msgpack_slice(&valid).unwrap();
+        assert_eq!(v.0, 1);
+    });
+    println!("from_msgpack_slice (Ok):  {:?}", elapsed);
+
+    let elapsed = measure(|| {
+        let e = serdeconv::from_msgpack_slice::<(u8, u8, u8)>(&invalid).unwrap_err();
+        assert!(e.is_invalid());
+    });
+    println!("from_msgpack_slice (Err): {:?}", elapsed);
+
+    let elapsed = measure(|| {
+        let v: [u8; 3] = serdeconv::from_json_slice(b"[1,2,3]").unwrap();
+        assert_eq!(v[0], 1);
+    });
+    println!("from_json_slice (Ok):     {:?}", elapsed);
+
+    let elapsed = measure(|| {
+        let e = serdeconv::from_json_slice::<[u8; 3]>(b"[1,2,").unwrap_err();
+        assert!(e.is_invalid());
+    });
+    println!("from_json_slice (Err):    {:?}", elapsed);
+}
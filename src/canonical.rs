@@ -0,0 +1,134 @@
+use serde::Serialize;
+use serde_json;
+
+use finite::check_finite_floats;
+use {Error, ErrorKind, Result};
+
+/// Converts the value to a canonical JSON bytes, following the JSON Canonicalization Scheme
+/// ([RFC 8785](https://www.rfc-editor.org/rfc/rfc8785)).
+///
+/// The output is suitable for computing signatures or hashes of JSON payloads,
+/// because equal values are always converted to the same bytes.
+///
+/// The following rules are applied:
+///
+/// - No insignificant whitespace is emitted.
+/// - Object members are sorted by their keys, compared as arrays of UTF-16 code units.
+/// - Strings are emitted as is, except that `"`, `\`, and the control characters are escaped
+///   (`\b`, `\t`, `\n`, `\f`, `\r`, or `\u00XX` with lowercase hexadecimal digits for the others).
+/// - Floating point numbers are formatted like ECMAScript's `Number.prototype.toString`:
+///   the shortest representation which round-trips is used, integral values are emitted
+///   without a fraction part (e.g., `1.0` becomes `1`), `-0.0` becomes `0`,
+///   and the exponential notation (e.g., `1e+21` and `1e-7`) is used
+///   if the decimal exponent is at least 21 or less than -6.
+/// - Integers are emitted in their exact decimal form.
+///   Note that RFC 8785 assumes that all numbers are IEEE 754 doubles,
+///   so integers whose magnitude exceeds 2^53 may not interoperate with other implementations.
+///
+/// Non-finite floats cannot be represented and result in an `ErrorKind::Invalid` error.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// let mut map = HashMap::new();
+/// map.insert("b", vec![1.0, 0.5, 1e21, 1e-7]);
+/// map.insert("a", vec![-0.0]);
+/// let json = serdeconv::to_canonical_json_vec(&map).unwrap();
+/// assert_eq!(json, br#"{"a":[0],"b":[1,0.5,1e+21,1e-7]}"#);
+/// ```
+pub fn to_canonical_json_vec<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: ?Sized + Serialize,
+{
+    track!(check_finite_floats(value))?;
+    let value = track!(serde_json::to_value(value).map_err(Error::from))?;
+    let mut buf = Vec::new();
+    track!(write_value(&mut buf, &value))?;
+    Ok(buf)
+}
+
+fn write_value(buf: &mut Vec<u8>, value: &serde_json::Value) -> Result<()> {
+    match *value {
+        serde_json::Value::Number(ref n) => {
+            if n.is_f64() {
+                let v = track_assert_some!(n.as_f64(), ErrorKind::Invalid);
+                buf.extend_from_slice(format_f64(v).as_bytes());
+            } else {
+                buf.extend_from_slice(n.to_string().as_bytes());
+            }
+        }
+        serde_json::Value::Array(ref values) => {
+            buf.push(b'[');
+            for (i, v) in values.iter().enumerate() {
+                if i > 0 {
+                    buf.push(b',');
+                }
+                track!(write_value(buf, v))?;
+            }
+            buf.push(b']');
+        }
+        serde_json::Value::Object(ref map) => {
+            let mut entries = map.iter().collect::<Vec<_>>();
+            entries.sort_by(|a, b| a.0.encode_utf16().cmp(b.0.encode_utf16()));
+
+            buf.push(b'{');
+            for (i, (k, v)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    buf.push(b',');
+                }
+                track!(serde_json::to_writer(&mut *buf, k).map_err(Error::from))?;
+                buf.push(b':');
+                track!(write_value(buf, v))?;
+            }
+            buf.push(b'}');
+        }
+        _ => {
+            track!(serde_json::to_writer(&mut *buf, value).map_err(Error::from))?;
+        }
+    }
+    Ok(())
+}
+
+/// Formats the finite float in the same way as ECMAScript's `Number.prototype.toString`.
+fn format_f64(v: f64) -> String {
+    if v == 0.0 {
+        return "0".to_owned();
+    }
+
+    // `{:e}` yields the shortest digits which round-trip (e.g., "-1.2345e-7")
+    let s = format!("{:e}", v);
+    let (mantissa, exp) = s.split_at(s.find('e').expect("never fails"));
+    let exp: i32 = exp[1..].parse().expect("never fails");
+    let (sign, mantissa) = if let Some(m) = mantissa.strip_prefix('-') {
+        ("-", m)
+    } else {
+        ("", mantissa)
+    };
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    let n = exp + 1;
+
+    let body = if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat(-n as usize), digits)
+    } else {
+        let exp_sign = if n - 1 < 0 { "-" } else { "+" };
+        if k == 1 {
+            format!("{}e{}{}", digits, exp_sign, (n - 1).abs())
+        } else {
+            format!(
+                "{}.{}e{}{}",
+                &digits[..1],
+                &digits[1..],
+                exp_sign,
+                (n - 1).abs()
+            )
+        }
+    };
+    format!("{}{}", sign, body)
+}
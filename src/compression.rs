@@ -0,0 +1,56 @@
+use flate2;
+use std::io::{Read, Write};
+use zstd;
+
+use {Error, Result};
+
+/// Compression algorithms applied to readers and writers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compression {
+    /// No compression.
+    None,
+
+    /// gzip compression.
+    ///
+    /// When decompressing, all the members of a multi-member input
+    /// (e.g., concatenated `.gz` files or the output of `pigz`) are read.
+    Gzip,
+
+    /// Zstandard compression.
+    Zstd,
+}
+impl Compression {
+    pub(crate) fn wrap_reader<'a, R>(self, reader: R) -> Result<Box<dyn Read + 'a>>
+    where
+        R: Read + 'a,
+    {
+        Ok(match self {
+            Compression::None => Box::new(reader),
+            Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+            Compression::Zstd => Box::new(track!(zstd::Decoder::new(reader).map_err(Error::from))?),
+        })
+    }
+
+    pub(crate) fn write_with<W, F>(self, mut writer: W, f: F) -> Result<()>
+    where
+        W: Write,
+        F: FnOnce(&mut dyn Write) -> Result<()>,
+    {
+        match self {
+            Compression::None => track!(f(&mut writer)),
+            Compression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(writer, flate2::Compression::default());
+                track!(f(&mut encoder))?;
+                track!(encoder.finish().map_err(Error::from))?;
+                Ok(())
+            }
+            Compression::Zstd => {
+                let mut encoder = track!(zstd::Encoder::new(writer, 0).map_err(Error::from))?;
+                track!(f(&mut encoder))?;
+                track!(encoder.finish().map_err(Error::from))?;
+                Ok(())
+            }
+        }
+    }
+}
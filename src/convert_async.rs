@@ -0,0 +1,172 @@
+//! Asynchronous file I/O backed by `tokio::fs`.
+//!
+//! The functions in this module read (or write) the whole file asynchronously,
+//! and then deserialize (or have serialized) the bytes with the corresponding synchronous function,
+//! so the parsing itself runs on the calling task.
+//!
+//! This crate is written in Rust 2015, so the functions return `impl Future` instead of being `async fn`.
+//! The futures must be polled within a Tokio runtime, because `tokio::fs` delegates
+//! the file operations to the blocking thread pool of the runtime.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate serde;
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serdeconv;
+//! extern crate tokio;
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Config {
+//!     name: String,
+//!     port: u16,
+//! }
+//!
+//! # fn main() {
+//! let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//! let path = std::env::temp_dir().join("serdeconv_doctest_async.toml");
+//! let config = Config { name: "foo".to_owned(), port: 8080 };
+//!
+//! runtime.block_on(serdeconv::to_toml_file_async(&config, &path)).unwrap();
+//! let loaded: Config = runtime.block_on(serdeconv::from_toml_file_async(&path)).unwrap();
+//! assert_eq!(loaded, config);
+//!
+//! let missing = path.with_extension("missing");
+//! let error = runtime.block_on(serdeconv::from_json_file_async::<Config, _>(missing)).unwrap_err();
+//! assert!(error.is_io());
+//! # }
+//! ```
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::io;
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio;
+
+use {from_json_slice, from_msgpack_slice, from_toml_slice};
+use {to_json_vec, to_msgpack_vec, to_toml_vec};
+use {Error, Result};
+
+/// Asynchronously reads the JSON file and converts it to a value of `T` type.
+///
+/// This function is available only if the `tokio` feature is enabled.
+pub fn from_json_file_async<T, P>(path: P) -> impl Future<Output = Result<T>>
+where
+    T: for<'a> Deserialize<'a>,
+    P: AsRef<Path>,
+{
+    FileFuture::io(tokio::fs::read(path), |bytes: Vec<u8>| {
+        track!(from_json_slice(&bytes))
+    })
+}
+
+/// Asynchronously reads the TOML file and converts it to a value of `T` type.
+///
+/// This function is available only if the `tokio` feature is enabled.
+pub fn from_toml_file_async<T, P>(path: P) -> impl Future<Output = Result<T>>
+where
+    T: for<'a> Deserialize<'a>,
+    P: AsRef<Path>,
+{
+    FileFuture::io(tokio::fs::read(path), |bytes: Vec<u8>| {
+        track!(from_toml_slice(&bytes))
+    })
+}
+
+/// Asynchronously reads the MessagePack file and converts it to a value of `T` type.
+///
+/// This function is available only if the `tokio` feature is enabled.
+pub fn from_msgpack_file_async<T, P>(path: P) -> impl Future<Output = Result<T>>
+where
+    T: for<'a> Deserialize<'a>,
+    P: AsRef<Path>,
+{
+    FileFuture::io(tokio::fs::read(path), |bytes: Vec<u8>| {
+        track!(from_msgpack_slice(&bytes))
+    })
+}
+
+/// Converts the value to a JSON string and asynchronously writes it to the speficied file.
+///
+/// The value is serialized before this function returns, so the returned future does not borrow `value`.
+///
+/// This function is available only if the `tokio` feature is enabled.
+pub fn to_json_file_async<T, P>(value: &T, path: P) -> impl Future<Output = Result<()>>
+where
+    T: ?Sized + Serialize,
+    P: AsRef<Path>,
+{
+    write_file(track!(to_json_vec(value)), path)
+}
+
+/// Converts the value to a TOML string and asynchronously writes it to the speficied file.
+///
+/// The value is serialized before this function returns, so the returned future does not borrow `value`.
+///
+/// This function is available only if the `tokio` feature is enabled.
+pub fn to_toml_file_async<T, P>(value: &T, path: P) -> impl Future<Output = Result<()>>
+where
+    T: ?Sized + Serialize,
+    P: AsRef<Path>,
+{
+    write_file(track!(to_toml_vec(value)), path)
+}
+
+/// Converts the value to MessagePack bytes and asynchronously writes it to the speficied file.
+///
+/// The value is serialized before this function returns, so the returned future does not borrow `value`.
+///
+/// This function is available only if the `tokio` feature is enabled.
+pub fn to_msgpack_file_async<T, P>(value: &T, path: P) -> impl Future<Output = Result<()>>
+where
+    T: ?Sized + Serialize,
+    P: AsRef<Path>,
+{
+    write_file(track!(to_msgpack_vec(value)), path)
+}
+
+fn write_file<P>(bytes: Result<Vec<u8>>, path: P) -> impl Future<Output = Result<()>>
+where
+    P: AsRef<Path>,
+{
+    match bytes {
+        Ok(bytes) => FileFuture::io(tokio::fs::write(path, bytes), Ok),
+        Err(e) => FileFuture::Failed(Some(e)),
+    }
+}
+
+/// A future that completes an I/O future and then applies `F` to its output.
+enum FileFuture<IO, F> {
+    Io(Pin<Box<IO>>, Option<F>),
+    Failed(Option<Error>),
+}
+impl<IO, F> FileFuture<IO, F> {
+    fn io(future: IO, f: F) -> Self {
+        FileFuture::Io(Box::pin(future), Some(f))
+    }
+}
+impl<IO, F, T, U> Future for FileFuture<IO, F>
+where
+    IO: Future<Output = io::Result<T>>,
+    F: FnOnce(T) -> Result<U> + Unpin,
+{
+    type Output = Result<U>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        match *self.get_mut() {
+            FileFuture::Io(ref mut future, ref mut f) => {
+                let output = match future.as_mut().poll(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(output) => output,
+                };
+                let f = f.take().expect("polled after completion");
+                Poll::Ready(track!(output.map_err(Error::from)).and_then(f))
+            }
+            FileFuture::Failed(ref mut e) => {
+                Poll::Ready(Err(e.take().expect("polled after completion")))
+            }
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use bson;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use {Error, Result};
+
+/// Converts from the BSON file to a value of `T` type.
+pub fn from_bson_file<T, P>(path: P) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    P: AsRef<Path>,
+{
+    let f = track!(File::open(path).map_err(Error::from))?;
+    track!(from_bson_reader(f))
+}
+
+/// Reads a BSON bytes from the reader and converts it to a value of `T` type.
+pub fn from_bson_reader<T, R>(reader: R) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    R: Read,
+{
+    let value = track!(bson::from_reader(reader).map_err(Error::from))?;
+    Ok(value)
+}
+
+/// Converts from the BSON bytes to a value of `T` type.
+///
+/// BSON documents must have a document (i.e., struct or map) at the root.
+///
+/// BSON-specific types such as `bson::oid::ObjectId` and `bson::DateTime` implement
+/// `Serialize` and `Deserialize`, so they can be used as field types of structs.
+/// They are encoded as the native BSON types (i.e., ObjectId and UTC datetime)
+/// rather than strings or integers.
+///
+/// # Examples
+///
+/// ```
+/// extern crate bson;
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// use bson::oid::ObjectId;
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct Foo {
+///     #[serde(rename = "_id")]
+///     id: ObjectId,
+///     bar: String,
+/// }
+///
+/// # fn main() {
+/// let foo = Foo { id: ObjectId::new(), bar: "aaa".to_owned() };
+/// let bytes = serdeconv::to_bson_vec(&foo).unwrap();
+/// assert_eq!(serdeconv::from_bson_slice::<Foo>(&bytes).unwrap(), foo);
+/// # }
+/// ```
+pub fn from_bson_slice<'a, T>(bytes: &'a [u8]) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    let value = track!(bson::from_slice(bytes).map_err(Error::from))?;
+    Ok(value)
+}
+
+/// Converts the value to a BSON bytes and writes it to the speficied file.
+pub fn to_bson_file<T, P>(value: &T, path: P) -> Result<()>
+where
+    T: ?Sized + Serialize,
+    P: AsRef<Path>,
+{
+    let f = track!(File::create(path).map_err(Error::from))?;
+    track!(to_bson_writer(value, f))
+}
+
+/// Converts the value to a BSON bytes and writes it to the writer.
+pub fn to_bson_writer<T, W>(value: &T, mut writer: W) -> Result<()>
+where
+    T: ?Sized + Serialize,
+    W: Write,
+{
+    let bytes = track!(to_bson_vec(value))?;
+    track!(writer.write_all(&bytes).map_err(Error::from))?;
+    Ok(())
+}
+
+/// Converts the value to a BSON bytes.
+pub fn to_bson_vec<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: ?Sized + Serialize,
+{
+    let bytes = track!(bson::to_vec(&value).map_err(Error::from))?;
+    Ok(bytes)
+}
@@ -0,0 +1,114 @@
+use csv;
+use serde::Serialize;
+use serde_json::{self, Value};
+use std::io::Write;
+
+use {Error, ErrorKind, Result};
+
+/// Writes the rows to the writer as CSV, arranging the fields in the columns named by `headers`.
+///
+/// Each row is serialized to a map from the field names to the values
+/// (i.e., `T` must be a struct or a map), and the values are written in the order of `headers`
+/// rather than in the order of the fields.
+/// The header row is written first if `write_headers` is `true`.
+///
+/// A field missing in a row (e.g., an optional field skipped by `#[serde(skip_serializing_if = ...)]`)
+/// and a `None` value are written as an empty field.
+/// Strings, numbers and booleans are written as is, but nested values (e.g., sequences and structs)
+/// cannot be represented in CSV and result in an `ErrorKind::Invalid` error.
+/// A field whose name is not in `headers` also results in an `ErrorKind::Invalid` error.
+///
+/// This function is available only if the `csv` feature is enabled.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Serialize)]
+/// struct Row {
+///     name: &'static str,
+///     age: u32,
+///     email: Option<&'static str>,
+/// }
+///
+/// # fn main() {
+/// let rows = vec![
+///     Row { name: "foo", age: 20, email: Some("foo@example.com") },
+///     Row { name: "bar, baz", age: 30, email: None },
+/// ];
+///
+/// let mut csv = Vec::new();
+/// serdeconv::to_csv_writer_with_headers(&mut csv, &["age", "name", "email"], &rows, true).unwrap();
+/// assert_eq!(
+///     String::from_utf8(csv).unwrap(),
+///     "age,name,email\n20,foo,foo@example.com\n30,\"bar, baz\",\n"
+/// );
+///
+/// let mut csv = Vec::new();
+/// serdeconv::to_csv_writer_with_headers(&mut csv, &["name", "age", "email"], &rows, false).unwrap();
+/// assert_eq!(String::from_utf8(csv).unwrap(), "foo,20,foo@example.com\n\"bar, baz\",30,\n");
+///
+/// // `email` is not among the headers
+/// let error = serdeconv::to_csv_writer_with_headers(Vec::new(), &["name", "age"], &rows, true)
+///     .unwrap_err();
+/// assert!(error.is_invalid());
+/// assert!(error.to_string().contains("Unknown column: \"email\""));
+/// # }
+/// ```
+pub fn to_csv_writer_with_headers<T, W, I>(
+    writer: W,
+    headers: &[&str],
+    rows: I,
+    write_headers: bool,
+) -> Result<()>
+where
+    T: Serialize,
+    W: Write,
+    I: IntoIterator<Item = T>,
+{
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(writer);
+    if write_headers {
+        track!(writer.write_record(headers).map_err(Error::from))?;
+    }
+
+    let mut record = Vec::with_capacity(headers.len());
+    for (i, row) in rows.into_iter().enumerate() {
+        let row = track!(serde_json::to_value(row).map_err(Error::from), "row={}", i)?;
+        let mut fields = match row {
+            Value::Object(fields) => fields,
+            _ => track_panic!(
+                ErrorKind::Invalid,
+                "A row must be a struct or a map: row={}",
+                i
+            ),
+        };
+
+        record.clear();
+        for header in headers {
+            let field = match fields.remove(*header) {
+                None | Some(Value::Null) => String::new(),
+                Some(Value::String(s)) => s,
+                Some(v @ Value::Number(_)) | Some(v @ Value::Bool(_)) => v.to_string(),
+                Some(_) => track_panic!(
+                    ErrorKind::Invalid,
+                    "A nested value cannot be written as a CSV field: row={}, column={:?}",
+                    i,
+                    header
+                ),
+            };
+            record.push(field);
+        }
+        if let Some(name) = fields.keys().next() {
+            track_panic!(ErrorKind::Invalid, "Unknown column: {:?} (row={})", name, i);
+        }
+        track!(writer.write_record(&record).map_err(Error::from))?;
+    }
+    track!(writer.flush().map_err(Error::from))?;
+    Ok(())
+}
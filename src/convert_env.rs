@@ -0,0 +1,657 @@
+//! Conversions from `.env` files and environment variables.
+//!
+//! # Key nesting
+//!
+//! Keys are lowercased and split by `__` (double underscore) into nested tables.
+//! For example, `SERVER__PORT=8080` is deserialized as `{"server": {"port": "8080"}}`,
+//! so it can be read into the same struct as the TOML document `[server]\nport = 8080`.
+//! A key cannot be both a value and a table (e.g., `SERVER=x` and `SERVER__PORT=8080`),
+//! nor be defined twice (including the definitions that differ only in case, e.g., `PORT` and `port`).
+//! `from_env_str` rejects such keys, whereas `from_env_vars` rejects them only if `T` consumes them.
+//!
+//! # Values
+//!
+//! All values are strings, and they are converted to the types requested by `T` when deserializing:
+//!
+//! - Integers and floats are parsed from their textual forms (e.g., `8080`, `0.5`).
+//! - Booleans are `true` or `false` (case-insensitive).
+//! - Sequences are comma-separated lists (e.g., `HOSTS=foo,bar`); an empty value is an empty sequence.
+//! - Options are `None` if the value is empty, `Some` otherwise (missing keys are `None` as usual).
+//! - Unit enum variants are given by their names.
+//!
+//! # Syntax and escaping
+//!
+//! The following rules apply to `.env` files (they do not apply to `from_env_vars`):
+//!
+//! - Each line is `KEY=VALUE`, optionally prefixed with `export `.
+//! - Empty lines and lines starting with `#` are ignored.
+//! - Unquoted values are trimmed, and a `#` preceded by whitespace starts a comment.
+//! - Single-quoted values (`'...'`) are taken literally; they cannot contain `'`.
+//! - Double-quoted values (`"..."`) support the escapes `\n`, `\r`, `\t`, `\"` and `\\`;
+//!   other backslashes are kept as is.
+//! - Quoted values must be closed on the same line, and only a comment may follow them.
+//!
+//! Variable expansion (e.g., `${HOME}`) is not performed.
+use serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+use serde::{Deserialize, Deserializer};
+use serde_json::{self, Map, Value};
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::env;
+use std::vec;
+
+use {Error, ErrorKind, Result};
+
+const KEY_SEPARATOR: &str = "__";
+
+/// Converts from the `.env` formatted string to a value of `T` type.
+///
+/// See the [module documentation](index.html) for the key-nesting convention and the escaping rules.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Debug, PartialEq, Deserialize)]
+/// struct Config {
+///     name: String,
+///     greeting: String,
+///     pattern: String,
+///     debug: bool,
+///     server: Server,
+/// }
+///
+/// #[derive(Debug, PartialEq, Deserialize)]
+/// struct Server {
+///     port: u16,
+///     hosts: Vec<String>,
+///     timeout: Option<f64>,
+/// }
+///
+/// # fn main() {
+/// let env = r#"
+/// ## Application settings
+/// NAME=foo bar  # trailing comment
+/// export GREETING="hello\n\"world\" # not a comment"
+/// PATTERN='C:\path\*'
+/// DEBUG=true
+///
+/// SERVER__PORT=8080
+/// SERVER__HOSTS=localhost,example.com
+/// SERVER__TIMEOUT=
+/// "#;
+///
+/// let config: Config = serdeconv::from_env_str(env).unwrap();
+/// assert_eq!(config.name, "foo bar");
+/// assert_eq!(config.greeting, "hello\n\"world\" # not a comment");
+/// assert_eq!(config.pattern, "C:\\path\\*");
+/// assert!(config.debug);
+/// assert_eq!(config.server, Server {
+///     port: 8080,
+///     hosts: vec!["localhost".to_owned(), "example.com".to_owned()],
+///     timeout: None,
+/// });
+///
+/// // Malformed lines are reported with their line numbers.
+/// let error = serdeconv::from_env_str::<Config>("NAME=foo\nDEBUG\n").unwrap_err();
+/// assert!(error.is_invalid());
+/// assert!(error.to_string().contains("line=2"));
+///
+/// let error = serdeconv::from_env_str::<Config>("NAME=\"foo\n").unwrap_err();
+/// assert!(error.is_invalid());
+/// # }
+/// ```
+pub fn from_env_str<T>(text: &str) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let mut root = Map::new();
+    for (i, line) in text.lines().enumerate() {
+        let n = i + 1;
+        if let Some((key, value)) = track!(parse_line(line), "line={}", n)? {
+            if let Err((path, reason)) = insert_value(&mut root, key, value) {
+                track_panic!(ErrorKind::Invalid, "{}: {:?} (line={})", reason, path, n);
+            }
+        }
+    }
+    track!(from_env_value(Value::Object(root), &Unusable::new()))
+}
+
+/// Converts from the environment variables whose names start with `prefix` to a value of `T` type.
+///
+/// The prefix is stripped from the names, and then the same key-nesting convention as `from_env_str` is applied.
+/// The prefix is matched case-sensitively,
+/// and variables without the prefix (and variables whose names are not valid Unicode) are ignored.
+///
+/// Since the rest of the names are case-folded, `HTTP_PROXY` and `http_proxy` are mapped to the same key `http_proxy`.
+/// Unlike `from_env_str`, conflicting variables do not fail the conversion by themselves,
+/// because the environment usually contains many variables unrelated to `T`.
+/// Instead, a key is regarded as unusable if it is defined by several variables with different values
+/// (e.g., `HTTP_PROXY` and `http_proxy`), it is both a value and a table (e.g., `X=1` and `X__Y=2`),
+/// or its value is not valid Unicode.
+///
+/// # Errors
+///
+/// If `T` consumes an unusable key, an error of kind `ErrorKind::Invalid` is returned.
+/// Unusable keys that `T` does not consume (including the ones ignored as unknown fields) are harmless.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// use std::env;
+///
+/// #[derive(Debug, PartialEq, Deserialize)]
+/// struct Config {
+///     name: String,
+///     server: Server,
+/// }
+///
+/// #[derive(Debug, PartialEq, Deserialize)]
+/// struct Server {
+///     port: u16,
+/// }
+///
+/// # fn main() {
+/// env::set_var("SERDECONV_DOCTEST_NAME", "foo");
+/// env::set_var("SERDECONV_DOCTEST_SERVER__PORT", "8080");
+///
+/// let config: Config = serdeconv::from_env_vars("SERDECONV_DOCTEST_").unwrap();
+/// assert_eq!(config, Config {
+///     name: "foo".to_owned(),
+///     server: Server { port: 8080 },
+/// });
+///
+/// // Conflicting variables that `Config` does not consume are ignored.
+/// env::set_var("SERDECONV_DOCTEST_PROXY", "http://foo");
+/// env::set_var("SERDECONV_DOCTEST_proxy", "http://bar");
+/// env::set_var("SERDECONV_DOCTEST_UNRELATED", "1");
+/// env::set_var("SERDECONV_DOCTEST_UNRELATED__VALUE", "2");
+/// let config: Config = serdeconv::from_env_vars("SERDECONV_DOCTEST_").unwrap();
+/// assert_eq!(config.server.port, 8080);
+///
+/// #[derive(Deserialize)]
+/// struct Empty {}
+/// assert!(serdeconv::from_env_vars::<Empty>("").is_ok());
+///
+/// // ... but they are reported if consumed.
+/// env::set_var("SERDECONV_DOCTEST_name", "bar");
+/// let error = serdeconv::from_env_vars::<Config>("SERDECONV_DOCTEST_").unwrap_err();
+/// assert!(error.is_invalid());
+/// assert!(error.to_string().contains("Ambiguous variables"));
+/// # }
+/// ```
+pub fn from_env_vars<T>(prefix: &str) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let mut vars = BTreeMap::new();
+    let mut unusable = Unusable::new();
+    for (name, value) in env::vars_os() {
+        let name = match name.into_string() {
+            Ok(name) => name,
+            Err(_) => continue,
+        };
+        if !name.starts_with(prefix) {
+            continue;
+        }
+        let key = name[prefix.len()..].to_lowercase();
+        let value = match value.into_string() {
+            Ok(value) => value,
+            Err(_) => {
+                unusable.insert(key.clone(), format!("Non-Unicode value of {:?}", name));
+                String::new()
+            }
+        };
+        match vars.entry(key) {
+            Entry::Vacant(e) => {
+                e.insert((name, value));
+            }
+            Entry::Occupied(e) => {
+                if e.get().1 != value {
+                    let reason = format!("Ambiguous variables {:?} and {:?}", e.get().0, name);
+                    unusable.insert(e.key().clone(), reason);
+                }
+            }
+        }
+    }
+
+    let mut root = Map::new();
+    for (key, (_, value)) in vars {
+        if let Err((path, reason)) = insert_value(&mut root, &key, value) {
+            unusable.entry(path).or_insert_with(|| reason.to_owned());
+        }
+    }
+    track!(from_env_value(Value::Object(root), &unusable))
+}
+
+fn from_env_value<T>(value: Value, unusable: &Unusable) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let deserializer = EnvDeserializer {
+        value,
+        path: String::new(),
+        unusable,
+    };
+    let value = track!(T::deserialize(deserializer).map_err(Error::from))?;
+    Ok(value)
+}
+
+fn parse_line(line: &str) -> Result<Option<(&str, String)>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let line = line
+        .strip_prefix("export ")
+        .map_or(line, |line| line.trim_start());
+
+    let eq = track_assert_some!(line.find('='), ErrorKind::Invalid, "Missing '='");
+    let key = line[..eq].trim();
+    track_assert!(!key.is_empty(), ErrorKind::Invalid, "Empty key");
+    track_assert!(
+        !key.contains(char::is_whitespace),
+        ErrorKind::Invalid,
+        "Whitespace in key: {:?}",
+        key
+    );
+
+    let rest = line[eq + 1..].trim_start();
+    let (value, remaining) = if let Some(quoted) = rest.strip_prefix('\'') {
+        let end = track_assert_some!(
+            quoted.find('\''),
+            ErrorKind::Invalid,
+            "Unterminated single-quoted value"
+        );
+        (quoted[..end].to_owned(), &quoted[end + 1..])
+    } else if let Some(quoted) = rest.strip_prefix('"') {
+        track!(parse_double_quoted(quoted))?
+    } else {
+        let end = rest
+            .char_indices()
+            .find(|&(i, c)| c == '#' && i > 0 && rest[..i].ends_with(char::is_whitespace))
+            .map_or(rest.len(), |(i, _)| i);
+        (rest[..end].trim_end().to_owned(), "")
+    };
+
+    let remaining = remaining.trim_start();
+    track_assert!(
+        remaining.is_empty() || remaining.starts_with('#'),
+        ErrorKind::Invalid,
+        "Unexpected characters after the quoted value: {:?}",
+        remaining
+    );
+    Ok(Some((key, value)))
+}
+
+fn parse_double_quoted(s: &str) -> Result<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = s.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((value, &s[i + 1..])),
+            '\\' => match chars.next() {
+                Some((_, 'n')) => value.push('\n'),
+                Some((_, 'r')) => value.push('\r'),
+                Some((_, 't')) => value.push('\t'),
+                Some((_, '"')) => value.push('"'),
+                Some((_, '\\')) => value.push('\\'),
+                Some((_, c)) => {
+                    value.push('\\');
+                    value.push(c);
+                }
+                None => break,
+            },
+            c => value.push(c),
+        }
+    }
+    track_panic!(ErrorKind::Invalid, "Unterminated double-quoted value");
+}
+
+/// Inserts the value into the env map, returning the conflicting key (joined by `KEY_SEPARATOR`)
+/// and the reason if it cannot be inserted.
+fn insert_value(
+    root: &mut Map<String, Value>,
+    key: &str,
+    value: String,
+) -> ::std::result::Result<(), (String, &'static str)> {
+    let key = key.to_lowercase();
+    let segments = key.split(KEY_SEPARATOR).collect::<Vec<_>>();
+    if segments.iter().any(|s| s.is_empty()) {
+        return Err((key.clone(), "Empty key segment"));
+    }
+
+    let (last, parents) = segments.split_last().expect("never fails");
+    let mut table = root;
+    for (i, segment) in parents.iter().enumerate() {
+        let entry = table
+            .entry((*segment).to_owned())
+            .or_insert_with(|| Value::Object(Map::new()));
+        table = match *entry {
+            Value::Object(ref mut table) => table,
+            _ => {
+                let path = segments[..=i].join(KEY_SEPARATOR);
+                return Err((path, "Both a value and a table"));
+            }
+        };
+    }
+    match table.get(*last) {
+        None => {}
+        Some(&Value::Object(_)) => return Err((key.clone(), "Both a value and a table")),
+        Some(_) => return Err((key.clone(), "Duplicate key")),
+    }
+    table.insert((*last).to_owned(), Value::String(value));
+    Ok(())
+}
+
+/// Keys (joined by `KEY_SEPARATOR`) that cannot be deserialized, with the reasons.
+type Unusable = BTreeMap<String, String>;
+
+/// A deserializer that converts the string leaves of an env map to the requested types.
+struct EnvDeserializer<'a> {
+    value: Value,
+    path: String,
+    unusable: &'a Unusable,
+}
+
+macro_rules! parse_value {
+    ($($method:ident => $visit:ident,)*) => {
+        $(fn $method<V: Visitor<'de>>(
+            self,
+            visitor: V,
+        ) -> ::std::result::Result<V::Value, Self::Error> {
+            match self.value {
+                Value::String(ref s) => match s.trim().parse() {
+                    Ok(v) => visitor.$visit(v),
+                    Err(_) => Err(de::Error::invalid_value(de::Unexpected::Str(s), &visitor)),
+                },
+                value => value.deserialize_any(visitor),
+            }
+        })*
+    };
+}
+
+macro_rules! forward_value {
+    ($($method:ident)*) => {
+        $(fn $method<V: Visitor<'de>>(
+            self,
+            visitor: V,
+        ) -> ::std::result::Result<V::Value, Self::Error> {
+            self.value.$method(visitor)
+        })*
+    };
+}
+
+impl<'de, 'a> Deserializer<'de> for EnvDeserializer<'a> {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Object(map) => visitor.visit_map(MapAccess::new(map, self.path, self.unusable)),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    parse_value! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        if let Value::String(ref s) = self.value {
+            let s = s.trim();
+            if s.eq_ignore_ascii_case("true") {
+                return visitor.visit_bool(true);
+            } else if s.eq_ignore_ascii_case("false") {
+                return visitor.visit_bool(false);
+            }
+        }
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            Value::String(ref s) if s.is_empty() => visitor.visit_none(),
+            value => visitor.visit_some(EnvDeserializer { value, ..self }),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        match self.value {
+            Value::String(s) => {
+                let items = if s.trim().is_empty() {
+                    Vec::new()
+                } else {
+                    s.split(',')
+                        .map(|item| Value::String(item.trim().to_owned()))
+                        .collect()
+                };
+                visitor.visit_seq(SeqAccess {
+                    items: items.into_iter(),
+                    unusable: self.unusable,
+                })
+            }
+            value => value.deserialize_seq(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _: usize,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: usize,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Object(map) => visitor.visit_map(MapAccess::new(map, self.path, self.unusable)),
+            value => value.deserialize_map(visitor),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        match self.value {
+            Value::String(s) => visitor.visit_enum(s.into_deserializer()),
+            value => value.deserialize_enum(name, variants, visitor),
+        }
+    }
+
+    forward_value! {
+        deserialize_char deserialize_str deserialize_string deserialize_bytes
+        deserialize_byte_buf deserialize_unit deserialize_identifier deserialize_ignored_any
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        self.value.deserialize_unit_struct(name, visitor)
+    }
+}
+
+struct SeqAccess<'a> {
+    items: vec::IntoIter<Value>,
+    unusable: &'a Unusable,
+}
+impl<'de, 'a> de::SeqAccess<'de> for SeqAccess<'a> {
+    type Error = serde_json::Error;
+
+    fn next_element_seed<T>(
+        &mut self,
+        seed: T,
+    ) -> ::std::result::Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.items.next() {
+            Some(value) => seed
+                .deserialize(EnvDeserializer {
+                    value,
+                    path: String::new(),
+                    unusable: self.unusable,
+                })
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+struct MapAccess<'a> {
+    entries: ::serde_json::map::IntoIter,
+    value: Option<(String, Value)>,
+    path: String,
+    unusable: &'a Unusable,
+}
+impl<'a> MapAccess<'a> {
+    fn new(map: Map<String, Value>, path: String, unusable: &'a Unusable) -> Self {
+        MapAccess {
+            entries: map.into_iter(),
+            value: None,
+            path,
+            unusable,
+        }
+    }
+}
+impl<'de, 'a> de::MapAccess<'de> for MapAccess<'a> {
+    type Error = serde_json::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> ::std::result::Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.entries.next() {
+            Some((k, v)) => {
+                let path = if self.path.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{}{}{}", self.path, KEY_SEPARATOR, k)
+                };
+                self.value = Some((path, v));
+                seed.deserialize(Value::String(k)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> ::std::result::Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let (path, value) = self
+            .value
+            .take()
+            .unwrap_or_else(|| (String::new(), Value::Null));
+        if let Some(reason) = self.unusable.get(&path) {
+            return seed.deserialize(UnusableDeserializer {
+                path: &path,
+                reason,
+            });
+        }
+        seed.deserialize(EnvDeserializer {
+            value,
+            path,
+            unusable: self.unusable,
+        })
+    }
+}
+
+/// A deserializer for an unusable key, which fails unless the value is ignored.
+struct UnusableDeserializer<'a> {
+    path: &'a str,
+    reason: &'a str,
+}
+impl<'de, 'a> Deserializer<'de> for UnusableDeserializer<'a> {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(
+        self,
+        _: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        Err(de::Error::custom(format_args!(
+            "{}: {:?}",
+            self.reason, self.path
+        )))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier
+    }
+}
@@ -1,10 +1,40 @@
+#[cfg(feature = "erased-serde")]
+use erased_serde;
+#[cfg(feature = "mmap")]
+use memmap2;
+use serde::de;
+use serde::ser::{self, Serializer};
 use serde::{Deserialize, Serialize};
 use serde_json;
-use std::fs::File;
-use std::io::{Read, Write};
+#[cfg(feature = "raw_value")]
+use serde_json::value::RawValue;
+#[cfg(feature = "serde_path_to_error")]
+use serde_path_to_error;
+use std::borrow::Cow;
+use std::cell::RefCell;
+#[cfg(feature = "raw_value")]
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, Read, Write};
+use std::marker::PhantomData;
 use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use trackable::error::ErrorKindExt;
 
-use {Error, Result};
+use finite::check_finite_floats;
+use options::JsonOptions;
+#[cfg(feature = "raw_value")]
+use patch::{parse_index, parse_pointer};
+use stringify::StringifyKeys;
+use traits::FromJsonValidated;
+use util::{skip_bom, strip_bom, strip_bom_str, write_file_atomic, CountingWriter};
+#[cfg(feature = "compression")]
+use Compression;
+use {Error, ErrorKind, Result};
 
 /// Converts from the JSON file to a value of `T` type.
 pub fn from_json_file<T, P>(path: P) -> Result<T>
@@ -16,31 +46,970 @@ where
     track!(from_json_reader(f))
 }
 
+/// Converts from the JSON file to a value of `T` type,
+/// showing the offending line of the file in the error message if the file is malformed.
+///
+/// On a syntax (or data) error, the file is read again to extract the line at which the error occurred,
+/// and the line is added to the error history with a caret under the column, e.g.:
+///
+/// ```text
+/// 3 |   "baz": 123,
+///   |             ^
+/// ```
+///
+/// Use `from_json_file` if the extra read on error is undesirable.
+///
+/// # Examples
+///
+/// ```
+/// let path = std::env::temp_dir().join("serdeconv_doctest_from_json_file_verbose.json");
+/// std::fs::write(&path, "{\n  \"foo\": 1,\n  \"bar\" 2\n}").unwrap();
+///
+/// let error = serdeconv::from_json_file_verbose::<serdeconv::Value, _>(&path).unwrap_err();
+/// assert!(error.is_invalid());
+/// assert!(error.to_string().contains("3 |   \"bar\" 2\n  |         ^"));
+/// ```
+pub fn from_json_file_verbose<T, P>(path: P) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let e = match from_json_file(path) {
+        Ok(value) => return Ok(value),
+        Err(e) => e,
+    };
+    let position = e
+        .concrete_cause::<serde_json::Error>()
+        .filter(|c| !c.is_io() && c.line() > 0)
+        .map(|c| (c.line(), c.column()));
+    let snippet = position.and_then(|(line, column)| {
+        let json = fs::read_to_string(path).ok()?;
+        let text = json.lines().nth(line - 1)?;
+        Some(format_snippet(text, line, column))
+    });
+    match snippet {
+        Some(snippet) => Err(track!(e, "\n{}", snippet)),
+        None => Err(track!(e)),
+    }
+}
+
+/// Formats the line with a caret under the (1-based byte) column.
+fn format_snippet(text: &str, line: usize, column: usize) -> String {
+    let number = line.to_string();
+    let caret_indent: String = text
+        .char_indices()
+        .take_while(|&(i, _)| i + 1 < column)
+        .map(|(_, c)| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    format!(
+        "{} | {}\n{} | {}^",
+        number,
+        text,
+        " ".repeat(number.len()),
+        caret_indent
+    )
+}
+
+/// Converts from the JSON file to a value of `T` type.
+///
+/// If the file does not exist, `T::default()` is returned instead.
+/// Other I/O errors and parse errors are propagated as usual.
+pub fn from_json_file_or_default<T, P>(path: P) -> Result<T>
+where
+    T: for<'a> Deserialize<'a> + Default,
+    P: AsRef<Path>,
+{
+    match from_json_file(path) {
+        Err(ref e) if e.is_not_found() => Ok(T::default()),
+        result => track!(result),
+    }
+}
+
 /// Reads a JSON string from the reader and converts it to a value of `T` type.
+///
+/// A leading UTF-8 BOM (`EF BB BF`) is skipped.
+///
+/// # Borrowing
+///
+/// The bytes read from the reader are discarded once they are consumed,
+/// so `T` cannot borrow from them (i.e., `T` must be an owned type such as `String`, not `&str`).
+/// This is the case even if the reader is backed by a slice (e.g., `Cursor<&[u8]>`),
+/// as `Read` provides no way to borrow the underlying data.
+/// To deserialize a value that borrows from the input, read the input into a buffer
+/// and use `from_json_slice` (or `from_json_str`) instead:
+///
+/// ```
+/// use std::io::Read;
+///
+/// let mut reader = std::io::Cursor::new(br#"{"foo": "bar"}"#);
+/// let mut buf = Vec::new();
+/// reader.read_to_end(&mut buf).unwrap();
+///
+/// let map: std::collections::HashMap<&str, &str> = serdeconv::from_json_slice(&buf).unwrap();
+/// assert_eq!(map["foo"], "bar");
+/// ```
 pub fn from_json_reader<T, R>(reader: R) -> Result<T>
 where
     T: for<'a> Deserialize<'a>,
     R: Read,
 {
+    let reader = track!(skip_bom(reader))?;
     let value = track!(serde_json::from_reader(reader).map_err(Error::from))?;
     Ok(value)
 }
 
+/// Reads exactly one JSON value from the reader and converts it to a value of `T` type.
+///
+/// Unlike `from_json_reader`, this does not read the reader until EOF.
+/// The bytes following the value (if any) are left in the reader,
+/// so that it can be used to read multiple concatenated values one by one.
+///
+/// The reader must implement `BufRead` because a JSON number can only be terminated
+/// by peeking the next byte, which must then be put back.
+/// Wrap a plain `Read` in a `std::io::BufReader` and keep using the same `BufReader`
+/// for subsequent reads.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Read;
+///
+/// let mut reader = &b"{\"foo\": 1} 2[3]rest"[..];
+/// let v: serdeconv::Value = serdeconv::from_json_reader_one(&mut reader).unwrap();
+/// assert_eq!(serdeconv::to_json_string(&v).unwrap(), r#"{"foo":1}"#);
+/// let v: u8 = serdeconv::from_json_reader_one(&mut reader).unwrap();
+/// assert_eq!(v, 2);
+/// let v: Vec<u8> = serdeconv::from_json_reader_one(&mut reader).unwrap();
+/// assert_eq!(v, [3]);
+///
+/// let mut rest = String::new();
+/// reader.read_to_string(&mut rest).unwrap();
+/// assert_eq!(rest, "rest");
+/// ```
+pub fn from_json_reader_one<T, R>(reader: &mut R) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    R: BufRead,
+{
+    let mut reader = PeekingReader {
+        inner: reader,
+        first: None,
+        pending: false,
+    };
+    let value = {
+        let mut deserializer = serde_json::Deserializer::from_reader(&mut reader);
+        track!(T::deserialize(&mut deserializer).map_err(Error::from))?
+    };
+
+    // Only a number needs to peek the byte following it; the other values end with their last byte.
+    let is_number = reader
+        .first
+        .is_some_and(|b| b == b'-' || b.is_ascii_digit());
+    if reader.pending && !is_number {
+        reader.inner.consume(1);
+    }
+    Ok(value)
+}
+
+/// Reads a JSON array from the reader and converts its elements to values of `T` type,
+/// invoking `on_record` with the number of the elements converted so far after each element.
+///
+/// The elements are converted one by one while the array is being read,
+/// so the callback can be used to report the progress of reading a huge array
+/// (e.g., to update a progress bar).
+/// See `from_ndjson_reader_with_progress` for NDJSON inputs.
+///
+/// # Examples
+///
+/// ```
+/// let mut counts = Vec::new();
+/// let values: Vec<u32> =
+///     serdeconv::from_json_array_reader_with_progress(&b"[1, 2, 3]"[..], |n| counts.push(n))
+///         .unwrap();
+/// assert_eq!(values, [1, 2, 3]);
+/// assert_eq!(counts, [1, 2, 3]);
+///
+/// let mut counts = Vec::new();
+/// let result = serdeconv::from_json_array_reader_with_progress::<u32, _, _>(
+///     &b"[1, \"two\", 3]"[..],
+///     |n| counts.push(n),
+/// );
+/// assert!(result.unwrap_err().is_invalid());
+/// assert_eq!(counts, [1]);
+/// ```
+pub fn from_json_array_reader_with_progress<T, R, F>(reader: R, on_record: F) -> Result<Vec<T>>
+where
+    T: for<'a> Deserialize<'a>,
+    R: Read,
+    F: FnMut(usize),
+{
+    let reader = track!(skip_bom(reader))?;
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let visitor = ProgressSeqVisitor {
+        on_record,
+        _value: PhantomData,
+    };
+    let values =
+        track!(de::Deserializer::deserialize_seq(&mut deserializer, visitor).map_err(Error::from))?;
+    track!(deserializer.end().map_err(Error::from))?;
+    Ok(values)
+}
+
+/// A visitor that collects the elements of a sequence, reporting the number of the collected ones.
+struct ProgressSeqVisitor<T, F> {
+    on_record: F,
+    _value: PhantomData<fn() -> T>,
+}
+impl<'de, T, F> de::Visitor<'de> for ProgressSeqVisitor<T, F>
+where
+    T: Deserialize<'de>,
+    F: FnMut(usize),
+{
+    type Value = Vec<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an array")
+    }
+
+    fn visit_seq<A>(mut self, mut seq: A) -> ::std::result::Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+            (self.on_record)(values.len());
+        }
+        Ok(values)
+    }
+}
+
+/// A reader that yields one byte at a time and consumes each byte only when the next one is requested.
+struct PeekingReader<'a, R: 'a> {
+    inner: &'a mut R,
+    first: Option<u8>,
+    pending: bool,
+}
+impl<'a, R: BufRead> Read for PeekingReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.pending {
+            self.inner.consume(1);
+            self.pending = false;
+        }
+        let b = match self.inner.fill_buf()?.first() {
+            None => return Ok(0),
+            Some(&b) => b,
+        };
+        if self.first.is_none() && !b.is_ascii_whitespace() {
+            self.first = Some(b);
+        }
+        buf[0] = b;
+        self.pending = true;
+        Ok(1)
+    }
+}
+
+/// Reads a JSON string from the reader and converts it to a value of `T` type,
+/// rejecting inputs in which arrays and objects are nested deeper than `max_depth`.
+///
+/// See the documentation of `from_json_slice_depth_limited` for details.
+pub fn from_json_reader_depth_limited<T, R>(reader: R, max_depth: usize) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    R: Read,
+{
+    track!(JsonOptions::new().max_depth(max_depth).from_reader(reader))
+}
+
+/// Reads a JSON string of at most `max_bytes` bytes from the reader and
+/// converts it to a value of `T` type.
+///
+/// If the reader yields more than `max_bytes` bytes,
+/// an `ErrorKind::Invalid` error is returned instead of silently truncating the input.
+///
+/// # Examples
+///
+/// ```
+/// let value: Vec<u8> = serdeconv::from_json_reader_size_limited(&b"[1, 2]"[..], 6).unwrap();
+/// assert_eq!(value, [1, 2]);
+///
+/// let error = serdeconv::from_json_reader_size_limited::<Vec<u8>, _>(&b"[1, 2]"[..], 5).unwrap_err();
+/// assert!(error.is_invalid());
+/// ```
+pub fn from_json_reader_size_limited<T, R>(reader: R, max_bytes: usize) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    R: Read,
+{
+    track!(JsonOptions::new().max_bytes(max_bytes).from_reader(reader))
+}
+
+/// Reads a JSON string from the reader and converts it to a value of `T` type,
+/// using the underlying `serde_json::Deserializer` configured according to `options`.
+///
+/// # Examples
+///
+/// ```
+/// use serdeconv::JsonDeserializeOptions;
+///
+/// let options = JsonDeserializeOptions::new().allow_trailing_characters(true);
+/// let value: Vec<u8> = serdeconv::from_json_reader_with(&b"[1, 2] [3]"[..], &options).unwrap();
+/// assert_eq!(value, [1, 2]);
+///
+/// let options = JsonDeserializeOptions::new();
+/// assert!(serdeconv::from_json_reader_with::<Vec<u8>, _>(&b"[1, 2] [3]"[..], &options).is_err());
+/// ```
+pub fn from_json_reader_with<T, R>(reader: R, options: &JsonDeserializeOptions) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    R: Read,
+{
+    let reader = track!(skip_bom(reader))?;
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    #[cfg(feature = "unbounded_depth")]
+    {
+        if options.disable_recursion_limit {
+            deserializer.disable_recursion_limit();
+        }
+    }
+    let value = track!(T::deserialize(&mut deserializer).map_err(Error::from))?;
+    if !options.allow_trailing_characters {
+        track!(deserializer.end().map_err(Error::from))?;
+    }
+    Ok(value)
+}
+
+/// Options for configuring the JSON deserializer used by `from_json_reader_with`.
+///
+/// The default value behaves in the same way as `from_json_reader`.
+#[derive(Debug, Clone, Default)]
+pub struct JsonDeserializeOptions {
+    allow_trailing_characters: bool,
+    #[cfg(feature = "unbounded_depth")]
+    disable_recursion_limit: bool,
+}
+impl JsonDeserializeOptions {
+    /// Makes a new `JsonDeserializeOptions` instance with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether to ignore the characters following the first JSON value (default: `false`).
+    ///
+    /// If `false`, the input must not contain anything except whitespace after the value.
+    pub fn allow_trailing_characters(mut self, allow: bool) -> Self {
+        self.allow_trailing_characters = allow;
+        self
+    }
+
+    /// Sets whether to disable the recursion limit (128 levels) of the deserializer (default: `false`).
+    ///
+    /// Note that deeply nested inputs may overflow the stack if the limit is disabled.
+    #[cfg(feature = "unbounded_depth")]
+    pub fn disable_recursion_limit(mut self, disable: bool) -> Self {
+        self.disable_recursion_limit = disable;
+        self
+    }
+}
+
 /// Converts from the JSON string to a value of `T` type.
+///
+/// A leading UTF-8 BOM (`U+FEFF`) is skipped.
+/// If an object has duplicate keys, the last value of the key is taken
+/// (use `from_json_str_no_dup_keys` to reject such objects).
+///
+/// If the `raw_value` feature is enabled, `T` may contain `serde_json::value::RawValue` fields
+/// (`Box<RawValue>` or `&RawValue` borrowing from `json`), which hold the JSON text of the field as is,
+/// so that it can be forwarded without being parsed into a value and re-encoded.
+/// See `passthrough_json` for an example.
 pub fn from_json_str<'a, T>(json: &'a str) -> Result<T>
 where
     T: Deserialize<'a>,
 {
-    let value = track!(serde_json::from_str(json).map_err(Error::from))?;
+    let value = track!(serde_json::from_str(strip_bom_str(json)).map_err(Error::from))?;
     Ok(value)
 }
 
+/// Converts from the JSON string, which is either borrowed or owned, to a value of `T` type.
+///
+/// If `json` is `Cow::Borrowed`, this is the same as `from_json_str`,
+/// so `T` may borrow from the input (e.g., `&'a str` fields) for the lifetime `'a`.
+///
+/// If `json` is `Cow::Owned`, the string is dropped when this function returns,
+/// so nothing can be borrowed from it: the input is read without borrowing
+/// and `T` receives owned data only.
+/// Types that can hold either (e.g., `Cow<'a, str>` fields) become owned values, and
+/// types that can only borrow (e.g., `&'a str` fields) result in an `ErrorKind::Invalid` error.
+/// The output is still typed as `T` with the lifetime `'a`, so generic code
+/// can handle both cases with the same signature.
+///
+/// # Examples
+///
+/// ```
+/// use std::borrow::Cow;
+///
+/// let json = String::from(r#"["foo", "bar"]"#);
+/// let borrowed: Vec<&str> = serdeconv::from_json_cow(Cow::Borrowed(json.as_str())).unwrap();
+/// assert_eq!(borrowed, ["foo", "bar"]);
+///
+/// let owned: Vec<Cow<str>> = serdeconv::from_json_cow(Cow::Owned(json.clone())).unwrap();
+/// assert_eq!(owned, ["foo", "bar"]);
+/// assert!(owned.iter().all(|s| matches!(s, Cow::Owned(_))));
+///
+/// // Nothing can be borrowed from an owned string.
+/// let error = serdeconv::from_json_cow::<Vec<&str>>(Cow::Owned(json)).unwrap_err();
+/// assert!(error.is_invalid());
+/// ```
+pub fn from_json_cow<'a, T>(json: Cow<'a, str>) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    match json {
+        Cow::Borrowed(json) => track!(from_json_str(json)),
+        Cow::Owned(json) => {
+            let mut de = serde_json::Deserializer::from_reader(strip_bom_str(&json).as_bytes());
+            let value = track!(T::deserialize(&mut de).map_err(Error::from))?;
+            track!(de.end().map_err(Error::from))?;
+            Ok(value)
+        }
+    }
+}
+
+/// Converts from the JSON string to a value of `T` type, rejecting objects with duplicate keys.
+///
+/// By default, `from_json_str` (and the other JSON functions) silently take the last value
+/// of a key that appears more than once in an object (e.g., `{"a": 1, "a": 2}` is regarded as `{"a": 2}`).
+/// That may mask mistakes in configuration files, and may make different parsers
+/// interpret the same document differently.
+/// This function returns an `ErrorKind::Invalid` error naming the duplicated key
+/// (and its position) instead.
+///
+/// The input is scanned for duplicate keys before being converted to `T`,
+/// so this is slower than `from_json_str`.
+///
+/// Note that TOML does not allow duplicate keys, so the TOML functions always reject them.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// let json = r#"{"a": 1, "b": {"c": 2, "c": 3}}"#;
+/// let error = serdeconv::from_json_str_no_dup_keys::<serdeconv::Value>(json).unwrap_err();
+/// assert!(error.is_invalid());
+/// assert!(error.to_string().contains(r#"Duplicate key: "c""#));
+///
+/// // `from_json_str` takes the last value
+/// let value: HashMap<String, HashMap<String, u32>> =
+///     serdeconv::from_json_str(r#"{"b": {"c": 2, "c": 3}}"#).unwrap();
+/// assert_eq!(value["b"]["c"], 3);
+///
+/// // TOML rejects duplicate keys
+/// assert!(serdeconv::from_toml_str::<serdeconv::Value>("a = 1\na = 2").is_err());
+/// ```
+pub fn from_json_str_no_dup_keys<'a, T>(json: &'a str) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    track!(serde_json::from_str::<DupKeyChecker>(strip_bom_str(json)).map_err(Error::from))?;
+    track!(from_json_str(json))
+}
+
+/// A value that fails to deserialize if any object in the input has duplicate keys.
+struct DupKeyChecker;
+impl<'de> Deserialize<'de> for DupKeyChecker {
+    fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(DupKeyChecker)
+    }
+}
+impl<'de> de::Visitor<'de> for DupKeyChecker {
+    type Value = DupKeyChecker;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "any JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, _: bool) -> ::std::result::Result<Self::Value, E> {
+        Ok(DupKeyChecker)
+    }
+
+    fn visit_i64<E: de::Error>(self, _: i64) -> ::std::result::Result<Self::Value, E> {
+        Ok(DupKeyChecker)
+    }
+
+    fn visit_u64<E: de::Error>(self, _: u64) -> ::std::result::Result<Self::Value, E> {
+        Ok(DupKeyChecker)
+    }
+
+    fn visit_f64<E: de::Error>(self, _: f64) -> ::std::result::Result<Self::Value, E> {
+        Ok(DupKeyChecker)
+    }
+
+    fn visit_str<E: de::Error>(self, _: &str) -> ::std::result::Result<Self::Value, E> {
+        Ok(DupKeyChecker)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> ::std::result::Result<Self::Value, E> {
+        Ok(DupKeyChecker)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> ::std::result::Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        while seq.next_element::<DupKeyChecker>()?.is_some() {}
+        Ok(DupKeyChecker)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> ::std::result::Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        let mut keys = HashSet::new();
+        while let Some(key) = map.next_key::<String>()? {
+            map.next_value::<DupKeyChecker>()?;
+            if !keys.insert(key.clone()) {
+                return Err(de::Error::custom(format!("Duplicate key: {:?}", key)));
+            }
+        }
+        Ok(DupKeyChecker)
+    }
+}
+
+/// Extracts the JSON text of the value at `pointer` (a [JSON Pointer]) from the JSON string.
+///
+/// The returned string is a slice of `json` (including any whitespace within the value),
+/// and only the ancestors of the value are inspected to find it,
+/// so this is an efficient way to forward an opaque subtree of a document.
+///
+/// If the value at `pointer` does not exist, an `ErrorKind::Invalid` error is returned.
+///
+/// This function is available only if the `raw_value` feature is enabled.
+///
+/// [JSON Pointer]: https://tools.ietf.org/html/rfc6901
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serde_json;
+/// extern crate serdeconv;
+///
+/// use serde_json::value::RawValue;
+///
+/// #[derive(Deserialize, Serialize)]
+/// struct Envelope<'a> {
+///     id: u32,
+///     #[serde(borrow)]
+///     payload: &'a RawValue,
+///     metadata: Box<RawValue>,
+/// }
+///
+/// # fn main() {
+/// let json = r#"{"id": 1, "payload": {"b": [1, 2.50], "a": null}, "metadata": "x"}"#;
+/// let envelope: Envelope = serdeconv::from_json_str(json).unwrap();
+/// assert_eq!(envelope.id, 1);
+/// assert_eq!(envelope.payload.get(), r#"{"b": [1, 2.50], "a": null}"#);
+/// assert_eq!(
+///     serdeconv::to_json_string(&envelope).unwrap(),
+///     r#"{"id":1,"payload":{"b": [1, 2.50], "a": null},"metadata":"x"}"#
+/// );
+///
+/// assert_eq!(serdeconv::passthrough_json(json, "/payload/b").unwrap(), "[1, 2.50]");
+/// assert_eq!(serdeconv::passthrough_json(json, "/payload/b/1").unwrap(), "2.50");
+/// assert_eq!(serdeconv::passthrough_json(json, "").unwrap(), json);
+/// assert!(serdeconv::passthrough_json(json, "/payload/c").unwrap_err().is_invalid());
+///
+/// // Array indices with leading zeros are rejected (as by `apply_json_patch`).
+/// assert!(serdeconv::passthrough_json(r#"[1, 2]"#, "/01").unwrap_err().is_invalid());
+/// # }
+/// ```
+#[cfg(feature = "raw_value")]
+pub fn passthrough_json<'a>(json: &'a str, pointer: &str) -> Result<&'a str> {
+    let mut raw: &RawValue = track!(from_json_str(json))?;
+    for token in track!(parse_pointer(pointer))? {
+        raw = if raw.get().starts_with('[') {
+            let mut array: Vec<&RawValue> =
+                track!(serde_json::from_str(raw.get()).map_err(Error::from))?;
+            let i = track!(parse_index(&token, array.len()))?;
+            array.swap_remove(i)
+        } else {
+            let child = if raw.get().starts_with('{') {
+                let mut object: HashMap<String, &RawValue> =
+                    track!(serde_json::from_str(raw.get()).map_err(Error::from))?;
+                object.remove(&token)
+            } else {
+                None
+            };
+            track_assert_some!(child, ErrorKind::Invalid, "No such member: {:?}", token)
+        };
+    }
+    Ok(raw.get())
+}
+
+/// Converts from the JSON string to a value of `T` type,
+/// turning integers out of the `i64`/`u64` range into strings.
+///
+/// Some producers emit large identifiers as unquoted numbers (e.g., `{"id": 18446744073709551616}`).
+/// By default, `serde_json` parses such numbers as `f64` and thus loses their digits.
+/// This function scans the raw JSON text beforehand,
+/// quotes every integer literal that fits neither `i64` nor `u64`,
+/// and then deserializes `T` from the resulting text.
+/// So the corresponding fields of `T` should be of a string type
+/// (or a type deserializable from a string).
+/// Integers within the range and floating point numbers are left as is.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Item {
+///     id: String,
+///     label: String,
+///     count: u64,
+///     ratio: f64,
+/// }
+///
+/// # fn main() {
+/// let json = r#"{"id": 123456789012345678901234567890, "label": "99999999999999999999",
+///                "count": 18446744073709551615, "ratio": 0.5}"#;
+/// assert!(serdeconv::from_json_str::<Item>(json).is_err());
+///
+/// let item: Item = serdeconv::from_json_str_lenient_numbers(json).unwrap();
+/// assert_eq!(item.id, "123456789012345678901234567890");
+/// assert_eq!(item.label, "99999999999999999999");
+/// assert_eq!(item.count, 18446744073709551615);
+/// assert_eq!(item.ratio, 0.5);
+///
+/// // Malformed numbers are rejected as by `from_json_str`.
+/// for json in &["[-]", "[1-2]", "[1+2]", r#"{"a": 12-34}"#, "[0123456789012345678901]"] {
+///     assert!(serdeconv::from_json_str_lenient_numbers::<serdeconv::Value>(json).is_err());
+/// }
+/// # }
+/// ```
+pub fn from_json_str_lenient_numbers<T>(json: &str) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let json = quote_big_integers(json);
+    track!(from_json_str(&json))
+}
+
+/// Quotes the integer literals out of the `i64`/`u64` range in the JSON text.
+///
+/// Malformed text is passed through as is, so that `serde_json` reports the error.
+fn quote_big_integers(json: &str) -> String {
+    let bytes = json.as_bytes();
+    let mut quoted = String::with_capacity(json.len());
+    let mut copied = 0;
+    let mut in_string = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if in_string {
+            match b {
+                b'\\' => i += 1,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            i += 1;
+        } else if b == b'"' {
+            in_string = true;
+            i += 1;
+        } else if b == b'-' || b.is_ascii_digit() {
+            let start = i;
+            while i < bytes.len()
+                && matches!(bytes[i], b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+            {
+                i += 1;
+            }
+            let literal = &json[start..i];
+            if is_json_integer(literal)
+                && literal.parse::<i64>().is_err()
+                && literal.parse::<u64>().is_err()
+            {
+                quoted.push_str(&json[copied..start]);
+                quoted.push('"');
+                quoted.push_str(literal);
+                quoted.push('"');
+                copied = i;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    quoted.push_str(&json[copied..]);
+    quoted
+}
+
+/// Returns `true` if the literal matches the JSON integer grammar (`-?(0|[1-9][0-9]*)`).
+fn is_json_integer(literal: &str) -> bool {
+    let digits = literal.strip_prefix('-').unwrap_or(literal).as_bytes();
+    match digits.split_first() {
+        Some((&b'0', rest)) => rest.is_empty(),
+        Some((first, rest)) => first.is_ascii_digit() && rest.iter().all(u8::is_ascii_digit),
+        None => false,
+    }
+}
+
+/// Converts the JSON value at the beginning of the string to a value of `T` type,
+/// and returns it together with the rest of the string.
+///
+/// This is useful for formats consisting of a JSON header followed by other data.
+/// The rest starts just after the value, so any whitespace following the value is included in it.
+/// Note that a top-level number must be followed by whitespace or a JSON delimiter (e.g., `[`),
+/// as it cannot be told where a number like `123abc` ends.
+/// A leading UTF-8 BOM (`U+FEFF`) is skipped.
+///
+/// # Examples
+///
+/// ```
+/// let input = r#"{"len": 5} hello"#;
+/// let (header, rest): (serdeconv::Value, &str) = serdeconv::from_json_str_partial(input).unwrap();
+/// assert_eq!(serdeconv::to_json_string(&header).unwrap(), r#"{"len":5}"#);
+/// assert_eq!(rest, " hello");
+/// assert_eq!(input.len() - rest.len(), 10);
+///
+/// let (value, rest): (Vec<u8>, &str) = serdeconv::from_json_str_partial("[1,2]").unwrap();
+/// assert_eq!(value, [1, 2]);
+/// assert_eq!(rest, "");
+/// ```
+pub fn from_json_str_partial<'a, T>(json: &'a str) -> Result<(T, &'a str)>
+where
+    T: Deserialize<'a>,
+{
+    let json = strip_bom_str(json);
+    let mut stream = serde_json::Deserializer::from_str(json).into_iter();
+    let value = track_assert_some!(stream.next(), ErrorKind::Invalid, "Empty input");
+    let value = track!(value.map_err(Error::from))?;
+    Ok((value, &json[stream.byte_offset()..]))
+}
+
+/// Converts from the JSON string to a value of `T` type, and then validates it.
+///
+/// If `T::validate` fails, the error is returned as an `ErrorKind::Invalid` error.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// use serdeconv::{ErrorKind, FromJsonValidated};
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Credentials {
+///     user: String,
+///     password: Option<String>,
+///     token: Option<String>,
+/// }
+/// impl FromJsonValidated for Credentials {
+///     fn validate(&self) -> serdeconv::Result<()> {
+///         if self.password.is_some() == self.token.is_some() {
+///             return Err(ErrorKind::Other.into());
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// # fn main() {
+/// let json = r#"{"user": "foo", "token": "bar"}"#;
+/// assert!(serdeconv::from_json_str_validated::<Credentials>(json).is_ok());
+///
+/// // Validation errors are reported as `ErrorKind::Invalid`
+/// let json = r#"{"user": "foo"}"#;
+/// let error = serdeconv::from_json_str_validated::<Credentials>(json).unwrap_err();
+/// assert_eq!(*error.kind(), ErrorKind::Invalid);
+/// # }
+/// ```
+pub fn from_json_str_validated<T>(json: &str) -> Result<T>
+where
+    T: FromJsonValidated,
+{
+    let value: T = track!(from_json_str(json))?;
+    track!(value
+        .validate()
+        .map_err(|e| Error::from(ErrorKind::Invalid.takes_over(e))))?;
+    Ok(value)
+}
+
+/// Converts from the JSON string to a value of `T` type.
+///
+/// If the string is empty or consists only of whitespace (as determined by `str::trim`),
+/// `T::default()` is returned instead.
+pub fn from_json_str_or_default<'a, T>(json: &'a str) -> Result<T>
+where
+    T: Deserialize<'a> + Default,
+{
+    if json.trim().is_empty() {
+        Ok(T::default())
+    } else {
+        track!(from_json_str(json))
+    }
+}
+
 /// Converts from the JSON bytes to a value of `T` type.
+///
+/// A leading UTF-8 BOM (`EF BB BF`) is skipped,
+/// as some editors on Windows prepend it to the files they save.
+///
+/// # Examples
+///
+/// ```
+/// let value: Vec<u8> = serdeconv::from_json_slice(b"\xEF\xBB\xBF[1, 2]").unwrap();
+/// assert_eq!(value, [1, 2]);
+/// ```
 pub fn from_json_slice<'a, T>(json: &'a [u8]) -> Result<T>
 where
     T: Deserialize<'a>,
 {
-    let value = track!(serde_json::from_slice(json).map_err(Error::from))?;
+    let value = track!(serde_json::from_slice(strip_bom(json)).map_err(Error::from))?;
+    Ok(value)
+}
+
+/// Converts from the JSON bytes to a value of `T` type, accepting anything byte-slice-like
+/// (e.g., `Vec<u8>`, `&[u8]`, arrays and `Box<[u8]>`).
+///
+/// This is the same as `from_json_slice(bytes.as_ref())`.
+/// Since `bytes` may be an owned buffer that is dropped when this function returns,
+/// `T` cannot borrow from the input; use `from_json_slice` to deserialize borrowing values.
+///
+/// # Examples
+///
+/// ```
+/// let bytes: Vec<u8> = b"[1, 2]".to_vec();
+/// let value: Vec<u8> = serdeconv::from_json_bytes(bytes).unwrap();
+/// assert_eq!(value, [1, 2]);
+/// ```
+pub fn from_json_bytes<T, B>(bytes: B) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    B: AsRef<[u8]>,
+{
+    track!(from_json_slice(bytes.as_ref()))
+}
+
+/// Converts from the JSON bytes to a value of `T` type,
+/// rejecting inputs in which arrays and objects are nested deeper than `max_depth`.
+///
+/// A top-level array or object has the depth `1`.
+/// If the limit is exceeded, an `ErrorKind::Invalid` error is returned before
+/// the deserialization starts, so a malicious input cannot overflow the stack.
+///
+/// Note that `serde_json` has its own fixed recursion limit (`128`) which is always applied,
+/// so specifying a value greater than `128` has no effect.
+///
+/// # Examples
+///
+/// ```
+/// let value: Vec<Vec<u8>> = serdeconv::from_json_slice_depth_limited(b"[[1], [2]]", 2).unwrap();
+/// assert_eq!(value, [[1], [2]]);
+///
+/// let error = serdeconv::from_json_slice_depth_limited::<Vec<Vec<u8>>>(b"[[1], [2]]", 1).unwrap_err();
+/// assert!(error.is_invalid());
+/// ```
+pub fn from_json_slice_depth_limited<'a, T>(json: &'a [u8], max_depth: usize) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    track!(JsonOptions::new().max_depth(max_depth).from_slice(json))
+}
+
+/// Converts from the JSON bytes to a value of `T` type, giving up if it takes longer than `timeout`.
+///
+/// The deserialization runs on a newly spawned thread, and if it does not finish within `timeout`,
+/// an `ErrorKind::Invalid` error ("timed out") is returned.
+/// This bounds the wall-clock time spent on a (possibly malicious) input,
+/// in addition to the limits of the size (`from_json_reader_size_limited`) and the depth (`from_json_slice_depth_limited`).
+///
+/// Note that a thread cannot be killed, so the abandoned thread keeps running until
+/// the deserialization finishes, consuming CPU time and memory in the background.
+/// Since the thread may outlive this call, the input is copied, and `T` must be owned and `Send`.
+///
+/// If the deserialization panics (e.g., in a custom `Deserialize` implementation),
+/// an `ErrorKind::Other` error is returned.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// use serde::Deserializer;
+/// use serdeconv::ErrorKind;
+/// use std::time::Duration;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Broken {
+///     #[serde(deserialize_with = "panic_on_deserialize")]
+///     value: u8,
+/// }
+///
+/// fn panic_on_deserialize<'de, D: Deserializer<'de>>(_: D) -> Result<u8, D::Error> {
+///     panic!("broken")
+/// }
+///
+/// # fn main() {
+/// let value: Vec<u8> = serdeconv::from_json_slice_timeout(b"[1, 2]", Duration::from_secs(10)).unwrap();
+/// assert_eq!(value, [1, 2]);
+///
+/// let error = serdeconv::from_json_slice_timeout::<Vec<u8>>(b"[1, 2", Duration::from_secs(10)).unwrap_err();
+/// assert!(error.is_invalid());
+///
+/// let json = br#"{"value": 1}"#;
+/// let error = serdeconv::from_json_slice_timeout::<Broken>(json, Duration::from_secs(10)).unwrap_err();
+/// assert_eq!(*error.kind(), ErrorKind::Other);
+/// # }
+/// ```
+pub fn from_json_slice_timeout<T>(json: &[u8], timeout: Duration) -> Result<T>
+where
+    T: for<'a> Deserialize<'a> + Send + 'static,
+{
+    let json = json.to_owned();
+    let (tx, rx) = mpsc::channel();
+    let spawned = thread::Builder::new()
+        .name("serdeconv-json-timeout".to_owned())
+        .spawn(move || {
+            let _ = tx.send(from_json_slice(&json));
+        });
+    track!(spawned.map_err(Error::from))?;
+    match rx.recv_timeout(timeout) {
+        Ok(result) => track!(result),
+        Err(mpsc::RecvTimeoutError::Timeout) => track_panic!(
+            ErrorKind::Invalid,
+            "Deserialization timed out: timeout={:?}",
+            timeout
+        ),
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            track_panic!(ErrorKind::Other, "Deserialization thread panicked")
+        }
+    }
+}
+
+/// Converts from the `serde_json::Value` to a value of `T` type.
+pub fn from_json_value<T>(json: serde_json::Value) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let value = track!(serde_json::from_value(json).map_err(Error::from))?;
     Ok(value)
 }
 
@@ -54,16 +1023,71 @@ where
     track!(to_json_writer(value, f))
 }
 
+/// Converts the value to a JSON string and writes it to the specified file atomically.
+///
+/// The output is first written to a temporary file in the same directory,
+/// which is flushed and synced to disk, and then renamed to `path`.
+/// Thus, a crash during the write never leaves a truncated file at `path`.
+/// If the serialization fails, the temporary file is removed.
+///
+/// Note that the atomicity relies on the underlying platform and file system:
+/// renaming is atomic on POSIX file systems, but may not be on some platforms
+/// (e.g., network file systems or Windows when the target file is opened by another process).
+pub fn to_json_file_atomic<T, P>(value: &T, path: P) -> Result<()>
+where
+    T: ?Sized + Serialize,
+    P: AsRef<Path>,
+{
+    track!(write_file_atomic(path.as_ref(), |f| track!(
+        to_json_writer(value, f)
+    )))
+}
+
 /// Converts the value to a JSON string and writes it to the writer.
+///
+/// See `to_json_string` for the handling of map keys that are not strings.
+///
+/// The serializer issues many small writes, so an unbuffered writer such as a `File` or a `TcpStream`
+/// should be wrapped in a `std::io::BufWriter` (for a large value, this can be orders of magnitude faster).
+///
+/// There is no separate fast path for `serde_json::Value`: the generic serializer is monomorphized for it,
+/// and `benches/json_value.rs` shows that a hand-written writer specialized for `Value`
+/// (which bypasses the `Serialize` machinery) is within the measurement noise of this function
+/// (about 6.2ms vs 6.3ms for an array of 100,000 small objects).
+/// To forward a JSON subtree without re-serializing it at all,
+/// keep it as a `serde_json::value::RawValue` (see `passthrough_json`, which requires the `raw_value` feature).
 pub fn to_json_writer<T, W>(value: &T, writer: W) -> Result<()>
 where
     T: ?Sized + Serialize,
     W: Write,
 {
-    track!(serde_json::to_writer(writer, value).map_err(Error::from))?;
+    track!(serde_json::to_writer(writer, value).map_err(serialize_error))?;
     Ok(())
 }
 
+/// Converts the value to a JSON string, writes it to the writer and flushes the writer.
+///
+/// Returns the number of bytes written.
+///
+/// # Examples
+///
+/// ```
+/// let mut buf = Vec::new();
+/// let size = serdeconv::to_json_writer_counted(&[1, 2, 3], &mut buf).unwrap();
+/// assert_eq!(size, 7);
+/// assert_eq!(buf, b"[1,2,3]");
+/// ```
+pub fn to_json_writer_counted<T, W>(value: &T, writer: W) -> Result<usize>
+where
+    T: ?Sized + Serialize,
+    W: Write,
+{
+    let mut writer = CountingWriter::new(writer);
+    track!(to_json_writer(value, &mut writer))?;
+    track!(writer.flush().map_err(Error::from))?;
+    Ok(writer.count())
+}
+
 /// Converts the value to a pretty printed JSON string and writes it to the writer.
 pub fn to_json_writer_pretty<T, W>(value: &T, writer: W) -> Result<()>
 where
@@ -74,12 +1098,403 @@ where
     Ok(())
 }
 
+/// Serializes the elements of the iterator as a JSON array and writes it to the writer.
+///
+/// The elements are written one by one as they are yielded from the iterator,
+/// so the whole sequence is never buffered in memory.
+///
+/// If an element fails to be serialized (or the writer fails), an error is returned
+/// and the output written so far is an incomplete JSON array which should be discarded.
+///
+/// # Examples
+///
+/// ```
+/// let mut buf = Vec::new();
+/// serdeconv::to_json_array_writer((0..3).map(|i| i * 10), &mut buf).unwrap();
+/// assert_eq!(buf, b"[0,10,20]");
+///
+/// let mut buf = Vec::new();
+/// serdeconv::to_json_array_writer_pretty(vec!["foo", "bar"], &mut buf).unwrap();
+/// assert_eq!(buf, &b"[\n  \"foo\",\n  \"bar\"\n]"[..]);
+/// ```
+pub fn to_json_array_writer<I, W>(iter: I, writer: W) -> Result<()>
+where
+    I: IntoIterator,
+    I::Item: Serialize,
+    W: Write,
+{
+    let array = IterArray(RefCell::new(Some(iter.into_iter())));
+    track!(
+        serde_json::to_writer(writer, &array).map_err(Error::from),
+        "The written JSON array is incomplete"
+    )?;
+    Ok(())
+}
+
+/// Serializes the elements of the iterator as a pretty printed JSON array and writes it to the writer.
+///
+/// See `to_json_array_writer` for details.
+pub fn to_json_array_writer_pretty<I, W>(iter: I, writer: W) -> Result<()>
+where
+    I: IntoIterator,
+    I::Item: Serialize,
+    W: Write,
+{
+    let array = IterArray(RefCell::new(Some(iter.into_iter())));
+    track!(
+        serde_json::to_writer_pretty(writer, &array).map_err(Error::from),
+        "The written JSON array is incomplete"
+    )?;
+    Ok(())
+}
+
+/// An adapter for serializing the elements of an iterator as a sequence without collecting them.
+struct IterArray<I>(RefCell<Option<I>>);
+impl<I> Serialize for IterArray<I>
+where
+    I: Iterator,
+    I::Item: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let iter = self.0.borrow_mut().take();
+        let iter =
+            iter.ok_or_else(|| ser::Error::custom("The iterator has already been consumed"))?;
+        serializer.collect_seq(iter)
+    }
+}
+
+/// Converts the value to a pretty printed JSON string formatted according to `format`
+/// and writes it to the writer.
+pub fn to_json_writer_pretty_with<T, W>(value: &T, writer: W, format: &JsonFormat) -> Result<()>
+where
+    T: ?Sized + Serialize,
+    W: Write,
+{
+    let options = JsonOptions::new().pretty(true).format(format.clone());
+    track!(options.to_writer(value, writer))
+}
+
 /// Converts the value to a JSON string.
+///
+/// JSON object keys must be strings. The keys of maps are converted to strings
+/// if they are strings, numbers, booleans, `char`s or unit variants (e.g., `HashMap<u32, _>` works as expected),
+/// but maps with other keys (e.g., `HashMap<SomeStruct, _>`) result in an `ErrorKind::Invalid` error
+/// whose message names the non-string map key.
+/// Use `to_json_string_stringify_keys` to convert such keys to strings instead.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// use std::collections::BTreeMap;
+///
+/// #[derive(PartialEq, Eq, PartialOrd, Ord, Serialize)]
+/// struct Point {
+///     x: u32,
+///     y: u32,
+/// }
+///
+/// # fn main() {
+/// let mut map = BTreeMap::new();
+/// map.insert(1u32, "foo");
+/// assert_eq!(serdeconv::to_json_string(&map).unwrap(), r#"{"1":"foo"}"#);
+///
+/// let mut map = BTreeMap::new();
+/// map.insert(Point { x: 1, y: 2 }, "foo");
+/// let error = serdeconv::to_json_string(&map).unwrap_err();
+/// assert!(error.is_invalid());
+/// assert!(error.to_string().contains("Non-string map key"));
+/// # }
+/// ```
 pub fn to_json_string<T>(value: &T) -> Result<String>
 where
     T: ?Sized + Serialize,
 {
-    let json = track!(serde_json::to_string(value).map_err(Error::from))?;
+    let json = track!(serde_json::to_string(value).map_err(serialize_error))?;
+    Ok(json)
+}
+
+/// Converts the value to a JSON string, converting every map key to a string.
+///
+/// Keys that `to_json_string` accepts are converted in the same way
+/// (e.g., the key `1u32` becomes `"1"`), and the other keys (e.g., structs, tuples or sequences)
+/// are converted to strings holding their compact JSON representation.
+///
+/// Note that the conversion is one-way: deserializing the output does not restore such keys.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// use std::collections::HashMap;
+///
+/// #[derive(PartialEq, Eq, Hash, Serialize)]
+/// struct Point {
+///     x: u32,
+///     y: u32,
+/// }
+///
+/// # fn main() {
+/// let mut map = HashMap::new();
+/// map.insert(Point { x: 1, y: 2 }, vec![(3, true)].into_iter().collect::<HashMap<_, _>>());
+/// assert_eq!(
+///     serdeconv::to_json_string_stringify_keys(&map).unwrap(),
+///     r#"{"{\"x\":1,\"y\":2}":{"3":true}}"#
+/// );
+/// # }
+/// ```
+pub fn to_json_string_stringify_keys<T>(value: &T) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    let json = track!(serde_json::to_string(&StringifyKeys(value)).map_err(Error::from))?;
+    Ok(json)
+}
+
+fn serialize_error(e: serde_json::Error) -> Error {
+    if e.to_string().starts_with("key must be a string") {
+        track!(
+            Error::from(e),
+            "Non-string map key: JSON object keys must be strings \
+             (use `to_json_string_stringify_keys` to convert them)"
+        )
+    } else {
+        Error::from(e)
+    }
+}
+
+/// Converts the value to a JSON bytes.
+pub fn to_json_vec<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: ?Sized + Serialize,
+{
+    let json = track!(serde_json::to_vec(value).map_err(serialize_error))?;
+    Ok(json)
+}
+
+/// Converts the value to a JSON string, failing if the value contains non-finite floats.
+///
+/// JSON cannot represent `NaN` and infinities, and `to_json_string` silently emits them as `null`.
+/// This function returns an `ErrorKind::Invalid` error indicating the path to
+/// the offending float instead. See also `check_finite_floats`.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(serdeconv::to_json_string(&[1.0, std::f64::NAN]).unwrap(), "[1.0,null]");
+///
+/// let error = serdeconv::to_json_string_checked(&[1.0, std::f64::NAN]).unwrap_err();
+/// assert!(error.is_invalid());
+/// ```
+pub fn to_json_string_checked<T>(value: &T) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    track!(check_finite_floats(value))?;
+    track!(to_json_string(value))
+}
+
+/// Converts the value to a JSON string consisting of ASCII characters only.
+///
+/// Every non-ASCII character in strings (and object keys) is escaped as `\uXXXX`,
+/// using a surrogate pair for a character outside the Basic Multilingual Plane.
+///
+/// # Examples
+///
+/// ```
+/// let json = serdeconv::to_json_string_ascii(&["caf\u{e9}", "e\u{301}", "\u{1F600}"]).unwrap();
+/// assert_eq!(json, r#"["caf\u00e9","e\u0301","\ud83d\ude00"]"#);
+///
+/// let value: Vec<String> = serdeconv::from_json_str(&json).unwrap();
+/// assert_eq!(value, ["caf\u{e9}", "e\u{301}", "\u{1F600}"]);
+/// ```
+pub fn to_json_string_ascii<T>(value: &T) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    let mut buf = Vec::new();
+    track!(to_json_writer_ascii(value, &mut buf))?;
+    Ok(String::from_utf8(buf).expect("never fails"))
+}
+
+/// Converts the value to a JSON string consisting of ASCII characters only and writes it to the writer.
+///
+/// See the documentation of `to_json_string_ascii` for details.
+pub fn to_json_writer_ascii<T, W>(value: &T, writer: W) -> Result<()>
+where
+    T: ?Sized + Serialize,
+    W: Write,
+{
+    let mut serializer = serde_json::Serializer::with_formatter(writer, AsciiFormatter);
+    track!(value.serialize(&mut serializer).map_err(Error::from))?;
+    Ok(())
+}
+
+/// A compact formatter which escapes all non-ASCII characters.
+struct AsciiFormatter;
+impl serde_json::ser::Formatter for AsciiFormatter {
+    fn write_string_fragment<W>(&mut self, writer: &mut W, fragment: &str) -> io::Result<()>
+    where
+        W: ?Sized + Write,
+    {
+        let mut start = 0;
+        for (i, c) in fragment.char_indices() {
+            if c.is_ascii() {
+                continue;
+            }
+            writer.write_all(&fragment.as_bytes()[start..i])?;
+            let mut units = [0; 2];
+            for unit in c.encode_utf16(&mut units) {
+                write!(writer, "\\u{:04x}", unit)?;
+            }
+            start = i + c.len_utf8();
+        }
+        writer.write_all(&fragment.as_bytes()[start..])
+    }
+}
+
+/// Converts the value to a JSON string that can be safely embedded in HTML (e.g., in a `<script>` element).
+///
+/// The characters `<`, `>` and `&` in strings (and object keys) are escaped as `\u003c`, `\u003e` and `\u0026`,
+/// so the output never contains `</script>` or `<!--` and cannot break out of the element.
+/// `U+2028` (LINE SEPARATOR) and `U+2029` (PARAGRAPH SEPARATOR) are also escaped,
+/// since they are line terminators in older JavaScript engines.
+/// The escaped output is still a valid JSON text denoting the same value.
+///
+/// # Examples
+///
+/// ```
+/// let json = serdeconv::to_json_string_html_safe(&["</script>", "a && b", "\u{2028}\u{2029}"]).unwrap();
+/// assert_eq!(
+///     json,
+///     r#"["\u003c/script\u003e","a \u0026\u0026 b","\u2028\u2029"]"#
+/// );
+///
+/// let value: Vec<String> = serdeconv::from_json_str(&json).unwrap();
+/// assert_eq!(value, ["</script>", "a && b", "\u{2028}\u{2029}"]);
+///
+/// assert_eq!(serdeconv::to_json_string(&"<&>").unwrap(), r#""<&>""#);
+/// ```
+///
+/// Raw JSON texts (e.g., `serde_json::value::RawValue` with the `raw_value` feature) are escaped as well:
+///
+/// ```
+/// # extern crate serde_json;
+/// # extern crate serdeconv;
+/// # fn main() {
+/// # #[cfg(feature = "raw_value")]
+/// # {
+/// use serde_json::value::RawValue;
+///
+/// let raw = RawValue::from_string(r#""</script><script>alert(1)</script>""#.to_owned()).unwrap();
+/// let json = serdeconv::to_json_string_html_safe(&vec![raw]).unwrap();
+/// assert_eq!(json, r#"["\u003c/script\u003e\u003cscript\u003ealert(1)\u003c/script\u003e"]"#);
+/// # }
+/// # }
+/// ```
+pub fn to_json_string_html_safe<T>(value: &T) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    let mut buf = Vec::new();
+    track!(to_json_writer_html_safe(value, &mut buf))?;
+    Ok(String::from_utf8(buf).expect("never fails"))
+}
+
+/// Converts the value to a JSON string that can be safely embedded in HTML and writes it to the writer.
+///
+/// See the documentation of `to_json_string_html_safe` for details.
+pub fn to_json_writer_html_safe<T, W>(value: &T, writer: W) -> Result<()>
+where
+    T: ?Sized + Serialize,
+    W: Write,
+{
+    let mut serializer = serde_json::Serializer::with_formatter(writer, HtmlSafeFormatter);
+    track!(value.serialize(&mut serializer).map_err(Error::from))?;
+    Ok(())
+}
+
+/// A compact formatter which escapes the characters that are unsafe to embed in HTML.
+struct HtmlSafeFormatter;
+impl HtmlSafeFormatter {
+    fn write_escaped<W>(writer: &mut W, fragment: &str) -> io::Result<()>
+    where
+        W: ?Sized + Write,
+    {
+        let mut start = 0;
+        for (i, c) in fragment.char_indices() {
+            if !matches!(c, '<' | '>' | '&' | '\u{2028}' | '\u{2029}') {
+                continue;
+            }
+            writer.write_all(&fragment.as_bytes()[start..i])?;
+            write!(writer, "\\u{:04x}", c as u32)?;
+            start = i + c.len_utf8();
+        }
+        writer.write_all(&fragment.as_bytes()[start..])
+    }
+}
+impl serde_json::ser::Formatter for HtmlSafeFormatter {
+    fn write_string_fragment<W>(&mut self, writer: &mut W, fragment: &str) -> io::Result<()>
+    where
+        W: ?Sized + Write,
+    {
+        Self::write_escaped(writer, fragment)
+    }
+
+    // In a valid JSON text, the escaped characters can only appear in strings,
+    // so escaping them in raw values (e.g., `RawValue`) keeps the text valid.
+    fn write_raw_fragment<W>(&mut self, writer: &mut W, fragment: &str) -> io::Result<()>
+    where
+        W: ?Sized + Write,
+    {
+        Self::write_escaped(writer, fragment)
+    }
+}
+
+/// Converts the value to a JSON string in which the keys of all objects are sorted.
+///
+/// The output is byte-stable regardless of the iteration order of maps (e.g., `HashMap`) in the value.
+///
+/// This requires two serialization passes: the value is first serialized to a `serde_json::Value`,
+/// the keys of the objects (including ones nested in other objects or arrays) are sorted recursively,
+/// and then the resulting tree is serialized to a string.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// let mut map = HashMap::new();
+/// map.insert("foo", 1);
+/// map.insert("bar", 2);
+/// map.insert("baz", 3);
+/// let json = serdeconv::to_json_string_sorted(&map).unwrap();
+/// assert_eq!(json, r#"{"bar":2,"baz":3,"foo":1}"#);
+/// ```
+pub fn to_json_string_sorted<T>(value: &T) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    track!(JsonOptions::new().sort_keys(true).to_string(value))
+}
+
+/// Converts the value to a `serde_json::Value`.
+pub fn to_json_value<T>(value: &T) -> Result<serde_json::Value>
+where
+    T: ?Sized + Serialize,
+{
+    let json = track!(serde_json::to_value(value).map_err(Error::from))?;
     Ok(json)
 }
 
@@ -91,3 +1506,237 @@ where
     let json = track!(serde_json::to_string_pretty(value).map_err(Error::from))?;
     Ok(json)
 }
+
+/// Converts the value to a pretty printed JSON bytes.
+pub fn to_json_vec_pretty<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: ?Sized + Serialize,
+{
+    let json = track!(serde_json::to_vec_pretty(value).map_err(Error::from))?;
+    Ok(json)
+}
+
+/// Indentation style used by `JsonFormat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonIndent {
+    /// Indents with the given number of spaces.
+    Spaces(usize),
+
+    /// Indents with a tab.
+    Tab,
+}
+impl fmt::Display for JsonIndent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            JsonIndent::Spaces(n) => write!(f, "{:1$}", "", n),
+            JsonIndent::Tab => write!(f, "\t"),
+        }
+    }
+}
+
+/// Options for controlling the layout of pretty printed JSON strings.
+///
+/// The default value produces the same output as `to_json_string_pretty`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonFormat {
+    /// Indentation style.
+    ///
+    /// The default value is `JsonIndent::Spaces(2)`.
+    pub indent: JsonIndent,
+
+    /// Whether a newline is appended to the end of the output.
+    ///
+    /// The default value is `false`.
+    pub trailing_newline: bool,
+}
+impl Default for JsonFormat {
+    fn default() -> Self {
+        JsonFormat {
+            indent: JsonIndent::Spaces(2),
+            trailing_newline: false,
+        }
+    }
+}
+
+/// Converts the value to a pretty printed JSON string formatted according to `format`.
+///
+/// # Examples
+///
+/// ```
+/// use serdeconv::{JsonFormat, JsonIndent};
+///
+/// let format = JsonFormat {
+///     indent: JsonIndent::Tab,
+///     trailing_newline: true,
+/// };
+/// let json = serdeconv::to_json_string_pretty_with(&[1, 2], &format).unwrap();
+/// assert_eq!(json, "[\n\t1,\n\t2\n]\n");
+/// ```
+pub fn to_json_string_pretty_with<T>(value: &T, format: &JsonFormat) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    let options = JsonOptions::new().pretty(true).format(format.clone());
+    track!(options.to_string(value))
+}
+
+/// Reads a JSON string from the compressed reader and converts it to a value of `T` type.
+///
+/// # Examples
+///
+/// ```
+/// use serdeconv::Compression;
+///
+/// let mut buf = Vec::new();
+/// serdeconv::to_json_writer_compressed(&[1, 2, 3], &mut buf, Compression::Gzip).unwrap();
+///
+/// let value: Vec<u8> =
+///     serdeconv::from_json_reader_compressed(&buf[..], Compression::Gzip).unwrap();
+/// assert_eq!(value, [1, 2, 3]);
+/// ```
+#[cfg(feature = "compression")]
+pub fn from_json_reader_compressed<T, R>(reader: R, compression: Compression) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    R: Read,
+{
+    let reader = track!(compression.wrap_reader(reader))?;
+    track!(from_json_reader(reader))
+}
+
+/// Converts the value to a JSON string and writes it to the writer with the given compression.
+#[cfg(feature = "compression")]
+pub fn to_json_writer_compressed<T, W>(value: &T, writer: W, compression: Compression) -> Result<()>
+where
+    T: ?Sized + Serialize,
+    W: Write,
+{
+    track!(compression.write_with(writer, |w| track!(to_json_writer(value, w))))
+}
+
+pub(crate) fn sort_json_value(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries = map.into_iter().collect::<Vec<_>>();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            serde_json::Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k, sort_json_value(v)))
+                    .collect(),
+            )
+        }
+        serde_json::Value::Array(values) => {
+            serde_json::Value::Array(values.into_iter().map(sort_json_value).collect())
+        }
+        value => value,
+    }
+}
+
+/// Converts the type-erased value to a JSON string and writes it to the type-erased writer.
+///
+/// This is useful when neither the type of the value nor the one of the writer can be named
+/// (e.g., in plugin code based on dynamic dispatch).
+///
+/// Note that the generic `to_json_writer` also accepts a `&mut dyn Write` as the writer.
+///
+/// # Examples
+///
+/// ```
+/// extern crate erased_serde;
+/// extern crate serdeconv;
+///
+/// use std::collections::BTreeMap;
+/// use std::io::Write;
+///
+/// # fn main() {
+/// let mut map = BTreeMap::new();
+/// map.insert("foo", 1);
+/// let value: &dyn erased_serde::Serialize = &map;
+///
+/// let mut buf = Vec::new();
+/// {
+///     let writer: &mut dyn Write = &mut buf;
+///     serdeconv::to_json_dyn_writer(value, writer).unwrap();
+/// }
+/// assert_eq!(buf, br#"{"foo":1}"#);
+/// # }
+/// ```
+#[cfg(feature = "erased-serde")]
+pub fn to_json_dyn_writer(
+    value: &dyn erased_serde::Serialize,
+    writer: &mut dyn Write,
+) -> Result<()> {
+    track!(to_json_writer(value, writer))
+}
+
+/// Converts from the JSON string to a value of `T` type,
+/// prepending the path of the failed field (e.g., `server.pool.max_connections`) to the error message.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Foo {
+///     server: Server,
+/// }
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Server {
+///     ports: Vec<u16>,
+/// }
+///
+/// # fn main() {
+/// let json = r#"{"server": {"ports": [80, "443"]}}"#;
+/// let error = serdeconv::from_json_str_with_path::<Foo>(json).unwrap_err();
+/// assert!(error.to_string().contains("server.ports[1]: invalid type"));
+/// # }
+/// ```
+#[cfg(feature = "serde_path_to_error")]
+pub fn from_json_str_with_path<T>(json: &str) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let value = track!(serde_path_to_error::deserialize(&mut deserializer).map_err(Error::from))?;
+    track!(deserializer.end().map_err(Error::from))?;
+    Ok(value)
+}
+
+/// Converts from the JSON bytes to a value of `T` type,
+/// prepending the path of the failed field to the error message.
+#[cfg(feature = "serde_path_to_error")]
+pub fn from_json_slice_with_path<T>(json: &[u8]) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let mut deserializer = serde_json::Deserializer::from_slice(json);
+    let value = track!(serde_path_to_error::deserialize(&mut deserializer).map_err(Error::from))?;
+    track!(deserializer.end().map_err(Error::from))?;
+    Ok(value)
+}
+
+/// Maps the JSON file into memory (read-only) and converts it to a value of `T` type.
+///
+/// Compared to `from_json_file`, this avoids copying the file content
+/// and lets the OS page it in lazily, which is beneficial for large data files.
+///
+/// # Safety
+///
+/// The file must not be modified (by this or any other process) while it is mapped,
+/// i.e., until this function returns. Otherwise the behavior is undefined.
+#[cfg(feature = "mmap")]
+pub unsafe fn from_json_mmap<T, P>(path: P) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    P: AsRef<Path>,
+{
+    let f = track!(File::open(path).map_err(Error::from))?;
+    let mmap = track!(memmap2::Mmap::map(&f).map_err(Error::from))?;
+    track!(from_json_slice(&mmap))
+}
@@ -0,0 +1,386 @@
+//! Conversions of JSONC (JSON with comments) documents, such as VS Code settings files.
+//!
+//! JSONC extends JSON with `// line comments`, `/* block comments */` and trailing commas
+//! in arrays and objects.
+//!
+//! For reading, the comments and trailing commas are blanked out (preserving the byte offsets,
+//! so the positions in error messages refer to the original input) and the result is parsed as JSON.
+//!
+//! For editing, `set_jsonc_value` rewrites only the bytes of the edited value,
+//! so the comments and the formatting of the rest of the document are preserved.
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+use serde_json;
+use std::fs;
+use std::path::Path;
+
+use {to_json_string, to_json_string_pretty, Error, ErrorKind, Result};
+
+/// Converts from the JSONC string to a value of `T` type.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Settings {
+///     #[serde(rename = "editor.tabSize")]
+///     tab_size: u8,
+///     #[serde(rename = "files.exclude")]
+///     files_exclude: Vec<String>,
+/// }
+///
+/// # fn main() {
+/// let jsonc = r#"{
+///     // Indentation
+///     "editor.tabSize": 4, /* spaces */
+///     "files.exclude": [
+///         "target", // build outputs
+///     ],
+/// }"#;
+/// let settings: Settings = serdeconv::from_jsonc_str(jsonc).unwrap();
+/// assert_eq!(settings.tab_size, 4);
+/// assert_eq!(settings.files_exclude, ["target"]);
+/// # }
+/// ```
+pub fn from_jsonc_str<T>(jsonc: &str) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let json = track!(strip_jsonc(jsonc))?.json;
+    let value = track!(serde_json::from_str(&json).map_err(Error::from))?;
+    Ok(value)
+}
+
+/// Converts from the JSONC file to a value of `T` type.
+pub fn from_jsonc_file<T, P>(path: P) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    P: AsRef<Path>,
+{
+    let jsonc = track!(fs::read_to_string(path).map_err(Error::from))?;
+    track!(from_jsonc_str(&jsonc))
+}
+
+/// Sets the value at `pointer` (a [JSON Pointer]) in the JSONC string, preserving the comments and formatting.
+///
+/// The edits are performed as follows:
+///
+/// - If the value at `pointer` exists, only its bytes are replaced by the compact JSON of `value`.
+///   Everything else (including comments inside the old value) is kept as is.
+/// - If the parent of `pointer` is an object without the member, the member is added after the last member
+///   (on a new line with the same indentation if the object spans multiple lines).
+///   Likewise, the token `-` appends an element to an array.
+///   The existing comments are kept as is.
+/// - If `pointer` is empty (i.e., the root), the whole document is reserialized as the pretty JSON of `value`,
+///   so all of the comments are lost.
+///
+/// If the input is malformed or the parent of `pointer` does not exist, an `ErrorKind::Invalid` error is returned.
+///
+/// [JSON Pointer]: https://tools.ietf.org/html/rfc6901
+///
+/// # Examples
+///
+/// ```
+/// let jsonc = r#"{
+///   // Indentation
+///   "editor.tabSize": 4,
+///   "files.exclude": ["target"], // build outputs
+/// }"#;
+///
+/// let jsonc = serdeconv::set_jsonc_value(jsonc, "/editor.tabSize", &2).unwrap();
+/// let jsonc = serdeconv::set_jsonc_value(&jsonc, "/files.exclude/-", &"node_modules").unwrap();
+/// let jsonc = serdeconv::set_jsonc_value(&jsonc, "/editor.wordWrap", &"on").unwrap();
+/// assert_eq!(
+///     jsonc,
+///     r#"{
+///   // Indentation
+///   "editor.tabSize": 2,
+///   "files.exclude": ["target", "node_modules"], // build outputs
+///   "editor.wordWrap": "on",
+/// }"#
+/// );
+///
+/// let result = serdeconv::set_jsonc_value(&jsonc, "/no/such/parent", &1);
+/// assert!(result.unwrap_err().is_invalid());
+/// ```
+pub fn set_jsonc_value<T>(jsonc: &str, pointer: &str, value: &T) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    let stripped = track!(strip_jsonc(jsonc))?;
+    track!(serde_json::from_str::<IgnoredAny>(&stripped.json).map_err(Error::from))?;
+    if pointer.is_empty() {
+        return track!(to_json_string_pretty(value));
+    }
+    track_assert!(
+        pointer.starts_with('/'),
+        ErrorKind::Invalid,
+        "Invalid JSON Pointer: {:?}",
+        pointer
+    );
+
+    let json = stripped.json.as_bytes();
+    let tokens = pointer[1..]
+        .split('/')
+        .map(|t| t.replace("~1", "/").replace("~0", "~"))
+        .collect::<Vec<_>>();
+    let (last, parents) = tokens.split_last().expect("never fails");
+    let mut parent = value_span(json, skip_whitespace(json, 0));
+    for token in parents {
+        let child = track!(children(json, parent))?
+            .into_iter()
+            .find(|c| c.matches(token));
+        parent = track_assert_some!(child, ErrorKind::Invalid, "No such member: {:?}", token).value;
+    }
+
+    let new_value = track!(to_json_string(value))?;
+    let siblings = track!(children(json, parent))?;
+    if let Some(child) = siblings.iter().find(|c| c.matches(last)) {
+        let mut output = jsonc[..child.value.0].to_owned();
+        output.push_str(&new_value);
+        output.push_str(&jsonc[child.value.1..]);
+        return Ok(output);
+    }
+
+    let entry = if json[parent.0] == b'{' {
+        format!("{}: {}", track!(to_json_string(last))?, new_value)
+    } else {
+        track_assert_eq!(last, "-", ErrorKind::Invalid, "No such element: {:?}", last);
+        new_value
+    };
+    Ok(insert_entry(
+        jsonc,
+        &stripped,
+        parent,
+        siblings.last(),
+        &entry,
+    ))
+}
+
+/// The results of blanking out the JSONC-specific syntax.
+///
+/// Both strings have the same length as the input.
+struct Stripped {
+    /// The input with the comments blanked out.
+    no_comments: String,
+
+    /// The input with the comments and trailing commas blanked out (i.e., a plain JSON text).
+    json: String,
+}
+
+fn strip_jsonc(jsonc: &str) -> Result<Stripped> {
+    let input = jsonc.as_bytes();
+    let mut no_comments = input.to_vec();
+    let mut pos = 0;
+    while pos < input.len() {
+        match (input[pos], input.get(pos + 1)) {
+            (b'"', _) => pos = string_end(input, pos),
+            (b'/', Some(b'/')) => {
+                while pos < input.len() && input[pos] != b'\n' {
+                    no_comments[pos] = b' ';
+                    pos += 1;
+                }
+            }
+            (b'/', Some(b'*')) => {
+                let start = pos;
+                let len = jsonc[pos + 2..].find("*/");
+                let len = track_assert_some!(
+                    len,
+                    ErrorKind::Invalid,
+                    "Unterminated block comment at byte {}",
+                    start
+                );
+                pos += len + 4;
+                for b in &mut no_comments[start..pos] {
+                    if *b != b'\n' {
+                        *b = b' ';
+                    }
+                }
+            }
+            _ => pos += 1,
+        }
+    }
+
+    let mut json = no_comments.clone();
+    let mut pos = 0;
+    while pos < no_comments.len() {
+        match no_comments[pos] {
+            b'"' => pos = string_end(&no_comments, pos),
+            b',' => {
+                let next = skip_whitespace(&no_comments, pos + 1);
+                if let Some(b'}') | Some(b']') = no_comments.get(next) {
+                    json[pos] = b' ';
+                }
+                pos += 1;
+            }
+            _ => pos += 1,
+        }
+    }
+
+    // Comments are blanked out byte by byte (keeping newlines), so the results remain valid UTF-8.
+    Ok(Stripped {
+        no_comments: String::from_utf8(no_comments).expect("never fails"),
+        json: String::from_utf8(json).expect("never fails"),
+    })
+}
+
+/// A member of an object or an element of an array.
+struct Child {
+    /// The key of the member, or the index of the element.
+    key: String,
+
+    /// The start position of the member (i.e., the key) or the element.
+    start: usize,
+
+    /// The byte range of the value.
+    value: (usize, usize),
+}
+impl Child {
+    fn matches(&self, token: &str) -> bool {
+        self.key == token
+    }
+}
+
+/// Returns the members (or elements) of the object (or array) spanning `span` in the valid JSON text.
+fn children(json: &[u8], span: (usize, usize)) -> Result<Vec<Child>> {
+    let is_object = match json[span.0] {
+        b'{' => true,
+        b'[' => false,
+        _ => track_panic!(ErrorKind::Invalid, "Not an object or array"),
+    };
+    let mut children = Vec::new();
+    let mut pos = skip_whitespace(json, span.0 + 1);
+    while pos < span.1 - 1 {
+        let start = pos;
+        let key = if is_object {
+            let end = string_end(json, pos);
+            let key = track!(serde_json::from_slice(&json[pos..end]).map_err(Error::from))?;
+            pos = skip_whitespace(json, end) + 1; // ':'
+            pos = skip_whitespace(json, pos);
+            key
+        } else {
+            children.len().to_string()
+        };
+        let value = value_span(json, pos);
+        children.push(Child { key, start, value });
+        pos = skip_whitespace(json, value.1);
+        if json[pos] == b',' {
+            pos = skip_whitespace(json, pos + 1);
+        }
+    }
+    Ok(children)
+}
+
+/// Inserts the entry as the last member (or element) of the object (or array) spanning `parent`.
+fn insert_entry(
+    jsonc: &str,
+    stripped: &Stripped,
+    parent: (usize, usize),
+    last: Option<&Child>,
+    entry: &str,
+) -> String {
+    let close = parent.1 - 1;
+    let line_start = jsonc[..close].rfind('\n').map_or(0, |i| i + 1);
+    let own_line = line_start > last.map_or(parent.0, |c| c.value.1)
+        && jsonc[line_start..close].trim().is_empty();
+    let mut output = String::with_capacity(jsonc.len() + entry.len() + 8);
+    match last {
+        Some(last) if own_line => {
+            let key_line_start = jsonc[..last.start].rfind('\n').map_or(0, |i| i + 1);
+            let indent = &jsonc[key_line_start..last.start];
+            let has_trailing_comma = stripped.no_comments[last.value.1..close].contains(',');
+            output.push_str(&jsonc[..last.value.1]);
+            if !has_trailing_comma {
+                output.push(',');
+            }
+            output.push_str(&jsonc[last.value.1..line_start]);
+            output.push_str(indent);
+            output.push_str(entry);
+            if has_trailing_comma {
+                output.push(',');
+            }
+            output.push('\n');
+            output.push_str(&jsonc[line_start..]);
+        }
+        Some(last) => {
+            output.push_str(&jsonc[..last.value.1]);
+            output.push_str(", ");
+            output.push_str(entry);
+            output.push_str(&jsonc[last.value.1..]);
+        }
+        None if own_line => {
+            output.push_str(&jsonc[..line_start]);
+            output.push_str(&jsonc[line_start..close]);
+            output.push_str("  ");
+            output.push_str(entry);
+            output.push('\n');
+            output.push_str(&jsonc[line_start..]);
+        }
+        None => {
+            output.push_str(&jsonc[..parent.0 + 1]);
+            output.push_str(entry);
+            output.push_str(&jsonc[parent.0 + 1..]);
+        }
+    }
+    output
+}
+
+/// Returns the byte range of the value starting at `pos` in the valid JSON text.
+fn value_span(json: &[u8], pos: usize) -> (usize, usize) {
+    match json[pos] {
+        b'"' => (pos, string_end(json, pos)),
+        b'{' | b'[' => {
+            let mut depth = 0;
+            let mut i = pos;
+            loop {
+                match json[i] {
+                    b'"' => {
+                        i = string_end(json, i);
+                        continue;
+                    }
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return (pos, i + 1);
+                        }
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+        }
+        _ => {
+            let len = json[pos..]
+                .iter()
+                .position(|b| b",}] \t\r\n".contains(b))
+                .unwrap_or(json.len() - pos);
+            (pos, pos + len)
+        }
+    }
+}
+
+/// Returns the position following the string starting at `pos` (or the end of the input if unterminated).
+fn string_end(bytes: &[u8], pos: usize) -> usize {
+    let mut i = pos + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+fn skip_whitespace(bytes: &[u8], pos: usize) -> usize {
+    pos + bytes[pos..]
+        .iter()
+        .take_while(|b| b.is_ascii_whitespace())
+        .count()
+}
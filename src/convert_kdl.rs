@@ -0,0 +1,148 @@
+//! Conversions of [KDL](https://kdl.dev/) (version 2) documents.
+//!
+//! KDL is a node-oriented language, so the mapping between KDL documents and
+//! serde data structures is less obvious than that of JSON or TOML:
+//!
+//! - A document (the top-level value) must be a struct or a map.
+//! - Each field of a struct (or each entry of a map) is a node whose name is the field name.
+//!   A scalar value is the first argument of the node (e.g., `port 8080`).
+//! - A nested struct is the children block of the node (e.g., `server { port 8080; }`).
+//! - A sequence of scalars is the arguments of a single node (e.g., `tags "a" "b" "c"`).
+//!   A sequence of structs is a node repeated with the same name.
+//!   When deserializing, the children block consisting of `-` nodes (e.g., `items { - 1; - 2; }`) is also accepted.
+//! - `None` is the absence of the node, and the `#null` argument is also deserialized as `None`.
+//! - A unit variant of an enum is a string argument (e.g., `color "Red"`),
+//!   and the other variants are a child node named after the variant (e.g., `shape { Circle { radius 5.0; }; }`).
+//!
+//! Properties (e.g., `node key=1`) and type annotations are not mapped to serde structures.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate serde;
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serdeconv;
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Config {
+//!     name: String,
+//!     tags: Vec<String>,
+//!     server: Server,
+//!     proxy: Option<String>,
+//! }
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Server {
+//!     host: String,
+//!     port: u16,
+//! }
+//!
+//! # fn main() {
+//! let kdl = r#"
+//! name "foo"
+//! tags "web" "rust"
+//! server {
+//!     host "localhost"
+//!     port 8080
+//! }
+//! "#;
+//! let config: Config = serdeconv::from_kdl_str(kdl).unwrap();
+//! assert_eq!(
+//!     config,
+//!     Config {
+//!         name: "foo".to_owned(),
+//!         tags: vec!["web".to_owned(), "rust".to_owned()],
+//!         server: Server {
+//!             host: "localhost".to_owned(),
+//!             port: 8080,
+//!         },
+//!         proxy: None,
+//!     }
+//! );
+//!
+//! let kdl = serdeconv::to_kdl_string(&config).unwrap();
+//! assert_eq!(serdeconv::from_kdl_str::<Config>(&kdl).unwrap(), config);
+//! # }
+//! ```
+use serde::{Deserialize, Serialize};
+use serde_kdl2;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::str;
+
+use util::strip_bom_str;
+use {Error, Result};
+
+/// Converts from the KDL file to a value of `T` type.
+pub fn from_kdl_file<T, P>(path: P) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    P: AsRef<Path>,
+{
+    let f = track!(File::open(path).map_err(Error::from))?;
+    track!(from_kdl_reader(f))
+}
+
+/// Reads a KDL string from the reader and converts it to a value of `T` type.
+pub fn from_kdl_reader<T, R>(mut reader: R) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    R: Read,
+{
+    let mut kdl = String::new();
+    track!(reader.read_to_string(&mut kdl).map_err(Error::from))?;
+    track!(from_kdl_str(&kdl))
+}
+
+/// Converts from the KDL string to a value of `T` type.
+///
+/// See [the module documentation](index.html) for the mapping between KDL documents and values.
+pub fn from_kdl_str<'a, T>(kdl: &'a str) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    let value = track!(serde_kdl2::from_str(strip_bom_str(kdl)).map_err(Error::from))?;
+    Ok(value)
+}
+
+/// Converts from the KDL bytes to a value of `T` type.
+pub fn from_kdl_slice<'a, T>(kdl: &'a [u8]) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    track!(from_kdl_str(str::from_utf8(kdl).map_err(Error::from)?))
+}
+
+/// Converts the value to a KDL string and writes it to the speficied file.
+pub fn to_kdl_file<T, P>(value: &T, path: P) -> Result<()>
+where
+    T: ?Sized + Serialize,
+    P: AsRef<Path>,
+{
+    let f = track!(File::create(path).map_err(Error::from))?;
+    track!(to_kdl_writer(value, f))
+}
+
+/// Converts the value to a KDL string and writes it to the writer.
+pub fn to_kdl_writer<T, W>(value: &T, mut writer: W) -> Result<()>
+where
+    T: ?Sized + Serialize,
+    W: Write,
+{
+    let kdl = track!(to_kdl_string(value))?;
+    track!(writer.write_all(kdl.as_bytes()).map_err(Error::from))?;
+    Ok(())
+}
+
+/// Converts the value to a KDL string.
+///
+/// The top-level value must be a struct or a map.
+pub fn to_kdl_string<T>(value: &T) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    let kdl = track!(serde_kdl2::to_string(&value).map_err(Error::from))?;
+    Ok(kdl)
+}
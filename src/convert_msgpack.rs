@@ -1,10 +1,23 @@
+#[cfg(feature = "erased-serde")]
+use erased_serde;
+#[cfg(feature = "mmap")]
+use memmap2;
 use rmp_serde;
-use serde::{Deserialize, Serialize};
-use std::fs::File;
+#[cfg(feature = "rmpv")]
+use rmpv;
+use serde::{Deserialize, Serialize, Serializer};
+#[cfg(feature = "serde_path_to_error")]
+use serde_path_to_error;
+use std::convert::TryFrom;
+use std::fs::{self, File};
 use std::io::{Read, Write};
 use std::path::Path;
 
-use {Error, Result};
+use options::MsgPackOptions;
+use util::{crc32, write_file_atomic, CountingWriter};
+#[cfg(feature = "compression")]
+use Compression;
+use {Error, ErrorKind, Result};
 
 /// Converts from the MessagePack file to a value of `T` type.
 pub fn from_msgpack_file<T, P>(path: P) -> Result<T>
@@ -16,7 +29,39 @@ where
     track!(from_msgpack_reader(f))
 }
 
+/// Converts from the MessagePack file to a value of `T` type.
+///
+/// If the file does not exist, `T::default()` is returned instead.
+/// Other I/O errors and parse errors are propagated as usual.
+pub fn from_msgpack_file_or_default<T, P>(path: P) -> Result<T>
+where
+    T: for<'a> Deserialize<'a> + Default,
+    P: AsRef<Path>,
+{
+    match from_msgpack_file(path) {
+        Err(ref e) if e.is_not_found() => Ok(T::default()),
+        result => track!(result),
+    }
+}
+
 /// Reads a MessagePack bytes from the reader and converts it to a value of `T` type.
+///
+/// This reads exactly one MessagePack value, and the bytes following it (if any) are left in the reader.
+/// Thus, multiple concatenated values can be read from the same reader one by one.
+///
+/// # Examples
+///
+/// ```
+/// let mut bytes = serdeconv::to_msgpack_vec(&(1, "foo")).unwrap();
+/// bytes.extend(serdeconv::to_msgpack_vec(&[2, 3]).unwrap());
+///
+/// let mut reader = &bytes[..];
+/// let first: (u8, String) = serdeconv::from_msgpack_reader(&mut reader).unwrap();
+/// assert_eq!(first, (1, "foo".to_owned()));
+/// let second: Vec<u8> = serdeconv::from_msgpack_reader(&mut reader).unwrap();
+/// assert_eq!(second, [2, 3]);
+/// assert!(reader.is_empty());
+/// ```
 pub fn from_msgpack_reader<T, R>(reader: R) -> Result<T>
 where
     T: for<'a> Deserialize<'a>,
@@ -26,7 +71,63 @@ where
     Ok(value)
 }
 
+/// Reads a MessagePack bytes of at most `max_bytes` bytes from the reader and
+/// converts it to a value of `T` type.
+///
+/// Unlike `from_msgpack_reader`, this reads the reader until EOF.
+/// If the reader yields more than `max_bytes` bytes,
+/// an `ErrorKind::Invalid` error is returned instead of silently truncating the input.
+pub fn from_msgpack_reader_size_limited<T, R>(reader: R, max_bytes: usize) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    R: Read,
+{
+    track!(MsgPackOptions::new()
+        .max_bytes(max_bytes)
+        .from_reader(reader))
+}
+
 /// Converts from the MessagePack bytes to a value of `T` type.
+///
+/// Structs are accepted in both the compact encoding (as arrays, produced by `to_msgpack_vec`)
+/// and the named encoding (as maps, produced by `to_msgpack_vec_interop` and most other implementations),
+/// in a single decoding pass.
+///
+/// Only the first value is decoded and any bytes following it are ignored,
+/// so messages padded to a fixed size can be read as is.
+/// Use `from_msgpack_slice_partial` if the trailing bytes are needed.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct Foo {
+///     bar: u8,
+///     baz: String,
+/// }
+///
+/// # fn main() {
+/// let foo = Foo { bar: 1, baz: "a".to_owned() };
+///
+/// let compact = serdeconv::to_msgpack_vec(&foo).unwrap();
+/// assert_eq!(compact, [0x92, 0x01, 0xa1, b'a']);
+/// assert_eq!(serdeconv::from_msgpack_slice::<Foo>(&compact).unwrap(), foo);
+///
+/// let named = serdeconv::to_msgpack_vec_interop(&foo).unwrap();
+/// assert_eq!(named[0], 0x82); // A map with two entries
+/// assert_eq!(serdeconv::from_msgpack_slice::<Foo>(&named).unwrap(), foo);
+///
+/// // Trailing padding is ignored.
+/// let mut padded = compact.clone();
+/// padded.resize(16, 0);
+/// assert_eq!(serdeconv::from_msgpack_slice::<Foo>(&padded).unwrap(), foo);
+/// # }
+/// ```
 pub fn from_msgpack_slice<'a, T>(bytes: &'a [u8]) -> Result<T>
 where
     T: Deserialize<'a>,
@@ -35,6 +136,70 @@ where
     Ok(value)
 }
 
+/// Converts from the MessagePack bytes to a value of `T` type, accepting anything byte-slice-like
+/// (e.g., `Vec<u8>`, `&[u8]`, arrays and `Box<[u8]>`).
+///
+/// This is the same as `from_msgpack_slice(bytes.as_ref())`.
+/// Since `bytes` may be an owned buffer that is dropped when this function returns,
+/// `T` cannot borrow from the input; use `from_msgpack_slice` to deserialize borrowing values.
+///
+/// # Examples
+///
+/// ```
+/// let bytes: Vec<u8> = vec![0x92, 0x01, 0x02];
+/// let value: Vec<u8> = serdeconv::from_msgpack_bytes(bytes).unwrap();
+/// assert_eq!(value, [1, 2]);
+/// ```
+pub fn from_msgpack_bytes<T, B>(bytes: B) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    B: AsRef<[u8]>,
+{
+    track!(from_msgpack_slice(bytes.as_ref()))
+}
+
+/// Converts the MessagePack value at the beginning of the bytes to a value of `T` type,
+/// and returns it together with the rest of the bytes.
+///
+/// This is useful for formats consisting of a MessagePack header followed by other data.
+///
+/// # Examples
+///
+/// ```
+/// let mut bytes = serdeconv::to_msgpack_vec(&("foo", 3)).unwrap();
+/// let header_len = bytes.len();
+/// bytes.extend_from_slice(b"\x01\x02\x03");
+///
+/// let (header, rest): ((String, usize), &[u8]) =
+///     serdeconv::from_msgpack_slice_partial(&bytes).unwrap();
+/// assert_eq!(header, ("foo".to_owned(), 3));
+/// assert_eq!(rest, b"\x01\x02\x03");
+/// assert_eq!(bytes.len() - rest.len(), header_len);
+/// ```
+pub fn from_msgpack_slice_partial<T>(bytes: &[u8]) -> Result<(T, &[u8])>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let mut deserializer = rmp_serde::Deserializer::new(bytes);
+    let value = track!(T::deserialize(&mut deserializer).map_err(Error::from))?;
+    Ok((value, deserializer.get_ref()))
+}
+
+/// Converts from the MessagePack bytes to a value of `T` type.
+///
+/// If the bytes are empty, `T::default()` is returned instead.
+/// Unlike the text formats, no trimming is performed.
+pub fn from_msgpack_slice_or_default<'a, T>(bytes: &'a [u8]) -> Result<T>
+where
+    T: Deserialize<'a> + Default,
+{
+    if bytes.is_empty() {
+        Ok(T::default())
+    } else {
+        track!(from_msgpack_slice(bytes))
+    }
+}
+
 /// Converts the value to a MessagePack bytes and writes it to the speficied file.
 pub fn to_msgpack_file<T, P>(value: &T, path: P) -> Result<()>
 where
@@ -45,6 +210,128 @@ where
     track!(to_msgpack_writer(value, f))
 }
 
+/// Converts the value to a MessagePack bytes and writes it to the specified file atomically.
+///
+/// The output is first written to a temporary file in the same directory,
+/// which is flushed and synced to disk, and then renamed to `path`.
+/// Thus, a crash during the write never leaves a truncated file at `path`.
+/// If the serialization fails, the temporary file is removed.
+///
+/// Note that the atomicity relies on the underlying platform and file system:
+/// renaming is atomic on POSIX file systems, but may not be on some platforms
+/// (e.g., network file systems or Windows when the target file is opened by another process).
+///
+/// # Examples
+///
+/// ```
+/// let path = std::env::temp_dir().join("serdeconv_doctest_to_msgpack_file_atomic.msgpack");
+/// serdeconv::to_msgpack_file_atomic(&[1, 2, 3], &path).unwrap();
+///
+/// let value: Vec<u8> = serdeconv::from_msgpack_file(&path).unwrap();
+/// assert_eq!(value, [1, 2, 3]);
+/// ```
+pub fn to_msgpack_file_atomic<T, P>(value: &T, path: P) -> Result<()>
+where
+    T: ?Sized + Serialize,
+    P: AsRef<Path>,
+{
+    track!(write_file_atomic(path.as_ref(), |f| track!(
+        to_msgpack_writer(value, f)
+    )))
+}
+
+/// The magic bytes at the beginning of the files written by `to_msgpack_file_checksummed`.
+const CHECKSUMMED_MAGIC: &[u8; 4] = b"SCMP";
+
+/// The size of the header of the files written by `to_msgpack_file_checksummed`.
+const CHECKSUMMED_HEADER_SIZE: usize = 16;
+
+/// Converts the value to a MessagePack bytes and writes it to the specified file atomically,
+/// prefixed by a header containing the checksum of the bytes.
+///
+/// The file consists of the following 16 bytes header followed by the MessagePack bytes (payload):
+///
+/// | Offset | Size | Content                                               |
+/// |--------|------|-------------------------------------------------------|
+/// | 0      | 4    | Magic bytes (`b"SCMP"`)                               |
+/// | 4      | 4    | CRC-32 (IEEE 802.3) of the payload (big endian)       |
+/// | 8      | 8    | Length of the payload in bytes (big endian)           |
+///
+/// The file can be read by `from_msgpack_file_checksummed`, which detects corrupted or truncated files.
+/// Note that this is not a regular MessagePack file, so it cannot be read by `from_msgpack_file`.
+///
+/// See `to_msgpack_file_atomic` for the atomicity of the write.
+///
+/// # Examples
+///
+/// ```
+/// let path = std::env::temp_dir().join("serdeconv_doctest_to_msgpack_file_checksummed.msgpack");
+/// serdeconv::to_msgpack_file_checksummed(&("foo", 1, 2), &path).unwrap();
+///
+/// let value: (String, u8, u8) = serdeconv::from_msgpack_file_checksummed(&path).unwrap();
+/// assert_eq!(value, ("foo".to_owned(), 1, 2));
+///
+/// // Flips a bit of the payload
+/// let mut bytes = std::fs::read(&path).unwrap();
+/// bytes[17] ^= 0x01;
+/// std::fs::write(&path, &bytes).unwrap();
+///
+/// let error = serdeconv::from_msgpack_file_checksummed::<(String, u8, u8), _>(&path).unwrap_err();
+/// assert!(error.is_invalid());
+/// assert!(error.to_string().contains("Checksum mismatch"));
+/// ```
+pub fn to_msgpack_file_checksummed<T, P>(value: &T, path: P) -> Result<()>
+where
+    T: ?Sized + Serialize,
+    P: AsRef<Path>,
+{
+    let payload = track!(to_msgpack_vec(value))?;
+    let mut bytes = Vec::with_capacity(CHECKSUMMED_HEADER_SIZE + payload.len());
+    bytes.extend_from_slice(CHECKSUMMED_MAGIC);
+    bytes.extend_from_slice(&crc32(&payload).to_be_bytes());
+    bytes.extend_from_slice(&(payload.len() as u64).to_be_bytes());
+    bytes.extend_from_slice(&payload);
+    track!(write_file_atomic(path.as_ref(), |f| track!(f
+        .write_all(&bytes)
+        .map_err(Error::from))))
+}
+
+/// Converts from the MessagePack file written by `to_msgpack_file_checksummed` to a value of `T` type.
+///
+/// The checksum and the length in the header are verified before decoding the payload.
+/// If the file does not start with the header, is truncated, or its checksum does not match,
+/// an `ErrorKind::Invalid` error is returned.
+pub fn from_msgpack_file_checksummed<T, P>(path: P) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    P: AsRef<Path>,
+{
+    let bytes = track!(fs::read(path).map_err(Error::from))?;
+    track_assert!(
+        bytes.len() >= CHECKSUMMED_HEADER_SIZE && bytes.starts_with(CHECKSUMMED_MAGIC),
+        ErrorKind::Invalid,
+        "Not a checksummed MessagePack file"
+    );
+    let (header, payload) = bytes.split_at(CHECKSUMMED_HEADER_SIZE);
+    let checksum = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+    let mut len = [0; 8];
+    len.copy_from_slice(&header[8..]);
+    let len = u64::from_be_bytes(len);
+    track_assert_eq!(
+        len,
+        payload.len() as u64,
+        ErrorKind::Invalid,
+        "Payload length mismatch (the file may be truncated)"
+    );
+    track_assert_eq!(
+        crc32(payload),
+        checksum,
+        ErrorKind::Invalid,
+        "Checksum mismatch"
+    );
+    track!(from_msgpack_slice(payload))
+}
+
 /// Converts the value to a MessagePack bytes and writes it to the writer.
 pub fn to_msgpack_writer<T, W>(value: &T, mut writer: W) -> Result<()>
 where
@@ -55,7 +342,115 @@ where
     Ok(())
 }
 
+/// Serializes the elements of the iterator as a MessagePack array and writes it to the writer.
+///
+/// The elements are written one by one as they are yielded from the iterator,
+/// so the whole sequence is never buffered in memory.
+///
+/// A MessagePack array starts with a header holding the number of the elements,
+/// so the iterator must know its length in advance (i.e., implement `ExactSizeIterator`).
+/// To write an iterator of an unknown length (e.g., a filtered one), collect it into a `Vec` first.
+/// If the iterator yields a different number of elements than its reported length,
+/// an `ErrorKind::Invalid` error is returned.
+///
+/// If an element fails to be serialized (or the writer fails), an error is returned
+/// and the output written so far is an incomplete MessagePack array which should be discarded.
+///
+/// # Examples
+///
+/// ```
+/// let mut buf = Vec::new();
+/// serdeconv::to_msgpack_array_writer((0..3).map(|i| i * 10), &mut buf).unwrap();
+/// assert_eq!(buf, [0x93, 0, 10, 20]);
+///
+/// let mut buf = Vec::new();
+/// serdeconv::to_msgpack_array_writer(0..100_000, &mut buf).unwrap();
+/// let values: Vec<u32> = serdeconv::from_msgpack_slice(&buf).unwrap();
+/// assert_eq!(values, (0..100_000).collect::<Vec<_>>());
+/// ```
+pub fn to_msgpack_array_writer<I, W>(iter: I, mut writer: W) -> Result<()>
+where
+    I: IntoIterator,
+    I::IntoIter: ExactSizeIterator,
+    I::Item: Serialize,
+    W: Write,
+{
+    let iter = iter.into_iter();
+    let len = iter.len();
+    if len < 16 {
+        track!(writer.write_all(&[0x90 | len as u8]).map_err(Error::from))?;
+    } else if len <= usize::from(u16::MAX) {
+        track!(writer.write_all(&[0xdc]).map_err(Error::from))?;
+        track!(writer
+            .write_all(&(len as u16).to_be_bytes())
+            .map_err(Error::from))?;
+    } else {
+        track_assert!(
+            len as u64 <= u64::from(u32::MAX),
+            ErrorKind::Invalid,
+            "Too many elements for a MessagePack array: {}",
+            len
+        );
+        track!(writer.write_all(&[0xdd]).map_err(Error::from))?;
+        track!(writer
+            .write_all(&(len as u32).to_be_bytes())
+            .map_err(Error::from))?;
+    }
+
+    let mut count = 0;
+    for item in iter {
+        track_assert!(
+            count < len,
+            ErrorKind::Invalid,
+            "The iterator yielded more elements than its length: {}",
+            len
+        );
+        track!(to_msgpack_writer(&item, &mut writer))?;
+        count += 1;
+    }
+    track_assert_eq!(
+        count,
+        len,
+        ErrorKind::Invalid,
+        "The iterator yielded fewer elements than its length"
+    );
+    Ok(())
+}
+
+/// Converts the value to a MessagePack bytes, writes it to the writer and flushes the writer.
+///
+/// Returns the number of bytes written.
+pub fn to_msgpack_writer_counted<T, W>(value: &T, writer: W) -> Result<usize>
+where
+    T: ?Sized + Serialize,
+    W: Write,
+{
+    let mut writer = CountingWriter::new(writer);
+    track!(to_msgpack_writer(value, &mut writer))?;
+    track!(writer.flush().map_err(Error::from))?;
+    Ok(writer.count())
+}
+
 /// Converts the value to a MessagePack bytes.
+///
+/// # Enums
+///
+/// Enums are encoded according to their serde representation,
+/// so choose the `#[serde(...)]` attribute depending on the consumers of the bytes:
+///
+/// - Externally tagged (default): unit variants are encoded as strings (e.g., `"Logout"`),
+///   and the others as single-entry maps from the variant names to the contents
+///   (e.g., `{"Login": ["foo"]}`). This is compact, but awkward for non-Rust consumers.
+/// - Internally tagged (`#[serde(tag = "type")]`): variants are encoded as their contents with an additional
+///   `type` field. Note that this function encodes structs as arrays of the field values,
+///   so the tag becomes the first element (e.g., `["Login", "foo"]`).
+///   Use `to_msgpack_vec_interop` to obtain maps (e.g., `{"type": "Login", "user": "foo"}`),
+///   which is the most portable representation for non-Rust consumers.
+/// - Adjacently tagged (`#[serde(tag = "type", content = "value")]`) and untagged (`#[serde(untagged)]`)
+///   enums are encoded in the same manner as structs and their contents, respectively.
+///
+/// Every representation can be decoded by `from_msgpack_slice` (and the other decoding functions),
+/// regardless of whether the structs are encoded as arrays or maps.
 pub fn to_msgpack_vec<T>(value: &T) -> Result<Vec<u8>>
 where
     T: ?Sized + Serialize,
@@ -63,3 +458,326 @@ where
     let bytes = track!(rmp_serde::encode::to_vec(value).map_err(Error::from))?;
     Ok(bytes)
 }
+
+/// Converts the value to a MessagePack bytes in the form expected by the MessagePack libraries
+/// of other languages (e.g., Python's `msgpack` and Go's `msgpack`).
+///
+/// The serializer is configured as follows:
+///
+/// - Structs are encoded as maps keyed by the field names (`with_struct_map()`),
+///   instead of arrays of the field values.
+/// - The serializer is not human-readable (`is_human_readable()` returns `false`),
+///   so [`Timestamp`](value/struct.Timestamp.html) values are encoded as
+///   the timestamp extension type (`-1`).
+///
+/// Note that timestamps of other types (e.g., `SystemTime` or the `chrono` types)
+/// are encoded by their own `Serialize` implementations; wrap them in `Timestamp`
+/// to encode them as the extension type.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// use serdeconv::value::Timestamp;
+/// use std::time::UNIX_EPOCH;
+///
+/// #[derive(Serialize)]
+/// struct Foo {
+///     at: Timestamp,
+/// }
+///
+/// # fn main() {
+/// let bytes = serdeconv::to_msgpack_vec_interop(&Foo { at: Timestamp(UNIX_EPOCH) }).unwrap();
+/// assert_eq!(bytes, [0x81, 0xa2, b'a', b't', 0xd6, 0xff, 0, 0, 0, 0]);
+/// # }
+/// ```
+///
+/// Internally tagged enums are encoded as maps having the tag field,
+/// which non-Rust consumers can dispatch on (see also the "Enums" section of `to_msgpack_vec`):
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// #[serde(tag = "type")]
+/// enum Event {
+///     Login { user: String },
+///     Logout,
+/// }
+///
+/// # fn main() {
+/// let event = Event::Login { user: "foo".to_owned() };
+/// let bytes = serdeconv::to_msgpack_vec_interop(&event).unwrap();
+///
+/// let json = serdeconv::transcode(&bytes, serdeconv::Format::MessagePack, serdeconv::Format::Json).unwrap();
+/// assert_eq!(json, br#"{"type":"Login","user":"foo"}"#);
+/// assert_eq!(serdeconv::from_msgpack_slice::<Event>(&bytes).unwrap(), event);
+///
+/// let bytes = serdeconv::to_msgpack_vec_interop(&Event::Logout).unwrap();
+/// assert_eq!(serdeconv::from_msgpack_slice::<Event>(&bytes).unwrap(), Event::Logout);
+/// # }
+/// ```
+pub fn to_msgpack_vec_interop<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: ?Sized + Serialize,
+{
+    track!(MsgPackOptions::new().struct_map(true).to_vec(value))
+}
+
+/// Converts the value to a MessagePack bytes by using the given options.
+///
+/// This is the same as `options.to_vec(value)`.
+/// See `MsgPackOptions` for the available options and their impact on the interoperability.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// use serdeconv::options::MsgPackOptions;
+///
+/// #[derive(Serialize)]
+/// struct Foo {
+///     id: u32,
+/// }
+///
+/// # fn main() {
+/// let foo = Foo { id: 7 };
+/// assert_eq!(
+///     serdeconv::to_msgpack_vec_with(&foo, &MsgPackOptions::new()).unwrap(),
+///     serdeconv::to_msgpack_vec(&foo).unwrap()
+/// );
+///
+/// let options = MsgPackOptions::new().struct_map(true).compact_integers(false);
+/// assert_eq!(
+///     serdeconv::to_msgpack_vec_with(&foo, &options).unwrap(),
+///     [0x81, 0xa2, b'i', b'd', 0xcf, 0, 0, 0, 0, 0, 0, 0, 7]
+/// );
+///
+/// let options = MsgPackOptions::new().human_readable(true);
+/// let ip: std::net::IpAddr = "127.0.0.1".parse().unwrap();
+/// let bytes = serdeconv::to_msgpack_vec_with(&ip, &options).unwrap();
+/// assert_eq!(serdeconv::from_msgpack_slice::<String>(&bytes).unwrap(), "127.0.0.1");
+/// # }
+/// ```
+pub fn to_msgpack_vec_with<T>(value: &T, options: &MsgPackOptions) -> Result<Vec<u8>>
+where
+    T: ?Sized + Serialize,
+{
+    track!(options.to_vec(value))
+}
+
+/// A wrapper for converting MessagePack bytes by using the standard conversion traits.
+///
+/// `MsgPack<T>` implements `TryFrom<&[u8]>` and `TryFrom<Vec<u8>>` (delegating to `from_msgpack_slice`),
+/// and serializes as the wrapped value (so `to_msgpack_vec(&MsgPack(x))` is the same as `to_msgpack_vec(&x)`).
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// use serdeconv::MsgPack;
+/// use std::convert::TryInto;
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct Request {
+///     id: u32,
+///     path: String,
+/// }
+///
+/// fn handle(bytes: &[u8]) -> serdeconv::Result<Request> {
+///     let MsgPack(request) = bytes.try_into()?;
+///     Ok(request)
+/// }
+///
+/// # fn main() {
+/// let request = Request { id: 1, path: "/foo".to_owned() };
+/// let bytes = serdeconv::to_msgpack_vec(&MsgPack(&request)).unwrap();
+/// assert_eq!(bytes, serdeconv::to_msgpack_vec(&request).unwrap());
+///
+/// let MsgPack(decoded): MsgPack<Request> = bytes.as_slice().try_into().unwrap();
+/// assert_eq!(decoded, request);
+///
+/// assert_eq!(handle(&bytes).unwrap(), request);
+///
+/// let MsgPack(decoded): MsgPack<Request> = bytes.try_into().unwrap();
+/// assert_eq!(decoded, request);
+/// assert!(handle(&[0xc1]).is_err());
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MsgPack<T>(pub T);
+impl<T> MsgPack<T> {
+    /// Returns the wrapped value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+impl<'a, T> TryFrom<&'a [u8]> for MsgPack<T>
+where
+    T: for<'b> Deserialize<'b>,
+{
+    type Error = Error;
+
+    fn try_from(bytes: &'a [u8]) -> Result<Self> {
+        track!(from_msgpack_slice(bytes)).map(MsgPack)
+    }
+}
+impl<T> TryFrom<Vec<u8>> for MsgPack<T>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    type Error = Error;
+
+    fn try_from(bytes: Vec<u8>) -> Result<Self> {
+        track!(from_msgpack_slice(&bytes)).map(MsgPack)
+    }
+}
+impl<T: Serialize> Serialize for MsgPack<T> {
+    fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+/// Reads a MessagePack bytes from the compressed reader and converts it to a value of `T` type.
+#[cfg(feature = "compression")]
+pub fn from_msgpack_reader_compressed<T, R>(reader: R, compression: Compression) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    R: Read,
+{
+    let reader = track!(compression.wrap_reader(reader))?;
+    track!(from_msgpack_reader(reader))
+}
+
+/// Converts the value to a MessagePack bytes and writes it to the writer with the given compression.
+#[cfg(feature = "compression")]
+pub fn to_msgpack_writer_compressed<T, W>(
+    value: &T,
+    writer: W,
+    compression: Compression,
+) -> Result<()>
+where
+    T: ?Sized + Serialize,
+    W: Write,
+{
+    track!(compression.write_with(writer, |w| track!(to_msgpack_writer(value, w))))
+}
+
+/// Converts from the MessagePack bytes to a `rmpv::Value`.
+///
+/// This is useful for inspecting or forwarding messages without a static schema.
+/// MessagePack ext types are preserved as `rmpv::Value::Ext(type_id, payload)`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate rmpv;
+/// extern crate serdeconv;
+///
+/// use rmpv::Value;
+///
+/// # fn main() {
+/// let value = Value::Array(vec![Value::from(1), Value::Ext(5, vec![0, 1, 2])]);
+/// let bytes = serdeconv::to_msgpack_vec_value(&value).unwrap();
+/// assert_eq!(serdeconv::from_msgpack_slice_value(&bytes).unwrap(), value);
+/// # }
+/// ```
+#[cfg(feature = "rmpv")]
+pub fn from_msgpack_slice_value(bytes: &[u8]) -> Result<rmpv::Value> {
+    track!(from_msgpack_slice(bytes))
+}
+
+/// Converts the `rmpv::Value` to a MessagePack bytes.
+///
+/// `rmpv::Value::Ext(type_id, payload)` is encoded as a MessagePack ext type.
+#[cfg(feature = "rmpv")]
+pub fn to_msgpack_vec_value(value: &rmpv::Value) -> Result<Vec<u8>> {
+    track!(to_msgpack_vec(value))
+}
+
+/// Converts the type-erased value to a MessagePack bytes and writes it to the type-erased writer.
+///
+/// This is useful when neither the type of the value nor the one of the writer can be named
+/// (e.g., in plugin code based on dynamic dispatch).
+///
+/// Note that the generic `to_msgpack_writer` also accepts a `&mut dyn Write` as the writer.
+///
+/// # Examples
+///
+/// ```
+/// extern crate erased_serde;
+/// extern crate serdeconv;
+///
+/// use std::collections::BTreeMap;
+/// use std::io::Write;
+///
+/// # fn main() {
+/// let mut map = BTreeMap::new();
+/// map.insert("foo", 1);
+/// let value: &dyn erased_serde::Serialize = &map;
+///
+/// let mut buf = Vec::new();
+/// {
+///     let writer: &mut dyn Write = &mut buf;
+///     serdeconv::to_msgpack_dyn_writer(value, writer).unwrap();
+/// }
+/// assert_eq!(buf, [0x81, 0xa3, b'f', b'o', b'o', 0x01]);
+/// # }
+/// ```
+#[cfg(feature = "erased-serde")]
+pub fn to_msgpack_dyn_writer(
+    value: &dyn erased_serde::Serialize,
+    writer: &mut dyn Write,
+) -> Result<()> {
+    track!(to_msgpack_writer(value, writer))
+}
+
+/// Converts from the MessagePack bytes to a value of `T` type,
+/// prepending the path of the failed field to the error message.
+#[cfg(feature = "serde_path_to_error")]
+pub fn from_msgpack_slice_with_path<T>(bytes: &[u8]) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let mut deserializer = rmp_serde::Deserializer::from_read_ref(bytes);
+    let value = track!(serde_path_to_error::deserialize(&mut deserializer).map_err(Error::from))?;
+    Ok(value)
+}
+
+/// Maps the MessagePack file into memory (read-only) and converts it to a value of `T` type.
+///
+/// Compared to `from_msgpack_file`, this avoids copying the file content
+/// and lets the OS page it in lazily, which is beneficial for large data files.
+///
+/// # Safety
+///
+/// The file must not be modified (by this or any other process) while it is mapped,
+/// i.e., until this function returns. Otherwise the behavior is undefined.
+#[cfg(feature = "mmap")]
+pub unsafe fn from_msgpack_mmap<T, P>(path: P) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    P: AsRef<Path>,
+{
+    let f = track!(File::open(path).map_err(Error::from))?;
+    let mmap = track!(memmap2::Mmap::map(&f).map_err(Error::from))?;
+    track!(from_msgpack_slice(&mmap))
+}
@@ -0,0 +1,184 @@
+use serde::Deserialize;
+use serde_json;
+use std::fmt;
+use std::io::{BufRead, Lines};
+use std::marker::PhantomData;
+
+use {Error, Result};
+
+/// Reads NDJSON (newline delimited JSON) records from the reader.
+///
+/// The returned iterator yields each record together with its 1-based line number,
+/// so that the records (and the errors) can be referred to by line.
+/// Empty lines (including lines that consist only of whitespace) are skipped.
+///
+/// If a line is malformed, the yielded error contains the line number in its history,
+/// and the iteration can be continued from the next line.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Debug, PartialEq, Deserialize)]
+/// struct Record {
+///     id: u32,
+/// }
+///
+/// # fn main() {
+/// let ndjson = "{\"id\": 1}\n\n{\"id\": 2}\n{\"id\": \"three\"}\n";
+/// let mut records = serdeconv::from_ndjson_reader::<Record, _>(ndjson.as_bytes());
+///
+/// assert_eq!(records.next().unwrap().unwrap(), (1, Record { id: 1 }));
+/// assert_eq!(records.next().unwrap().unwrap(), (3, Record { id: 2 }));
+///
+/// let error = records.next().unwrap().unwrap_err();
+/// assert!(error.is_invalid());
+/// assert!(error.to_string().contains("line=4"));
+///
+/// assert!(records.next().is_none());
+/// # }
+/// ```
+pub fn from_ndjson_reader<T, R>(reader: R) -> NdjsonReader<T, R>
+where
+    T: for<'a> Deserialize<'a>,
+    R: BufRead,
+{
+    NdjsonReader {
+        lines: reader.lines(),
+        line: 0,
+        _value: PhantomData,
+    }
+}
+
+/// Reads NDJSON records from the reader, invoking `on_record` with the number of the records read so far.
+///
+/// This is the same as `from_ndjson_reader`, except that `on_record` is invoked
+/// each time the returned iterator yields a record (including a malformed one),
+/// which is handy for updating a progress bar.
+/// Skipped empty lines do not invoke the callback.
+///
+/// # Examples
+///
+/// ```
+/// let ndjson = "1\n\n2\n\"three\"\n4\n";
+/// let mut counts = Vec::new();
+/// let records = serdeconv::from_ndjson_reader_with_progress::<u32, _, _>(ndjson.as_bytes(), |n| {
+///     counts.push(n)
+/// })
+/// .collect::<Vec<_>>();
+/// assert_eq!(records.len(), 4);
+/// assert!(records[2].is_err());
+/// assert_eq!(counts, [1, 2, 3, 4]);
+/// ```
+pub fn from_ndjson_reader_with_progress<T, R, F>(
+    reader: R,
+    on_record: F,
+) -> NdjsonProgressReader<T, R, F>
+where
+    T: for<'a> Deserialize<'a>,
+    R: BufRead,
+    F: FnMut(usize),
+{
+    NdjsonProgressReader {
+        inner: from_ndjson_reader(reader),
+        records: 0,
+        on_record,
+    }
+}
+
+/// An iterator over the NDJSON records read from a reader.
+///
+/// This is created by the `from_ndjson_reader` function.
+pub struct NdjsonReader<T, R> {
+    lines: Lines<R>,
+    line: usize,
+    _value: PhantomData<fn() -> T>,
+}
+impl<T, R> NdjsonReader<T, R> {
+    /// Returns the number of the lines read so far.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+impl<T, R> Iterator for NdjsonReader<T, R>
+where
+    T: for<'a> Deserialize<'a>,
+    R: BufRead,
+{
+    type Item = Result<(usize, T)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for line in &mut self.lines {
+            self.line += 1;
+            let n = self.line;
+            let line = match track!(line.map_err(Error::from), "line={}", n) {
+                Err(e) => return Some(Err(e)),
+                Ok(line) => line,
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let result = track!(
+                serde_json::from_str(&line).map_err(Error::from),
+                "line={}",
+                n
+            );
+            return Some(result.map(|value| (n, value)));
+        }
+        None
+    }
+}
+impl<T, R> fmt::Debug for NdjsonReader<T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NdjsonReader")
+            .field("line", &self.line)
+            .finish()
+    }
+}
+
+/// An iterator over the NDJSON records read from a reader, reporting the progress to a callback.
+///
+/// This is created by the `from_ndjson_reader_with_progress` function.
+pub struct NdjsonProgressReader<T, R, F> {
+    inner: NdjsonReader<T, R>,
+    records: usize,
+    on_record: F,
+}
+impl<T, R, F> NdjsonProgressReader<T, R, F> {
+    /// Returns the number of the lines read so far.
+    pub fn line(&self) -> usize {
+        self.inner.line()
+    }
+
+    /// Returns the number of the records yielded so far.
+    pub fn records(&self) -> usize {
+        self.records
+    }
+}
+impl<T, R, F> Iterator for NdjsonProgressReader<T, R, F>
+where
+    T: for<'a> Deserialize<'a>,
+    R: BufRead,
+    F: FnMut(usize),
+{
+    type Item = Result<(usize, T)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.inner.next()?;
+        self.records += 1;
+        (self.on_record)(self.records);
+        Some(item)
+    }
+}
+impl<T, R, F> fmt::Debug for NdjsonProgressReader<T, R, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NdjsonProgressReader")
+            .field("line", &self.inner.line)
+            .field("records", &self.records)
+            .finish()
+    }
+}
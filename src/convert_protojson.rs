@@ -0,0 +1,853 @@
+//! Conversions following the [proto3 JSON mapping](https://protobuf.dev/programming-guides/proto3/#json).
+//!
+//! The functions in this module can be used with any (de)serializable types,
+//! but they are intended for messages generated from protobuf definitions
+//! (e.g., by `prost` with serde support) whose field names are in `snake_case`.
+//!
+//! The following conventions are applied on top of the plain JSON conversions:
+//!
+//! - Struct field names are converted to `lowerCamelCase` (e.g., `max_connections` becomes `maxConnections`).
+//!   When deserializing, both the `lowerCamelCase` names and the original names are accepted.
+//!   The keys of maps are kept as is.
+//! - 64-bit integers (`i64` and `u64`) are emitted as JSON strings (e.g., `"123"`).
+//!   When deserializing, integers of any width are accepted both as numbers and as strings.
+//! - Non-finite floats are emitted as the strings `"NaN"`, `"Infinity"` and `"-Infinity"`, and accepted as well.
+//! - Bytes (serialized via `serialize_bytes`, e.g., by `serde_bytes`) are emitted as standard base64 strings.
+//!   When deserializing, both the standard and the URL-safe alphabets are accepted.
+//! - Enum values are emitted as the names of the variants (this is the default behavior of serde).
+//!
+//! Note that fields with default values are emitted as is;
+//! the mapping allows this although the official implementations omit such fields by default.
+//! Also, well-known types (e.g., `google.protobuf.Timestamp`) have no special treatment.
+use serde::de::{self, DeserializeSeed, Visitor};
+use serde::ser::{self, Serialize};
+use serde::{Deserialize, Deserializer};
+use serde_json::{self, Map, Value};
+use std::vec;
+
+use util::{decode_base64, encode_base64};
+use {Error, Result};
+
+/// Converts from the JSON string in the proto3 JSON mapping to a value of `T` type.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct Pool {
+///     max_connections: u32,
+///     total_bytes: u64,
+/// }
+///
+/// # fn main() {
+/// let pool = Pool { max_connections: 10, total_bytes: 1024 };
+/// let json = serdeconv::to_protojson_string(&pool).unwrap();
+/// assert_eq!(json, r#"{"maxConnections":10,"totalBytes":"1024"}"#);
+///
+/// assert_eq!(serdeconv::from_protojson_str::<Pool>(&json).unwrap(), pool);
+///
+/// let json = r#"{"max_connections": "10", "totalBytes": 1024}"#;
+/// assert_eq!(serdeconv::from_protojson_str::<Pool>(json).unwrap(), pool);
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// enum Source {
+///     Pool(Pool),
+///     Limits { total_bytes: u64, max_count: (i64, u32) },
+///     Unlimited,
+/// }
+///
+/// for source in vec![
+///     Source::Pool(pool),
+///     Source::Limits { total_bytes: 5, max_count: (-1, 2) },
+///     Source::Unlimited,
+/// ] {
+///     let json = serdeconv::to_protojson_string(&source).unwrap();
+///     assert_eq!(serdeconv::from_protojson_str::<Source>(&json).unwrap(), source);
+/// }
+///
+/// let json = r#"{"Limits": {"totalBytes": "5", "maxCount": ["-1", 2]}}"#;
+/// let source = Source::Limits { total_bytes: 5, max_count: (-1, 2) };
+/// assert_eq!(serdeconv::from_protojson_str::<Source>(json).unwrap(), source);
+/// # }
+/// ```
+pub fn from_protojson_str<T>(json: &str) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let value: Value = track!(serde_json::from_str(json).map_err(Error::from))?;
+    track!(from_protojson_value(value))
+}
+
+/// Converts from the JSON bytes in the proto3 JSON mapping to a value of `T` type.
+pub fn from_protojson_slice<T>(json: &[u8]) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let value: Value = track!(serde_json::from_slice(json).map_err(Error::from))?;
+    track!(from_protojson_value(value))
+}
+
+/// Converts the value to a JSON string in the proto3 JSON mapping.
+pub fn to_protojson_string<T>(value: &T) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    let value = track!(to_protojson_value(value))?;
+    let json = track!(serde_json::to_string(&value).map_err(Error::from))?;
+    Ok(json)
+}
+
+/// Converts the value to a JSON bytes in the proto3 JSON mapping.
+pub fn to_protojson_vec<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: ?Sized + Serialize,
+{
+    let value = track!(to_protojson_value(value))?;
+    let json = track!(serde_json::to_vec(&value).map_err(Error::from))?;
+    Ok(json)
+}
+
+fn from_protojson_value<T>(value: Value) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let value = track!(T::deserialize(ProtoDeserializer(value)).map_err(Error::from))?;
+    Ok(value)
+}
+
+fn to_protojson_value<T>(value: &T) -> Result<Value>
+where
+    T: ?Sized + Serialize,
+{
+    let value = track!(value.serialize(ProtoSerializer).map_err(Error::from))?;
+    Ok(value)
+}
+
+fn to_lower_camel_case(name: &str) -> String {
+    let mut s = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            s.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            s.push(c);
+        }
+    }
+    s
+}
+
+struct ProtoSerializer;
+impl ser::Serializer for ProtoSerializer {
+    type Ok = Value;
+    type Error = serde_json::Error;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = SeqSerializer;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = MapSerializer;
+
+    fn serialize_bool(self, v: bool) -> ::std::result::Result<Value, Self::Error> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> ::std::result::Result<Value, Self::Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> ::std::result::Result<Value, Self::Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> ::std::result::Result<Value, Self::Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> ::std::result::Result<Value, Self::Error> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_u8(self, v: u8) -> ::std::result::Result<Value, Self::Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> ::std::result::Result<Value, Self::Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> ::std::result::Result<Value, Self::Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> ::std::result::Result<Value, Self::Error> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_f32(self, v: f32) -> ::std::result::Result<Value, Self::Error> {
+        self.serialize_f64(f64::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> ::std::result::Result<Value, Self::Error> {
+        if v.is_nan() {
+            Ok(Value::String("NaN".to_owned()))
+        } else if v.is_infinite() {
+            let s = if v > 0.0 { "Infinity" } else { "-Infinity" };
+            Ok(Value::String(s.to_owned()))
+        } else {
+            Ok(Value::from(v))
+        }
+    }
+
+    fn serialize_char(self, v: char) -> ::std::result::Result<Value, Self::Error> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> ::std::result::Result<Value, Self::Error> {
+        Ok(Value::String(v.to_owned()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> ::std::result::Result<Value, Self::Error> {
+        Ok(Value::String(encode_base64(v)))
+    }
+
+    fn serialize_none(self) -> ::std::result::Result<Value, Self::Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(
+        self,
+        value: &T,
+    ) -> ::std::result::Result<Value, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> ::std::result::Result<Value, Self::Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> ::std::result::Result<Value, Self::Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> ::std::result::Result<Value, Self::Error> {
+        Ok(Value::String(variant.to_owned()))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> ::std::result::Result<Value, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> ::std::result::Result<Value, Self::Error> {
+        let mut map = Map::new();
+        map.insert(variant.to_owned(), value.serialize(self)?);
+        Ok(Value::Object(map))
+    }
+
+    fn serialize_seq(
+        self,
+        len: Option<usize>,
+    ) -> ::std::result::Result<SeqSerializer, Self::Error> {
+        Ok(SeqSerializer {
+            variant: None,
+            values: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> ::std::result::Result<SeqSerializer, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        len: usize,
+    ) -> ::std::result::Result<SeqSerializer, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> ::std::result::Result<SeqSerializer, Self::Error> {
+        Ok(SeqSerializer {
+            variant: Some(variant),
+            values: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> ::std::result::Result<MapSerializer, Self::Error> {
+        Ok(MapSerializer {
+            variant: None,
+            map: Map::new(),
+            next_key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> ::std::result::Result<MapSerializer, Self::Error> {
+        self.serialize_map(None)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> ::std::result::Result<MapSerializer, Self::Error> {
+        Ok(MapSerializer {
+            variant: Some(variant),
+            map: Map::new(),
+            next_key: None,
+        })
+    }
+}
+
+fn wrap_variant(variant: Option<&'static str>, value: Value) -> Value {
+    if let Some(variant) = variant {
+        let mut map = Map::new();
+        map.insert(variant.to_owned(), value);
+        Value::Object(map)
+    } else {
+        value
+    }
+}
+
+struct SeqSerializer {
+    variant: Option<&'static str>,
+    values: Vec<Value>,
+}
+impl SeqSerializer {
+    fn push<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> ::std::result::Result<(), serde_json::Error> {
+        self.values.push(value.serialize(ProtoSerializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Value {
+        wrap_variant(self.variant, Value::Array(self.values))
+    }
+}
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = Value;
+    type Error = serde_json::Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> ::std::result::Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> ::std::result::Result<Value, Self::Error> {
+        Ok(self.finish())
+    }
+}
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = Value;
+    type Error = serde_json::Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> ::std::result::Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> ::std::result::Result<Value, Self::Error> {
+        Ok(self.finish())
+    }
+}
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = Value;
+    type Error = serde_json::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> ::std::result::Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> ::std::result::Result<Value, Self::Error> {
+        Ok(self.finish())
+    }
+}
+impl ser::SerializeTupleVariant for SeqSerializer {
+    type Ok = Value;
+    type Error = serde_json::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> ::std::result::Result<(), Self::Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> ::std::result::Result<Value, Self::Error> {
+        Ok(self.finish())
+    }
+}
+
+struct MapSerializer {
+    variant: Option<&'static str>,
+    map: Map<String, Value>,
+    next_key: Option<String>,
+}
+impl MapSerializer {
+    fn finish(self) -> Value {
+        wrap_variant(self.variant, Value::Object(self.map))
+    }
+
+    fn insert_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> ::std::result::Result<(), serde_json::Error> {
+        let value = value.serialize(ProtoSerializer)?;
+        self.map.insert(to_lower_camel_case(key), value);
+        Ok(())
+    }
+}
+impl ser::SerializeMap for MapSerializer {
+    type Ok = Value;
+    type Error = serde_json::Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(
+        &mut self,
+        key: &T,
+    ) -> ::std::result::Result<(), Self::Error> {
+        let key = match key.serialize(ProtoSerializer)? {
+            Value::String(s) => s,
+            Value::Number(n) => n.to_string(),
+            Value::Bool(b) => b.to_string(),
+            _ => return Err(ser::Error::custom("key must be a string")),
+        };
+        self.next_key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> ::std::result::Result<(), Self::Error> {
+        let key = self.next_key.take().unwrap_or_default();
+        self.map.insert(key, value.serialize(ProtoSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> ::std::result::Result<Value, Self::Error> {
+        Ok(self.finish())
+    }
+}
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = Value;
+    type Error = serde_json::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> ::std::result::Result<(), Self::Error> {
+        self.insert_field(key, value)
+    }
+
+    fn end(self) -> ::std::result::Result<Value, Self::Error> {
+        Ok(self.finish())
+    }
+}
+impl ser::SerializeStructVariant for MapSerializer {
+    type Ok = Value;
+    type Error = serde_json::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> ::std::result::Result<(), Self::Error> {
+        self.insert_field(key, value)
+    }
+
+    fn end(self) -> ::std::result::Result<Value, Self::Error> {
+        Ok(self.finish())
+    }
+}
+
+struct ProtoDeserializer(Value);
+impl ProtoDeserializer {
+    /// Converts a string holding a number (e.g., `"123"`) to the number.
+    fn unquote_number(self) -> Value {
+        if let Value::String(ref s) = self.0 {
+            if let Ok(n) = s.parse::<i64>() {
+                return Value::from(n);
+            } else if let Ok(n) = s.parse::<u64>() {
+                return Value::from(n);
+            } else if let Ok(n) = s.parse::<f64>() {
+                if n.is_finite() {
+                    return Value::from(n);
+                }
+            }
+        }
+        self.0
+    }
+}
+
+macro_rules! forward_integer {
+    ($($method:ident)*) => {
+        $(fn $method<V: Visitor<'de>>(
+            self,
+            visitor: V,
+        ) -> ::std::result::Result<V::Value, Self::Error> {
+            self.unquote_number().$method(visitor)
+        })*
+    };
+}
+
+macro_rules! forward_value {
+    ($($method:ident)*) => {
+        $(fn $method<V: Visitor<'de>>(
+            self,
+            visitor: V,
+        ) -> ::std::result::Result<V::Value, Self::Error> {
+            self.0.$method(visitor)
+        })*
+    };
+}
+
+impl<'de> Deserializer<'de> for ProtoDeserializer {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Array(values) => visitor.visit_seq(SeqAccess(values.into_iter())),
+            Value::Object(map) => visitor.visit_map(MapAccess::new(map.into_iter().collect())),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    forward_integer! {
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        if let Value::String(ref s) = self.0 {
+            match s.as_str() {
+                "NaN" => return visitor.visit_f64(f64::NAN),
+                "Infinity" => return visitor.visit_f64(f64::INFINITY),
+                "-Infinity" => return visitor.visit_f64(f64::NEG_INFINITY),
+                _ => {}
+            }
+        }
+        self.unquote_number().deserialize_f64(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        if let Value::String(ref s) = self.0 {
+            if let Some(bytes) = decode_base64(s) {
+                return visitor.visit_byte_buf(bytes);
+            }
+        }
+        self.0.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        if self.0.is_null() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Array(values) => visitor.visit_seq(SeqAccess(values.into_iter())),
+            value => value.deserialize_seq(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _: usize,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: usize,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Object(map) => visitor.visit_map(MapAccess::new(map.into_iter().collect())),
+            value => value.deserialize_map(visitor),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Object(map) => {
+                let entries = map
+                    .into_iter()
+                    .map(|(k, v)| {
+                        let field = fields.iter().find(|f| to_lower_camel_case(f) == k);
+                        (field.map_or(k, |f| (*f).to_owned()), v)
+                    })
+                    .collect();
+                visitor.visit_map(MapAccess::new(entries))
+            }
+            value => value.deserialize_struct(name, fields, visitor),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::String(variant) => visitor.visit_enum(EnumAccess {
+                variant,
+                value: None,
+            }),
+            Value::Object(map) => {
+                if map.len() != 1 {
+                    return Err(de::Error::invalid_value(
+                        de::Unexpected::Map,
+                        &"a map with a single key",
+                    ));
+                }
+                let (variant, value) = map.into_iter().next().expect("never fails");
+                visitor.visit_enum(EnumAccess {
+                    variant,
+                    value: Some(value),
+                })
+            }
+            value => value.deserialize_enum(name, variants, visitor),
+        }
+    }
+
+    forward_value! {
+        deserialize_bool deserialize_char deserialize_str deserialize_string
+        deserialize_unit deserialize_identifier deserialize_ignored_any
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        self.0.deserialize_unit_struct(name, visitor)
+    }
+}
+
+struct EnumAccess {
+    variant: String,
+    value: Option<Value>,
+}
+impl<'de> de::EnumAccess<'de> for EnumAccess {
+    type Error = serde_json::Error;
+    type Variant = VariantAccess;
+
+    fn variant_seed<V>(
+        self,
+        seed: V,
+    ) -> ::std::result::Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(Value::String(self.variant))?;
+        Ok((variant, VariantAccess(self.value)))
+    }
+}
+
+struct VariantAccess(Option<Value>);
+impl VariantAccess {
+    fn content(self, expected: &str) -> ::std::result::Result<Value, serde_json::Error> {
+        match self.0 {
+            Some(value) => Ok(value),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &expected,
+            )),
+        }
+    }
+}
+impl<'de> de::VariantAccess<'de> for VariantAccess {
+    type Error = serde_json::Error;
+
+    fn unit_variant(self) -> ::std::result::Result<(), Self::Error> {
+        match self.0 {
+            None | Some(Value::Null) => Ok(()),
+            Some(value) => Deserialize::deserialize(value),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> ::std::result::Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let value = self.content("newtype variant")?;
+        seed.deserialize(ProtoDeserializer(value))
+    }
+
+    fn tuple_variant<V>(self, _: usize, visitor: V) -> ::std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let value = self.content("tuple variant")?;
+        ProtoDeserializer(value).deserialize_seq(visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let value = self.content("struct variant")?;
+        ProtoDeserializer(value).deserialize_struct("", fields, visitor)
+    }
+}
+
+struct SeqAccess(vec::IntoIter<Value>);
+impl<'de> de::SeqAccess<'de> for SeqAccess {
+    type Error = serde_json::Error;
+
+    fn next_element_seed<T>(
+        &mut self,
+        seed: T,
+    ) -> ::std::result::Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.0.next() {
+            Some(v) => seed.deserialize(ProtoDeserializer(v)).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+struct MapAccess {
+    entries: vec::IntoIter<(String, Value)>,
+    value: Option<Value>,
+}
+impl MapAccess {
+    fn new(entries: Vec<(String, Value)>) -> Self {
+        MapAccess {
+            entries: entries.into_iter(),
+            value: None,
+        }
+    }
+}
+impl<'de> de::MapAccess<'de> for MapAccess {
+    type Error = serde_json::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> ::std::result::Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.entries.next() {
+            Some((k, v)) => {
+                self.value = Some(v);
+                seed.deserialize(ProtoDeserializer(Value::String(k)))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> ::std::result::Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let value = self.value.take().unwrap_or(Value::Null);
+        seed.deserialize(ProtoDeserializer(value))
+    }
+}
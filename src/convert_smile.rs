@@ -0,0 +1,97 @@
+//! Conversions of [Smile](https://github.com/FasterXML/smile-format-specification),
+//! a binary JSON format.
+//!
+//! Like MessagePack, Smile is a compact and self-describing binary format.
+//! However, it is a binary encoding of the JSON data model
+//! (e.g., structs are always encoded as maps keyed by the field names),
+//! and every Smile document starts with the `:)\n` header,
+//! so the documents are easier to identify and inspect during debugging.
+//! It also shares repeated keys and short string values via back references,
+//! which makes the documents containing many records with the same fields smaller.
+use serde::{Deserialize, Serialize};
+use serde_smile;
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+use std::path::Path;
+
+use {Error, Result};
+
+/// Converts from the Smile file to a value of `T` type.
+pub fn from_smile_file<T, P>(path: P) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    P: AsRef<Path>,
+{
+    let f = track!(File::open(path).map_err(Error::from))?;
+    track!(from_smile_reader(f))
+}
+
+/// Reads a Smile bytes from the reader and converts it to a value of `T` type.
+pub fn from_smile_reader<T, R>(reader: R) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    R: Read,
+{
+    let value = track!(serde_smile::from_reader(BufReader::new(reader)).map_err(Error::from))?;
+    Ok(value)
+}
+
+/// Converts from the Smile bytes to a value of `T` type.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct Foo {
+///     bar: String,
+///     baz: usize,
+/// }
+///
+/// # fn main() {
+/// let foo = Foo { bar: "aaa".to_owned(), baz: 123 };
+/// let bytes = serdeconv::to_smile_vec(&foo).unwrap();
+/// assert!(bytes.starts_with(b":)\n"));
+/// assert_eq!(serdeconv::from_smile_slice::<Foo>(&bytes).unwrap(), foo);
+/// # }
+/// ```
+pub fn from_smile_slice<'a, T>(bytes: &'a [u8]) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    let value = track!(serde_smile::from_slice(bytes).map_err(Error::from))?;
+    Ok(value)
+}
+
+/// Converts the value to a Smile bytes and writes it to the speficied file.
+pub fn to_smile_file<T, P>(value: &T, path: P) -> Result<()>
+where
+    T: ?Sized + Serialize,
+    P: AsRef<Path>,
+{
+    let f = track!(File::create(path).map_err(Error::from))?;
+    track!(to_smile_writer(value, f))
+}
+
+/// Converts the value to a Smile bytes and writes it to the writer.
+pub fn to_smile_writer<T, W>(value: &T, writer: W) -> Result<()>
+where
+    T: ?Sized + Serialize,
+    W: Write,
+{
+    track!(serde_smile::to_writer(writer, value).map_err(Error::from))?;
+    Ok(())
+}
+
+/// Converts the value to a Smile bytes.
+pub fn to_smile_vec<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: ?Sized + Serialize,
+{
+    let bytes = track!(serde_smile::to_vec(value).map_err(Error::from))?;
+    Ok(bytes)
+}
@@ -1,12 +1,27 @@
+#[cfg(feature = "erased-serde")]
+use erased_serde;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde_path_to_error")]
+use serde_path_to_error;
+use std::borrow::Cow;
 use std::fs::File;
 use std::io::{Read, Write};
-use std::path::Path;
+use std::mem;
+use std::path::{Path, PathBuf};
 use std::str;
 use toml;
+use toml_edit;
+use trackable::error::ErrorKindExt;
 
-use {Error, Result};
+use finite::check_finite_floats;
+use options::TomlOptions;
+use probe;
+use traits::FromTomlValidated;
+use util::{strip_bom_str, write_file_atomic, CountingWriter};
+#[cfg(feature = "compression")]
+use Compression;
+use {Error, ErrorKind, Result};
 
 /// Converts from the TOML file to a value of `T` type.
 pub fn from_toml_file<T, P>(path: P) -> Result<T>
@@ -18,19 +33,262 @@ where
     track!(from_toml_reader(f))
 }
 
+/// Converts from the TOML file to a value of `T` type.
+///
+/// If the file does not exist, `T::default()` is returned instead.
+/// Other I/O errors and parse errors are propagated as usual.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Debug, Default, Deserialize)]
+/// struct Foo {
+///     bar: usize,
+/// }
+///
+/// # fn main() {
+/// let dir = std::env::temp_dir();
+///
+/// // Absent file
+/// let path = dir.join("serdeconv_doctest_from_toml_file_or_default_absent.toml");
+/// let foo: Foo = serdeconv::from_toml_file_or_default(&path).unwrap();
+/// assert_eq!(foo.bar, 0);
+///
+/// // Malformed file
+/// let path = dir.join("serdeconv_doctest_from_toml_file_or_default_malformed.toml");
+/// std::fs::write(&path, "bar = ").unwrap();
+/// assert!(serdeconv::from_toml_file_or_default::<Foo, _>(&path).unwrap_err().is_invalid());
+///
+/// // Valid file
+/// let path = dir.join("serdeconv_doctest_from_toml_file_or_default_valid.toml");
+/// std::fs::write(&path, "bar = 10").unwrap();
+/// let foo: Foo = serdeconv::from_toml_file_or_default(&path).unwrap();
+/// assert_eq!(foo.bar, 10);
+/// # }
+/// ```
+pub fn from_toml_file_or_default<T, P>(path: P) -> Result<T>
+where
+    T: for<'a> Deserialize<'a> + Default,
+    P: AsRef<Path>,
+{
+    match from_toml_file(path) {
+        Err(ref e) if e.is_not_found() => Ok(T::default()),
+        result => track!(result),
+    }
+}
+
+/// Converts from the TOML file to a value of `T` type, resolving include directives.
+///
+/// An include directive is an inline table that consists of the single `$include` key
+/// (e.g., `foo = { "$include" = "foo.toml" }`).
+/// It is replaced by the parsed contents of the specified file.
+/// Relative paths are resolved against the directory of the including file.
+///
+/// Include directives are resolved recursively up to `max_depth` levels.
+/// If the limit is exceeded or an include cycle is detected, an `ErrorKind::Invalid` error is returned.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Config {
+///     name: String,
+///     server: Server,
+/// }
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Server {
+///     port: u16,
+/// }
+///
+/// # fn main() {
+/// let dir = std::env::temp_dir().join("serdeconv_doctest_from_toml_file_with_includes");
+/// std::fs::create_dir_all(&dir).unwrap();
+/// std::fs::write(dir.join("main.toml"), r#"
+/// name = "foo"
+/// server = { "$include" = "server.toml" }
+/// "#).unwrap();
+/// std::fs::write(dir.join("server.toml"), "port = 8080").unwrap();
+///
+/// let config: Config = serdeconv::from_toml_file_with_includes(dir.join("main.toml"), 8).unwrap();
+/// assert_eq!(config.name, "foo");
+/// assert_eq!(config.server.port, 8080);
+///
+/// // Cycle
+/// std::fs::write(dir.join("server.toml"), r#"port = { "$include" = "main.toml" }"#).unwrap();
+/// let result = serdeconv::from_toml_file_with_includes::<Config, _>(dir.join("main.toml"), 8);
+/// assert!(result.unwrap_err().is_invalid());
+/// # }
+/// ```
+pub fn from_toml_file_with_includes<T, P>(path: P, max_depth: usize) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    P: AsRef<Path>,
+{
+    let value = track!(load_toml_with_includes(
+        path.as_ref(),
+        &mut Vec::new(),
+        max_depth
+    ))?;
+    track!(from_toml_value(value))
+}
+
+/// Converts from the user TOML file overlaid on the defaults TOML file to a value of `T` type.
+///
+/// Both files are parsed as TOML documents and deep-merged before the deserialization:
+/// tables are merged recursively, and for the other values (including arrays and arrays of tables)
+/// the one in the user file replaces the one in the defaults file as a whole.
+///
+/// If the user file does not exist, the value is converted from the defaults file only.
+/// The defaults file must exist.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Config {
+///     name: String,
+///     hosts: Vec<String>,
+///     server: Server,
+/// }
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Server {
+///     addr: String,
+///     port: u16,
+/// }
+///
+/// # fn main() {
+/// let dir = std::env::temp_dir().join("serdeconv_doctest_from_toml_with_defaults");
+/// std::fs::create_dir_all(&dir).unwrap();
+/// std::fs::write(dir.join("defaults.toml"), r#"
+/// name = "default"
+/// hosts = ["a", "b"]
+///
+/// [server]
+/// addr = "127.0.0.1"
+/// port = 80
+/// "#).unwrap();
+/// std::fs::write(dir.join("config.toml"), r#"
+/// hosts = ["c"]
+///
+/// [server]
+/// port = 8080
+/// "#).unwrap();
+///
+/// let config: Config =
+///     serdeconv::from_toml_with_defaults(dir.join("defaults.toml"), dir.join("config.toml")).unwrap();
+/// assert_eq!(config.name, "default");
+/// assert_eq!(config.hosts, ["c"]);
+/// assert_eq!(config.server.addr, "127.0.0.1");
+/// assert_eq!(config.server.port, 8080);
+///
+/// // Missing user file
+/// let config: Config =
+///     serdeconv::from_toml_with_defaults(dir.join("defaults.toml"), dir.join("none.toml")).unwrap();
+/// assert_eq!(config.server.port, 80);
+/// # }
+/// ```
+pub fn from_toml_with_defaults<T, P, Q>(defaults_path: P, user_path: Q) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let defaults_path = defaults_path.as_ref();
+    let user_path = user_path.as_ref();
+    let mut value: toml::Value = track!(from_toml_file(defaults_path); defaults_path)?;
+    match from_toml_file(user_path) {
+        Err(ref e) if e.is_not_found() => {}
+        result => {
+            let user = track!(result; user_path)?;
+            merge_toml_value(&mut value, user);
+        }
+    }
+    track!(from_toml_value(value))
+}
+
 /// Reads a TOML string from the reader and converts it to a value of `T` type.
+///
+/// If the input is not a valid UTF-8 sequence,
+/// an `ErrorKind::Utf8` error holding the offset of the first invalid byte is returned.
+///
+/// # Examples
+///
+/// ```
+/// use serdeconv::ErrorKind;
+///
+/// let error = serdeconv::from_toml_reader::<(), _>(&b"foo = \"\xff\""[..]).unwrap_err();
+/// assert_eq!(*error.kind(), ErrorKind::Utf8 { offset: 7 });
+/// assert!(error.is_invalid());
+/// ```
 pub fn from_toml_reader<T, R>(mut reader: R) -> Result<T>
 where
     T: for<'a> Deserialize<'a>,
     R: Read,
 {
-    let mut toml = String::new();
-    track!(reader.read_to_string(&mut toml).map_err(Error::from))?;
-    track!(from_toml_str(&toml))
+    let mut toml = Vec::new();
+    track!(reader.read_to_end(&mut toml).map_err(Error::from))?;
+    track!(from_toml_slice(&toml))
+}
+
+/// Reads a TOML string of at most `max_bytes` bytes from the reader and
+/// converts it to a value of `T` type.
+///
+/// If the reader yields more than `max_bytes` bytes,
+/// an `ErrorKind::Invalid` error is returned instead of silently truncating the input.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Foo {
+///     bar: usize,
+/// }
+///
+/// # fn main() {
+/// let foo: Foo = serdeconv::from_toml_reader_size_limited(&b"bar = 1"[..], 7).unwrap();
+/// assert_eq!(foo.bar, 1);
+///
+/// let error = serdeconv::from_toml_reader_size_limited::<Foo, _>(&b"bar = 1"[..], 6).unwrap_err();
+/// assert!(error.is_invalid());
+/// assert!(error.to_string().contains("Input size limit exceeded"));
+/// # }
+/// ```
+pub fn from_toml_reader_size_limited<T, R>(reader: R, max_bytes: usize) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    R: Read,
+{
+    track!(TomlOptions::new().max_bytes(max_bytes).from_reader(reader))
 }
 
 /// Converts from the TOML string to a value of `T` type.
 ///
+/// A leading UTF-8 BOM (`U+FEFF`) is skipped,
+/// as some editors on Windows prepend it to the files they save.
+/// The same applies to the other TOML input functions such as `from_toml_reader` and `from_toml_slice`.
+///
 /// # Examples
 ///
 /// ```
@@ -55,46 +313,121 @@ where
 /// let foo: Foo = serdeconv::from_toml_str(toml).unwrap();
 /// assert_eq!(foo.bar, "aaa");
 /// assert_eq!(foo.baz, 123);
+///
+/// // A leading BOM is skipped.
+/// let foo: Foo = serdeconv::from_toml_str("\u{FEFF}bar = \"bbb\"\nbaz = 456").unwrap();
+/// assert_eq!(foo.bar, "bbb");
 /// # }
 /// ```
 pub fn from_toml_str<T>(toml: &str) -> Result<T>
 where
     T: DeserializeOwned,
 {
-    let value = track!(toml::from_str(toml).map_err(Error::from))?;
+    let value = track!(toml::from_str(strip_bom_str(toml)).map_err(Error::from))?;
     Ok(value)
 }
 
-/// Converts from the TOML bytes to a value of `T` type.
-pub fn from_toml_slice<T>(toml: &[u8]) -> Result<T>
+/// Converts from the TOML string, which is either borrowed or owned, to a value of `T` type.
+///
+/// Unlike `from_toml_str`, `T` is bounded by the lifetime `'a` of `toml`
+/// (i.e., `T: Deserialize<'a>` rather than `T: DeserializeOwned`),
+/// so this can be called from generic code handling both borrowed and owned inputs.
+/// Note, however, that the TOML parser never lends out the input:
+/// `T` receives owned data whether `toml` is borrowed or owned,
+/// so `Cow<'a, str>` fields become owned values and `&'a str` fields result in an `ErrorKind::Invalid` error.
+///
+/// # Examples
+///
+/// ```
+/// use std::borrow::Cow;
+/// use std::collections::BTreeMap;
+///
+/// let toml = "foo = \"bar\"";
+/// let map: BTreeMap<Cow<str>, Cow<str>> = serdeconv::from_toml_cow(Cow::Borrowed(toml)).unwrap();
+/// assert_eq!(map["foo"], "bar");
+///
+/// let map: BTreeMap<String, String> = serdeconv::from_toml_cow(Cow::Owned(toml.to_owned())).unwrap();
+/// assert_eq!(map["foo"], "bar");
+/// ```
+pub fn from_toml_cow<'a, T>(toml: Cow<'a, str>) -> Result<T>
 where
-    T: DeserializeOwned,
+    T: Deserialize<'a>,
 {
-    track!(from_toml_str(str::from_utf8(toml).map_err(Error::from)?))
+    let value =
+        track!(T::deserialize(toml::Deserializer::new(strip_bom_str(&toml))).map_err(Error::from))?;
+    Ok(value)
 }
 
-/// Converts the value to a TOML string and writes it to the speficied file.
-pub fn to_toml_file<T, P>(value: &T, path: P) -> Result<()>
+/// Converts from the TOML string to a value of `T` type, and then validates it.
+///
+/// If `T::validate` fails, the error is returned as an `ErrorKind::Invalid` error.
+/// See the documentation of `FromTomlValidated` for an example.
+pub fn from_toml_str_validated<T>(toml: &str) -> Result<T>
 where
-    T: ?Sized + Serialize,
-    P: AsRef<Path>,
+    T: FromTomlValidated,
 {
-    let f = track!(File::create(path).map_err(Error::from))?;
-    track!(to_toml_writer(value, f))
+    let value: T = track!(from_toml_str(toml))?;
+    track!(value
+        .validate()
+        .map_err(|e| Error::from(ErrorKind::Invalid.takes_over(e))))?;
+    Ok(value)
 }
 
-/// Converts the value to a TOML string and writes it to the writer.
-pub fn to_toml_writer<T, W>(value: &T, mut writer: W) -> Result<()>
+/// Converts from the TOML string to a value of `T` type.
+///
+/// If the string is empty or consists only of whitespace (as determined by `str::trim`),
+/// `T::default()` is returned instead. Note that a string containing only comments is not regarded as empty.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Default, Deserialize)]
+/// struct Foo {
+///     bar: usize,
+/// }
+///
+/// # fn main() {
+/// let foo: Foo = serdeconv::from_toml_str_or_default(" \n").unwrap();
+/// assert_eq!(foo.bar, 0);
+///
+/// let foo: Foo = serdeconv::from_toml_str_or_default("bar = 1").unwrap();
+/// assert_eq!(foo.bar, 1);
+/// # }
+/// ```
+pub fn from_toml_str_or_default<T>(toml: &str) -> Result<T>
 where
-    T: ?Sized + Serialize,
-    W: Write,
+    T: DeserializeOwned + Default,
 {
-    let toml = track!(to_toml_string(value).map_err(Error::from))?;
-    track!(writer.write_all(toml.as_bytes()).map_err(Error::from))?;
-    Ok(())
+    if toml.trim().is_empty() {
+        Ok(T::default())
+    } else {
+        track!(from_toml_str(toml))
+    }
 }
 
-/// Converts the value to a TOML string.
+/// Converts from the TOML string to a value of `T` type,
+/// rejecting keys that are not consumed by `T`.
+///
+/// This behaves like `#[serde(deny_unknown_fields)]` applied recursively,
+/// but also works for types that cannot be annotated (e.g., third-party types).
+/// If stray keys are found, an `ErrorKind::Invalid` error listing them is returned.
+///
+/// # Performance
+///
+/// This is a two-pass conversion: the input is parsed into a `toml::Value`,
+/// deserialized into `T`, then `T` is serialized back into a `toml::Value`
+/// and both trees are compared.
+/// Hence it is noticeably slower than `from_toml_str` and
+/// should be used for loading configurations rather than in hot paths.
+///
+/// Note that fields skipped during serialization
+/// (e.g., `#[serde(skip_serializing_if = "...")]`) are reported as stray keys
+/// if they appear in the input.
 ///
 /// # Examples
 ///
@@ -104,27 +437,1090 @@ where
 /// extern crate serde_derive;
 /// extern crate serdeconv;
 ///
-/// // Defines a serializable struct.
-/// #[derive(Serialize)]
+/// #[derive(Serialize, Deserialize)]
 /// struct Foo {
-///     bar: &'static str,
-///     baz: usize
+///     bar: String,
 /// }
 ///
 /// # fn main() {
-/// // Converts the `Foo` value to a TOML string.
-/// let foo = Foo { bar: "aaa", baz: 123 };
-/// let toml = serdeconv::to_toml_string(&foo).unwrap();
-/// assert_eq!(toml, "\
-/// bar = \"aaa\"
+/// let foo: Foo = serdeconv::from_toml_str_strict(r#"bar = "aaa""#).unwrap();
+/// assert_eq!(foo.bar, "aaa");
+///
+/// let toml = r#"
+/// bar = "aaa"
 /// baz = 123
-/// ");
+/// "#;
+/// assert!(serdeconv::from_toml_str_strict::<Foo>(toml).is_err());
 /// # }
 /// ```
-pub fn to_toml_string<T>(value: &T) -> Result<String>
+pub fn from_toml_str_strict<T>(toml: &str) -> Result<T>
 where
-    T: ?Sized + Serialize,
+    T: DeserializeOwned + Serialize,
 {
-    let toml = track!(toml::to_string(value).map_err(Error::from))?;
-    Ok(toml)
+    let (value, stray) = track!(from_toml_str_warn_unknown(toml))?;
+    track_assert!(
+        stray.is_empty(),
+        ErrorKind::Invalid,
+        "Unknown keys: {:?}",
+        stray
+    );
+    Ok(value)
+}
+
+/// Converts from the TOML string to a value of `T` type,
+/// also returning the keys that are not consumed by `T`.
+///
+/// This is the non-fatal counterpart of `from_toml_str_strict`:
+/// the stray keys are returned (e.g., for logging deprecated settings) instead of being rejected.
+/// Nested keys are reported by dotted paths (e.g., `"server.timeout"`).
+///
+/// The same two-pass approach as `from_toml_str_strict` is used, so it has the following limitations:
+///
+/// - Fields skipped during serialization (e.g., `#[serde(skip_serializing_if = "...")]`)
+///   are reported as stray keys if they appear in the input.
+/// - A `#[serde(flatten)]` map field (e.g., `HashMap<String, toml::Value>`) absorbs every unknown key
+///   of its struct, so such keys are never reported.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Config {
+///     name: String,
+///     server: Server,
+/// }
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Server {
+///     port: u16,
+/// }
+///
+/// # fn main() {
+/// let toml = r#"
+/// name = "foo"
+/// debug = true
+///
+/// [server]
+/// port = 80
+/// timeout = 30
+/// "#;
+/// let (config, unknown) = serdeconv::from_toml_str_warn_unknown::<Config>(toml).unwrap();
+/// assert_eq!(config.server.port, 80);
+/// assert_eq!(unknown, ["debug", "server.timeout"]);
+///
+/// let (_, unknown) =
+///     serdeconv::from_toml_str_warn_unknown::<Config>("name = \"foo\"\n[server]\nport = 80").unwrap();
+/// assert!(unknown.is_empty());
+/// # }
+/// ```
+pub fn from_toml_str_warn_unknown<T>(toml: &str) -> Result<(T, Vec<String>)>
+where
+    T: DeserializeOwned + Serialize,
+{
+    let input: toml::Value = track!(from_toml_str(toml))?;
+    let value: T = track!(input.clone().try_into().map_err(Error::from))?;
+    let output = track!(toml::Value::try_from(&value).map_err(Error::from))?;
+
+    let mut stray = Vec::new();
+    collect_stray_keys(&input, &output, "", &mut stray);
+    Ok((value, stray))
+}
+
+/// Converts from the TOML string to a value of `T` type,
+/// also returning the names of the fields which were absent in the input (thus defaulted).
+///
+/// Nested fields are reported by dotted paths (e.g., `"server.port"`).
+///
+/// This is a heuristic based on the same two-pass approach as `from_toml_str_strict`:
+/// the keys in the input are compared with the ones obtained by serializing the resulting value.
+/// Hence, it has the following limitations:
+///
+/// - Fields that are not serialized (e.g., `None` values or `#[serde(skip_serializing)]` fields)
+///   are never reported.
+/// - The keys of a `#[serde(flatten)]` field cannot be distinguished from the ones of the parent struct.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Foo {
+///     bar: String,
+///     #[serde(default)]
+///     baz: usize,
+/// }
+///
+/// # fn main() {
+/// let (foo, defaulted) = serdeconv::from_toml_str_with_report::<Foo>(r#"bar = "aaa""#).unwrap();
+/// assert_eq!(foo.bar, "aaa");
+/// assert_eq!(defaulted, ["baz"]);
+/// # }
+/// ```
+pub fn from_toml_str_with_report<T>(toml: &str) -> Result<(T, Vec<String>)>
+where
+    T: DeserializeOwned + Serialize,
+{
+    let input: toml::Value = track!(from_toml_str(toml))?;
+    let value: T = track!(input.clone().try_into().map_err(Error::from))?;
+    let output = track!(toml::Value::try_from(&value).map_err(Error::from))?;
+
+    let mut defaulted = Vec::new();
+    collect_stray_keys(&output, &input, "", &mut defaulted);
+    Ok((value, defaulted))
+}
+
+/// Converts from the TOML string to a value of `T` type, collecting as many errors as possible.
+///
+/// Unlike `from_toml_str` which stops at the first error, this reports every top-level field
+/// of the struct `T` which is missing (and required), has a mismatched type, or is unknown
+/// (if `#[serde(deny_unknown_fields)]` is specified).
+/// Each error is tracked with the name of the offending field.
+///
+/// The scope of the collection is limited as follows:
+///
+/// - Only the top-level fields are inspected; an error in a nested struct is reported once,
+///   as the error of the top-level field containing it.
+/// - Each field is checked independently, so struct-level validations (e.g., `#[serde(try_from)]`)
+///   and flattened fields are not supported.
+/// - If `T` is not a struct, or the errors cannot be attributed to the fields,
+///   the result is the single error returned by `from_toml_str`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Foo {
+///     bar: String,
+///     baz: usize,
+///     qux: bool,
+/// }
+///
+/// # fn main() {
+/// let errors = serdeconv::from_toml_str_collect_errors::<Foo>(r#"baz = "aaa""#).unwrap_err();
+/// assert_eq!(errors.len(), 3); // `bar` and `qux` are missing, and `baz` is not an integer
+/// # }
+/// ```
+pub fn from_toml_str_collect_errors<T>(toml: &str) -> ::std::result::Result<T, Vec<Error>>
+where
+    T: DeserializeOwned,
+{
+    let input: toml::Value = track!(from_toml_str(toml)).map_err(|e| vec![e])?;
+    let e = match track!(from_toml_value(input.clone())) {
+        Ok(value) => return Ok(value),
+        Err(e) => e,
+    };
+    let errors = input
+        .as_table()
+        .and_then(|table| probe::collect_field_errors::<T>(table));
+    Err(errors.unwrap_or_else(|| vec![e]))
+}
+
+/// Converts from the TOML bytes to a value of `T` type.
+pub fn from_toml_slice<T>(toml: &[u8]) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    track!(from_toml_str(str::from_utf8(toml).map_err(Error::from)?))
+}
+
+/// Converts from the TOML bytes to a value of `T` type, accepting anything byte-slice-like
+/// (e.g., `Vec<u8>`, `&[u8]`, arrays and `Box<[u8]>`).
+///
+/// This is the same as `from_toml_slice(bytes.as_ref())`.
+/// Since `bytes` may be an owned buffer that is dropped when this function returns,
+/// `T` cannot borrow from the input; use `from_toml_slice` to deserialize borrowing values.
+///
+/// # Examples
+///
+/// ```
+/// let bytes: Vec<u8> = b"foo = 1".to_vec();
+/// let value: std::collections::BTreeMap<String, u8> = serdeconv::from_toml_bytes(bytes).unwrap();
+/// assert_eq!(value, vec![("foo".to_owned(), 1)].into_iter().collect());
+/// ```
+pub fn from_toml_bytes<T, B>(bytes: B) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    B: AsRef<[u8]>,
+{
+    track!(from_toml_slice(bytes.as_ref()))
+}
+
+/// Converts from the `toml::Value` to a value of `T` type.
+pub fn from_toml_value<T>(toml: toml::Value) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let value = track!(toml.try_into().map_err(Error::from))?;
+    Ok(value)
+}
+
+/// Converts the value to a TOML string and writes it to the speficied file.
+pub fn to_toml_file<T, P>(value: &T, path: P) -> Result<()>
+where
+    T: ?Sized + Serialize,
+    P: AsRef<Path>,
+{
+    let f = track!(File::create(path).map_err(Error::from))?;
+    track!(to_toml_writer(value, f))
+}
+
+/// Converts the value to a TOML string and writes it to the specified file atomically.
+///
+/// The output is first written to a temporary file in the same directory,
+/// which is flushed and synced to disk, and then renamed to `path`.
+/// Thus, a crash during the write never leaves a truncated file at `path`.
+/// If the serialization fails, the temporary file is removed.
+///
+/// Note that the atomicity relies on the underlying platform and file system:
+/// renaming is atomic on POSIX file systems, but may not be on some platforms
+/// (e.g., network file systems or Windows when the target file is opened by another process).
+pub fn to_toml_file_atomic<T, P>(value: &T, path: P) -> Result<()>
+where
+    T: ?Sized + Serialize,
+    P: AsRef<Path>,
+{
+    track!(write_file_atomic(path.as_ref(), |f| track!(
+        to_toml_writer(value, f)
+    )))
+}
+
+/// Appends the value to the array of tables named `array_key` in the specified TOML file.
+///
+/// The file is parsed (or treated as an empty document if it does not exist),
+/// the value is pushed to the `[[array_key]]` array (which is created if absent),
+/// and the whole document is rewritten atomically (see `to_toml_file_atomic`).
+///
+/// If `array_key` exists but is not an array, or the value is not serialized as a table,
+/// an `ErrorKind::Invalid` error is returned and the file is left untouched.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Debug, Deserialize, Serialize)]
+/// struct Entry {
+///     user: String,
+/// }
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Log {
+///     entry: Vec<Entry>,
+/// }
+///
+/// # fn main() {
+/// let path = std::env::temp_dir().join("serdeconv_doctest_append_toml_table.toml");
+/// let _ = std::fs::remove_file(&path);
+///
+/// // Create
+/// serdeconv::append_toml_table(&path, "entry", &Entry { user: "foo".to_owned() }).unwrap();
+/// let log: Log = serdeconv::from_toml_file(&path).unwrap();
+/// assert_eq!(log.entry.len(), 1);
+///
+/// // Append
+/// serdeconv::append_toml_table(&path, "entry", &Entry { user: "bar".to_owned() }).unwrap();
+/// let log: Log = serdeconv::from_toml_file(&path).unwrap();
+/// assert_eq!(log.entry.len(), 2);
+/// assert_eq!(log.entry[1].user, "bar");
+///
+/// // Type conflict
+/// std::fs::write(&path, "entry = 1").unwrap();
+/// let result = serdeconv::append_toml_table(&path, "entry", &Entry { user: "baz".to_owned() });
+/// assert!(result.unwrap_err().is_invalid());
+/// assert_eq!(std::fs::read_to_string(&path).unwrap(), "entry = 1");
+/// # }
+/// ```
+pub fn append_toml_table<T, P>(path: P, array_key: &str, value: &T) -> Result<()>
+where
+    T: ?Sized + Serialize,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let mut document: toml::value::Table = track!(from_toml_file_or_default(path))?;
+    let table = track!(to_toml_value(value))?;
+    track_assert!(
+        table.is_table(),
+        ErrorKind::Invalid,
+        "Not a table: {}",
+        table.type_str()
+    );
+
+    let array = document
+        .entry(array_key.to_owned())
+        .or_insert_with(|| toml::Value::Array(Vec::new()));
+    match *array {
+        toml::Value::Array(ref mut array) => array.push(table),
+        ref other => track_panic!(
+            ErrorKind::Invalid,
+            "Not an array: key={:?}, type={}",
+            array_key,
+            other.type_str()
+        ),
+    }
+    track!(to_toml_file_atomic(&document, path))
+}
+
+/// Converts the value to a TOML string and writes it to the writer.
+///
+/// Note that, unlike `to_json_writer` and `to_msgpack_writer`, this does not stream the output:
+/// the whole TOML document is built in memory and then written to the writer at once,
+/// so the peak memory usage is proportional to the size of the output.
+/// This is because the underlying `toml` serializer only writes into a `String`
+/// (it has to defer the tables until all of the plain key/value pairs of their parent are emitted).
+pub fn to_toml_writer<T, W>(value: &T, mut writer: W) -> Result<()>
+where
+    T: ?Sized + Serialize,
+    W: Write,
+{
+    let toml = track!(to_toml_string(value))?;
+    track!(writer.write_all(toml.as_bytes()).map_err(Error::from))?;
+    Ok(())
+}
+
+/// Converts the value to a TOML string, writes it to the writer and flushes the writer.
+///
+/// Returns the number of bytes written.
+pub fn to_toml_writer_counted<T, W>(value: &T, writer: W) -> Result<usize>
+where
+    T: ?Sized + Serialize,
+    W: Write,
+{
+    let mut writer = CountingWriter::new(writer);
+    track!(to_toml_writer(value, &mut writer))?;
+    track!(writer.flush().map_err(Error::from))?;
+    Ok(writer.count())
+}
+
+/// Converts the value to a TOML string.
+///
+/// Keys that cannot be written as bare keys (e.g., keys containing dots or spaces, and empty keys)
+/// are quoted, so the resulting string can always be read back by `from_toml_str`.
+///
+/// TOML requires the key/value pairs of a table to precede its sub-tables,
+/// so the fields of a struct are reordered if needed:
+/// the fields written as key/value pairs (including inline arrays) come first, followed by
+/// the fields written as tables and arrays of tables.
+/// The relative order within each group follows the declaration order of the fields.
+///
+/// Strings are written as literal strings, basic strings or multi-line basic strings (if they contain newlines),
+/// and control characters are escaped as TOML requires, so any string can be read back as is.
+///
+/// Non-finite floats are emitted as `nan`, `inf` and `-inf` as the TOML specification allows.
+/// Use `to_toml_string_finite` to reject them instead.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// // Defines a serializable struct.
+/// #[derive(Serialize)]
+/// struct Foo {
+///     bar: &'static str,
+///     baz: usize
+/// }
+///
+/// # fn main() {
+/// // Converts the `Foo` value to a TOML string.
+/// let foo = Foo { bar: "aaa", baz: 123 };
+/// let toml = serdeconv::to_toml_string(&foo).unwrap();
+/// assert_eq!(toml, "\
+/// bar = \"aaa\"
+/// baz = 123
+/// ");
+///
+/// // The root of a TOML document must be a table.
+/// let error = serdeconv::to_toml_string(&vec![1, 2, 3]).unwrap_err();
+/// assert!(error.is_invalid());
+/// assert!(error.to_string().contains("TOML requires a table at the root, but the value is array"));
+///
+/// let error = serdeconv::to_toml_string(&123).unwrap_err();
+/// assert!(error.to_string().contains("TOML requires a table at the root, but the value is integer"));
+///
+/// // Keys are quoted if needed.
+/// let mut map = std::collections::BTreeMap::new();
+/// map.insert("a.b", 1);
+/// map.insert("with space", 2);
+/// map.insert("", 3);
+/// map.insert("bare_key", 4);
+/// let toml = serdeconv::to_toml_string(&map).unwrap();
+/// assert_eq!(toml, "\"\" = 3\n\"a.b\" = 1\nbare_key = 4\n\"with space\" = 2\n");
+///
+/// let nested: std::collections::BTreeMap<_, _> = vec![("a.b", map)].into_iter().collect();
+/// let toml = serdeconv::to_toml_string(&nested).unwrap();
+/// assert!(toml.starts_with("[\"a.b\"]\n"));
+/// let value: std::collections::BTreeMap<String, std::collections::BTreeMap<String, u8>> =
+///     serdeconv::from_toml_str(&toml).unwrap();
+/// assert_eq!(value["a.b"]["with space"], 2);
+/// assert_eq!(value["a.b"][""], 3);
+/// # }
+/// ```
+///
+/// Sub-tables are written after the key/value pairs, regardless of the declaration order:
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Serialize)]
+/// struct Config {
+///     server: Server,
+///     name: &'static str,
+///     ports: Vec<u16>,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct Server {
+///     host: &'static str,
+/// }
+///
+/// # fn main() {
+/// let config = Config {
+///     server: Server { host: "localhost" },
+///     name: "foo",
+///     ports: vec![80, 443],
+/// };
+/// assert_eq!(
+///     serdeconv::to_toml_string(&config).unwrap(),
+///     "name = \"foo\"\nports = [80, 443]\n\n[server]\nhost = \"localhost\"\n"
+/// );
+/// # }
+/// ```
+///
+/// Strings containing newlines, control characters and non-ASCII characters are round-tripped:
+///
+/// ```
+/// use serdeconv::TomlFormat;
+/// use std::collections::BTreeMap;
+///
+/// let strings = [
+///     "a\nb", "a\r\nb", "a\tb", "a\rb", "a\0b", "\u{1b}[0m\u{7f}", "\u{8}\u{c}",
+///     "\"\"\"'''\n\\", "trailing\\", "日本語 😀",
+/// ];
+/// let format = TomlFormat {
+///     indent: "  ".to_owned(),
+///     ..TomlFormat::default()
+/// };
+/// for s in &strings {
+///     let mut map = BTreeMap::new();
+///     map.insert("s", *s);
+///
+///     let toml = serdeconv::to_toml_string(&map).unwrap();
+///     let value: BTreeMap<String, String> = serdeconv::from_toml_str(&toml).unwrap();
+///     assert_eq!(value["s"], *s);
+///
+///     let toml = serdeconv::to_toml_string_with(&map, &format).unwrap();
+///     let value: BTreeMap<String, String> = serdeconv::from_toml_str(&toml).unwrap();
+///     assert_eq!(value["s"], *s);
+/// }
+/// ```
+pub fn to_toml_string<T>(value: &T) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    match toml::to_string(value) {
+        Ok(toml) => Ok(toml),
+        Err(e) => {
+            if let Ok(root) = toml::Value::try_from(value) {
+                track_assert!(
+                    root.is_table(),
+                    ErrorKind::Invalid,
+                    "TOML requires a table at the root, but the value is {} \
+                     (wrap it in a struct or a map, e.g., `{{ items = [1, 2, 3] }}`)",
+                    root.type_str()
+                );
+            }
+            Err(track!(Error::from(e)))
+        }
+    }
+}
+
+/// Converts the value to a TOML string, failing if the value contains non-finite floats.
+///
+/// Although TOML supports `nan` and `inf`, some parsers reject them.
+/// This function returns an `ErrorKind::Invalid` error indicating the path to
+/// the offending float instead of emitting them. See also `check_finite_floats`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Serialize)]
+/// struct Foo {
+///     bar: f64,
+///     baz: f64,
+/// }
+///
+/// # fn main() {
+/// let foo = Foo { bar: std::f64::NAN, baz: std::f64::INFINITY };
+/// assert_eq!(serdeconv::to_toml_string(&foo).unwrap(), "bar = nan\nbaz = inf\n");
+///
+/// let error = serdeconv::to_toml_string_finite(&foo).unwrap_err();
+/// assert!(error.is_invalid());
+/// assert!(error.to_string().contains("bar"));
+///
+/// let foo = Foo { bar: 1.0, baz: std::f64::INFINITY };
+/// let error = serdeconv::to_toml_string_finite(&foo).unwrap_err();
+/// assert!(error.to_string().contains("baz"));
+/// # }
+/// ```
+pub fn to_toml_string_finite<T>(value: &T) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    let toml = track!(to_toml_value(value))?;
+    track!(check_finite_floats(&toml))?;
+    track!(to_toml_string(&toml))
+}
+
+/// Converts the value to a TOML bytes.
+pub fn to_toml_vec<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: ?Sized + Serialize,
+{
+    let toml = track!(to_toml_string(value))?;
+    Ok(toml.into_bytes())
+}
+
+/// Options for controlling the layout of TOML strings generated by `to_toml_string_with`.
+///
+/// The default value produces the same output as `to_toml_string`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TomlFormat {
+    /// Indentation string for nested entries.
+    ///
+    /// Entries in a table nested at depth `n` are indented by `n` times this string
+    /// (and the header of the table by `n - 1` times).
+    ///
+    /// The default value is `""`.
+    pub indent: String,
+
+    /// Whether arrays of tables are expanded to `[[array.of.tables]]` sections
+    /// rather than emitted as arrays of inline tables.
+    ///
+    /// The default value is `true`.
+    pub expand_array_of_tables: bool,
+
+    /// The maximum number of entries of a table emitted as an inline table.
+    ///
+    /// Only tables that do not contain sub-tables (after applying this rule to them) are inlined.
+    /// `0` means that tables are never inlined.
+    ///
+    /// The default value is `0`.
+    pub inline_table_max_entries: usize,
+}
+impl Default for TomlFormat {
+    fn default() -> Self {
+        TomlFormat {
+            indent: String::new(),
+            expand_array_of_tables: true,
+            inline_table_max_entries: 0,
+        }
+    }
+}
+
+/// Converts the value to a TOML string formatted according to `format`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// use serdeconv::TomlFormat;
+/// use std::collections::BTreeMap;
+///
+/// #[derive(Serialize)]
+/// struct Foo {
+///     items: Vec<Item>,
+///     meta: BTreeMap<&'static str, usize>,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct Item {
+///     name: &'static str,
+/// }
+///
+/// # fn main() {
+/// let mut meta = BTreeMap::new();
+/// meta.insert("version", 1);
+/// let foo = Foo {
+///     items: vec![Item { name: "a" }, Item { name: "b" }],
+///     meta,
+/// };
+///
+/// // Array-of-tables expansion (default)
+/// let toml = serdeconv::to_toml_string_with(&foo, &TomlFormat::default()).unwrap();
+/// assert_eq!(toml, serdeconv::to_toml_string(&foo).unwrap());
+/// assert_eq!(toml, "\
+/// [[items]]
+/// name = \"a\"
+///
+/// [[items]]
+/// name = \"b\"
+///
+/// [meta]
+/// version = 1
+/// ");
+///
+/// // Inline arrays and tables
+/// let format = TomlFormat {
+///     expand_array_of_tables: false,
+///     inline_table_max_entries: 1,
+///     ..TomlFormat::default()
+/// };
+/// let toml = serdeconv::to_toml_string_with(&foo, &format).unwrap();
+/// assert_eq!(toml, "\
+/// items = [{ name = \"a\" }, { name = \"b\" }]
+/// meta = { version = 1 }
+/// ");
+///
+/// // `0` (the default) means that tables are never inlined, even if they are empty
+/// let empty: serdeconv::Value = serdeconv::from_json_str(r#"{"x": {"a": {}}}"#).unwrap();
+/// let format = TomlFormat {
+///     expand_array_of_tables: false,
+///     ..TomlFormat::default()
+/// };
+/// let toml = serdeconv::to_toml_string_with(&empty, &format).unwrap();
+/// assert_eq!(toml, "[x.a]\n");
+///
+/// // Indentation
+/// let format = TomlFormat {
+///     indent: "  ".to_owned(),
+///     ..TomlFormat::default()
+/// };
+/// let toml = serdeconv::to_toml_string_with(&foo, &format).unwrap();
+/// assert_eq!(toml, "\
+/// [[items]]
+///   name = \"a\"
+///
+/// [[items]]
+///   name = \"b\"
+///
+/// [meta]
+///   version = 1
+/// ");
+/// # }
+/// ```
+pub fn to_toml_string_with<T>(value: &T, format: &TomlFormat) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    track!(TomlOptions::new().format(format.clone()).to_string(value))
+}
+
+/// Converts the value to a TOML string in which the keys of all tables are sorted.
+///
+/// The output is byte-stable regardless of the iteration order of maps (e.g., `HashMap`) in the value.
+///
+/// This requires two serialization passes: the value is first serialized to a `toml::Value`,
+/// the keys of the tables (including ones nested in other tables or arrays) are sorted recursively,
+/// and then the resulting tree is serialized to a string.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// let mut map = HashMap::new();
+/// map.insert("foo", 1);
+/// map.insert("bar", 2);
+/// map.insert("baz", 3);
+/// let toml = serdeconv::to_toml_string_sorted(&map).unwrap();
+/// assert_eq!(toml, "bar = 2\nbaz = 3\nfoo = 1\n");
+/// ```
+pub fn to_toml_string_sorted<T>(value: &T) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    track!(TomlOptions::new().sort_keys(true).to_string(value))
+}
+
+/// Converts the value to a `toml::Value`.
+pub fn to_toml_value<T>(value: &T) -> Result<toml::Value>
+where
+    T: ?Sized + Serialize,
+{
+    let toml = track!(toml::Value::try_from(value).map_err(Error::from))?;
+    Ok(toml)
+}
+
+fn collect_stray_keys(
+    input: &toml::Value,
+    output: &toml::Value,
+    path: &str,
+    stray: &mut Vec<String>,
+) {
+    match (input, output) {
+        (toml::Value::Table(input), toml::Value::Table(output)) => {
+            for (key, input) in input {
+                let key_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                if let Some(output) = output.get(key) {
+                    collect_stray_keys(input, output, &key_path, stray);
+                } else {
+                    stray.push(key_path);
+                }
+            }
+        }
+        (toml::Value::Array(input), toml::Value::Array(output)) => {
+            for (i, (input, output)) in input.iter().zip(output.iter()).enumerate() {
+                collect_stray_keys(input, output, &format!("{}[{}]", path, i), stray);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Reads a TOML string from the compressed reader and converts it to a value of `T` type.
+///
+/// # Examples
+///
+/// ```
+/// use serdeconv::Compression;
+/// use std::collections::BTreeMap;
+///
+/// // Concatenated gzip members (e.g., `cat a.toml.gz b.toml.gz`) are decompressed as a whole.
+/// let mut buf = Vec::new();
+/// let a: BTreeMap<_, _> = vec![("a", 1)].into_iter().collect();
+/// serdeconv::to_toml_writer_compressed(&a, &mut buf, Compression::Gzip).unwrap();
+/// let b: BTreeMap<_, _> = vec![("b", 2)].into_iter().collect();
+/// serdeconv::to_toml_writer_compressed(&b, &mut buf, Compression::Gzip).unwrap();
+///
+/// let value: BTreeMap<String, u8> =
+///     serdeconv::from_toml_reader_compressed(&buf[..], Compression::Gzip).unwrap();
+/// assert_eq!(value.len(), 2);
+/// ```
+#[cfg(feature = "compression")]
+pub fn from_toml_reader_compressed<T, R>(reader: R, compression: Compression) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    R: Read,
+{
+    let reader = track!(compression.wrap_reader(reader))?;
+    track!(from_toml_reader(reader))
+}
+
+/// Converts the value to a TOML string and writes it to the writer with the given compression.
+#[cfg(feature = "compression")]
+pub fn to_toml_writer_compressed<T, W>(value: &T, writer: W, compression: Compression) -> Result<()>
+where
+    T: ?Sized + Serialize,
+    W: Write,
+{
+    track!(compression.write_with(writer, |w| track!(to_toml_writer(value, w))))
+}
+
+pub(crate) fn sort_toml_value(value: toml::Value) -> toml::Value {
+    match value {
+        toml::Value::Table(table) => {
+            let mut entries = table.into_iter().collect::<Vec<_>>();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            toml::Value::Table(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k, sort_toml_value(v)))
+                    .collect(),
+            )
+        }
+        toml::Value::Array(values) => {
+            toml::Value::Array(values.into_iter().map(sort_toml_value).collect())
+        }
+        value => value,
+    }
+}
+
+pub(crate) fn format_toml_table(table: &mut toml_edit::Table, format: &TomlFormat, depth: usize) {
+    for (mut key, item) in table.iter_mut() {
+        *item = match mem::take(item) {
+            toml_edit::Item::Table(mut t) => {
+                format_toml_table(&mut t, format, depth + 1);
+                if is_inlinable(&t, format) {
+                    key.fmt();
+                    toml_edit::Item::Value(t.into_inline_table().into())
+                } else {
+                    toml_edit::Item::Table(t)
+                }
+            }
+            toml_edit::Item::ArrayOfTables(mut a) => {
+                for t in a.iter_mut() {
+                    format_toml_table(t, format, depth + 1);
+                }
+                if format.expand_array_of_tables {
+                    toml_edit::Item::ArrayOfTables(a)
+                } else {
+                    key.fmt();
+                    toml_edit::Item::Value(a.into_array().into())
+                }
+            }
+            other => other,
+        };
+        if item.is_value() {
+            key.decor_mut().set_prefix(format.indent.repeat(depth));
+        }
+    }
+    if depth > 0 {
+        let prefix = table
+            .decor()
+            .prefix()
+            .and_then(|p| p.as_str())
+            .unwrap_or("")
+            .to_owned();
+        let prefix = format!("{}{}", prefix, format.indent.repeat(depth - 1));
+        table.decor_mut().set_prefix(prefix);
+    }
+}
+
+fn is_inlinable(table: &toml_edit::Table, format: &TomlFormat) -> bool {
+    format.inline_table_max_entries > 0
+        && table.len() <= format.inline_table_max_entries
+        && table.iter().all(|(_, item)| item.is_value())
+}
+
+fn merge_toml_value(base: &mut toml::Value, overlay: toml::Value) {
+    match (base, overlay) {
+        (&mut toml::Value::Table(ref mut base), toml::Value::Table(overlay)) => {
+            for (k, v) in overlay {
+                match base.get_mut(&k) {
+                    Some(b) => merge_toml_value(b, v),
+                    None => {
+                        base.insert(k, v);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+fn load_toml_with_includes(
+    path: &Path,
+    stack: &mut Vec<PathBuf>,
+    max_depth: usize,
+) -> Result<toml::Value> {
+    let path = track!(path.canonicalize().map_err(Error::from); path)?;
+    track_assert!(
+        !stack.contains(&path),
+        ErrorKind::Invalid,
+        "Include cycle detected: {:?}",
+        path
+    );
+    track_assert!(
+        stack.len() <= max_depth,
+        ErrorKind::Invalid,
+        "Maximum include depth exceeded: max_depth={}",
+        max_depth
+    );
+
+    let mut value = track!(from_toml_file(&path); path)?;
+    let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    stack.push(path);
+    track!(resolve_toml_includes(&mut value, &dir, stack, max_depth))?;
+    stack.pop();
+    Ok(value)
+}
+
+fn resolve_toml_includes(
+    value: &mut toml::Value,
+    dir: &Path,
+    stack: &mut Vec<PathBuf>,
+    max_depth: usize,
+) -> Result<()> {
+    let include = match *value {
+        toml::Value::Table(ref t) if t.len() == 1 => t
+            .get("$include")
+            .and_then(|v| v.as_str())
+            .map(|v| dir.join(v)),
+        _ => None,
+    };
+    if let Some(path) = include {
+        *value = track!(load_toml_with_includes(&path, stack, max_depth))?;
+        return Ok(());
+    }
+
+    match *value {
+        toml::Value::Table(ref mut t) => {
+            for (_, v) in t.iter_mut() {
+                track!(resolve_toml_includes(v, dir, stack, max_depth))?;
+            }
+        }
+        toml::Value::Array(ref mut a) => {
+            for v in a.iter_mut() {
+                track!(resolve_toml_includes(v, dir, stack, max_depth))?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Converts the type-erased value to a TOML string and writes it to the type-erased writer.
+///
+/// This is useful when neither the type of the value nor the one of the writer can be named
+/// (e.g., in plugin code based on dynamic dispatch).
+///
+/// Note that the generic `to_toml_writer` also accepts a `&mut dyn Write` as the writer.
+///
+/// # Examples
+///
+/// ```
+/// extern crate erased_serde;
+/// extern crate serdeconv;
+///
+/// use std::collections::BTreeMap;
+/// use std::io::Write;
+///
+/// # fn main() {
+/// let mut map = BTreeMap::new();
+/// map.insert("foo", 1);
+/// let value: &dyn erased_serde::Serialize = &map;
+///
+/// let mut buf = Vec::new();
+/// {
+///     let writer: &mut dyn Write = &mut buf;
+///     serdeconv::to_toml_dyn_writer(value, writer).unwrap();
+/// }
+/// assert_eq!(buf, b"foo = 1\n");
+/// # }
+/// ```
+#[cfg(feature = "erased-serde")]
+pub fn to_toml_dyn_writer(
+    value: &dyn erased_serde::Serialize,
+    writer: &mut dyn Write,
+) -> Result<()> {
+    track!(to_toml_writer(value, writer))
+}
+
+/// Converts from the TOML string to a value of `T` type,
+/// prepending the path of the failed field (e.g., `server.pool.max_connections`) to the error message.
+#[cfg(feature = "serde_path_to_error")]
+pub fn from_toml_str_with_path<T>(toml: &str) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let deserializer = toml::Deserializer::new(toml);
+    let value = track!(serde_path_to_error::deserialize(deserializer).map_err(Error::from))?;
+    Ok(value)
+}
+
+/// Converts from the TOML string to a value of `T` type,
+/// adding diagnostics to the error if an enum (e.g., an item of `[[items]]`) failed to match any variant.
+///
+/// Deserializing an untagged enum reports only "data did not match any variant",
+/// without telling which value was rejected.
+/// In that case, this function adds the path to the rejected value (e.g., `items[2]`),
+/// the value itself and its keys to the error history.
+///
+/// Note that serde does not expose the variants of an untagged enum to deserializers,
+/// so the variants cannot be tried one by one to tell which one came closest.
+/// Deserializing the rejected value directly into the intended variant type
+/// (e.g., with `from_toml_value`) gives the precise error.
+/// This function is only helpful for untagged and adjacently tagged enums;
+/// errors of the other enum representations already point to the offending field.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Config {
+///     items: Vec<Item>,
+/// }
+///
+/// #[derive(Debug, Deserialize)]
+/// #[serde(untagged)]
+/// enum Item {
+///     File { path: String },
+///     Url { url: String, timeout: u32 },
+/// }
+///
+/// # fn main() {
+/// let toml = r#"
+/// [[items]]
+/// path = "/tmp/foo"
+///
+/// [[items]]
+/// url = "http://example.com/"
+/// timeout = "10s"
+/// "#;
+/// let error = serdeconv::from_toml_str_enum_diagnostics::<Config>(toml).unwrap_err();
+/// assert!(error.is_invalid());
+/// let message = error.to_string();
+/// assert!(message.contains("items[1]: no variant matched the value"), "{}", message);
+/// assert!(message.contains("keys: [timeout, url]"), "{}", message);
+/// # }
+/// ```
+#[cfg(feature = "serde_path_to_error")]
+pub fn from_toml_str_enum_diagnostics<T>(toml: &str) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let deserializer = toml::Deserializer::new(toml);
+    let e = match serde_path_to_error::deserialize(deserializer) {
+        Ok(value) => return Ok(value),
+        Err(e) => e,
+    };
+    if !e.inner().to_string().contains("did not match any variant") {
+        return Err(track!(Error::from(e)));
+    }
+
+    let mut value = track!(toml::from_str::<toml::Value>(toml).map_err(Error::from))?;
+    for segment in e.path() {
+        let child = match *segment {
+            serde_path_to_error::Segment::Seq { index } => value.get(index).cloned(),
+            serde_path_to_error::Segment::Map { ref key } => value.get(key).cloned(),
+            _ => Some(value.clone()),
+        };
+        value = match child {
+            Some(child) => child,
+            None => return Err(track!(Error::from(e))),
+        };
+    }
+    let keys = value
+        .as_table()
+        .map(|t| t.keys().cloned().collect::<Vec<_>>().join(", "))
+        .unwrap_or_default();
+    let path = e.path().to_string();
+    Err(track!(
+        Error::from(e),
+        "{}: no variant matched the value {} (keys: [{}])",
+        path,
+        value,
+        keys
+    ))
 }
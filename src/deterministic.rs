@@ -0,0 +1,215 @@
+use serde::Serialize;
+
+use {to_json_string_sorted, to_msgpack_vec, to_toml_string_sorted, ErrorKind, Result};
+
+/// Converts the value to a JSON string in which the keys of every object are sorted.
+///
+/// The output does not depend on the iteration order of the maps in `value` (e.g., `HashMap`),
+/// so it is suitable for golden files and snapshot tests.
+///
+/// This is an alias of `to_json_string_sorted`, named consistently with the other deterministic serializers.
+/// Note that the value is first converted to an intermediate `serde_json::Value`,
+/// which costs an extra allocation for every node of the value.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// let mut map = HashMap::new();
+/// map.insert("foo", vec![(3, 4)].into_iter().collect::<HashMap<_, _>>());
+/// map.insert("bar", vec![(2, 1), (1, 2)].into_iter().collect());
+/// let json = serdeconv::to_json_string_deterministic(&map).unwrap();
+/// assert_eq!(json, r#"{"bar":{"1":2,"2":1},"foo":{"3":4}}"#);
+/// ```
+pub fn to_json_string_deterministic<T>(value: &T) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    track!(to_json_string_sorted(value))
+}
+
+/// Converts the value to a TOML string in which the keys of every table are sorted.
+///
+/// The output does not depend on the iteration order of the maps in `value` (e.g., `HashMap`).
+///
+/// This is an alias of `to_toml_string_sorted`, named consistently with the other deterministic serializers.
+/// Note that the value is first converted to an intermediate `toml::Value`,
+/// which costs an extra allocation for every node of the value.
+pub fn to_toml_string_deterministic<T>(value: &T) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    track!(to_toml_string_sorted(value))
+}
+
+/// Converts the value to a MessagePack bytes in which the entries of every map are sorted.
+///
+/// The entries are ordered by the encoded bytes of their keys
+/// (i.e., shorter string keys come first, and keys of the same length are ordered lexicographically).
+/// The output does not depend on the iteration order of the maps in `value` (e.g., `HashMap`).
+///
+/// Note that the value is first encoded as usual and then re-encoded with sorted maps,
+/// which costs an extra allocation for every map entry.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// let mut map = HashMap::new();
+/// for i in 0..100 {
+///     map.insert(i.to_string(), i);
+/// }
+/// let bytes = serdeconv::to_msgpack_vec_deterministic(&map).unwrap();
+///
+/// let decoded: HashMap<String, i32> = serdeconv::from_msgpack_slice(&bytes).unwrap();
+/// assert_eq!(decoded, map);
+///
+/// let map = map.into_iter().collect::<HashMap<_, _>>();
+/// assert_eq!(serdeconv::to_msgpack_vec_deterministic(&map).unwrap(), bytes);
+/// ```
+pub fn to_msgpack_vec_deterministic<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: ?Sized + Serialize,
+{
+    let bytes = track!(to_msgpack_vec(value))?;
+    let mut sorted = Vec::with_capacity(bytes.len());
+    let end = track!(sort_msgpack_maps(&bytes, 0, &mut sorted))?;
+    track_assert_eq!(end, bytes.len(), ErrorKind::Other);
+    Ok(sorted)
+}
+
+/// Copies the MessagePack value starting at `pos` to `out`, sorting the entries of maps,
+/// and returns the position following the value.
+fn sort_msgpack_maps(bytes: &[u8], pos: usize, out: &mut Vec<u8>) -> Result<usize> {
+    let marker = *track_assert_some!(bytes.get(pos), ErrorKind::Other);
+    let (header_len, count, is_map) = match marker {
+        0x80..=0x8f => (1, usize::from(marker & 0x0f), true),
+        0xde => (3, track!(read_len(bytes, pos + 1, 2))?, true),
+        0xdf => (5, track!(read_len(bytes, pos + 1, 4))?, true),
+        0x90..=0x9f => (1, usize::from(marker & 0x0f), false),
+        0xdc => (3, track!(read_len(bytes, pos + 1, 2))?, false),
+        0xdd => (5, track!(read_len(bytes, pos + 1, 4))?, false),
+        _ => {
+            let end = pos + track!(scalar_len(bytes, pos))?;
+            let scalar = track_assert_some!(bytes.get(pos..end), ErrorKind::Other);
+            out.extend_from_slice(scalar);
+            return Ok(end);
+        }
+    };
+
+    out.extend_from_slice(&bytes[pos..pos + header_len]);
+    let mut pos = pos + header_len;
+    if is_map {
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            let mut key = Vec::new();
+            pos = track!(sort_msgpack_maps(bytes, pos, &mut key))?;
+            let mut value = Vec::new();
+            pos = track!(sort_msgpack_maps(bytes, pos, &mut value))?;
+            entries.push((key, value));
+        }
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        for (key, value) in entries {
+            out.extend_from_slice(&key);
+            out.extend_from_slice(&value);
+        }
+    } else {
+        for _ in 0..count {
+            pos = track!(sort_msgpack_maps(bytes, pos, out))?;
+        }
+    }
+    Ok(pos)
+}
+
+/// Copies the MessagePack value starting at `pos` to `out`, re-encoding every integer
+/// in the 64-bit form (`uint 64` for non-negative integers and `int 64` for negative ones),
+/// and returns the position following the value.
+pub(crate) fn widen_msgpack_integers(bytes: &[u8], pos: usize, out: &mut Vec<u8>) -> Result<usize> {
+    let marker = *track_assert_some!(bytes.get(pos), ErrorKind::Other);
+    let (header_len, count) = match marker {
+        0x80..=0x8f => (1, usize::from(marker & 0x0f) * 2),
+        0xde => (3, track!(read_len(bytes, pos + 1, 2))? * 2),
+        0xdf => (5, track!(read_len(bytes, pos + 1, 4))? * 2),
+        0x90..=0x9f => (1, usize::from(marker & 0x0f)),
+        0xdc => (3, track!(read_len(bytes, pos + 1, 2))?),
+        0xdd => (5, track!(read_len(bytes, pos + 1, 4))?),
+        0x00..=0x7f => return Ok(write_u64(u64::from(marker), pos + 1, out)),
+        0xcc..=0xcf => {
+            let size = 1 << (marker - 0xcc);
+            let n = track!(read_u64(bytes, pos + 1, size))?;
+            return Ok(write_u64(n, pos + 1 + size, out));
+        }
+        0xe0..=0xff => return Ok(write_i64(i64::from(marker as i8), pos + 1, out)),
+        0xd0..=0xd3 => {
+            let size = 1 << (marker - 0xd0);
+            let n = track!(read_u64(bytes, pos + 1, size))?;
+            let shift = 64 - size * 8;
+            return Ok(write_i64(
+                ((n << shift) as i64) >> shift,
+                pos + 1 + size,
+                out,
+            ));
+        }
+        _ => {
+            let end = pos + track!(scalar_len(bytes, pos))?;
+            let scalar = track_assert_some!(bytes.get(pos..end), ErrorKind::Other);
+            out.extend_from_slice(scalar);
+            return Ok(end);
+        }
+    };
+
+    out.extend_from_slice(&bytes[pos..pos + header_len]);
+    let mut pos = pos + header_len;
+    for _ in 0..count {
+        pos = track!(widen_msgpack_integers(bytes, pos, out))?;
+    }
+    Ok(pos)
+}
+
+fn write_u64(n: u64, end: usize, out: &mut Vec<u8>) -> usize {
+    out.push(0xcf);
+    out.extend_from_slice(&n.to_be_bytes());
+    end
+}
+
+fn write_i64(n: i64, end: usize, out: &mut Vec<u8>) -> usize {
+    out.push(0xd3);
+    out.extend_from_slice(&n.to_be_bytes());
+    end
+}
+
+/// Returns the length (including the marker) of the non-container MessagePack value starting at `pos`.
+fn scalar_len(bytes: &[u8], pos: usize) -> Result<usize> {
+    let len = match bytes[pos] {
+        0x00..=0x7f | 0xc0 | 0xc2 | 0xc3 | 0xe0..=0xff => 1,
+        marker @ 0xa0..=0xbf => 1 + usize::from(marker & 0x1f),
+        0xcc | 0xd0 => 2,
+        0xcd | 0xd1 | 0xd4 => 3,
+        0xd5 => 4,
+        0xca | 0xce | 0xd2 => 5,
+        0xd6 => 6,
+        0xcb | 0xcf | 0xd3 => 9,
+        0xd7 => 10,
+        0xd8 => 18,
+        0xc4 | 0xd9 => 2 + track!(read_len(bytes, pos + 1, 1))?,
+        0xc5 | 0xda => 3 + track!(read_len(bytes, pos + 1, 2))?,
+        0xc6 | 0xdb => 5 + track!(read_len(bytes, pos + 1, 4))?,
+        0xc7 => 3 + track!(read_len(bytes, pos + 1, 1))?,
+        0xc8 => 4 + track!(read_len(bytes, pos + 1, 2))?,
+        0xc9 => 6 + track!(read_len(bytes, pos + 1, 4))?,
+        marker => track_panic!(ErrorKind::Other, "Unexpected marker: {:#x}", marker),
+    };
+    Ok(len)
+}
+
+fn read_len(bytes: &[u8], pos: usize, size: usize) -> Result<usize> {
+    let bytes = track_assert_some!(bytes.get(pos..pos + size), ErrorKind::Other);
+    Ok(bytes.iter().fold(0, |n, &b| (n << 8) | usize::from(b)))
+}
+
+fn read_u64(bytes: &[u8], pos: usize, size: usize) -> Result<u64> {
+    let bytes = track_assert_some!(bytes.get(pos..pos + size), ErrorKind::Other);
+    Ok(bytes.iter().fold(0, |n, &b| (n << 8) | u64::from(b)))
+}
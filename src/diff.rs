@@ -0,0 +1,162 @@
+//! Structural comparison of serialized documents.
+//!
+//! The documents are parsed into `Value`s and compared structurally,
+//! so differences in whitespace and the order of object keys are ignored.
+//!
+//! Numbers are compared by their numeric values (e.g., `1` and `1.0` are equal).
+//! Floats can optionally be compared with an absolute tolerance (epsilon).
+//!
+//! # Examples
+//!
+//! ```
+//! use serdeconv::diff;
+//!
+//! assert!(diff::json_eq(r#"{"a": 1, "b": [2, 3]}"#, r#"{"b":[2,3],"a":1}"#).unwrap());
+//!
+//! let d = diff::json_diff(r#"{"a": {"b": [1, 2]}}"#, r#"{"a": {"b": [1, 3]}}"#).unwrap();
+//! assert_eq!(d, Some("a.b[1]: expected 2, actual 3".to_owned()));
+//!
+//! let d = diff::json_diff_with_epsilon("[0.1]", "[0.1000001]", 1e-6).unwrap();
+//! assert_eq!(d, None);
+//! ```
+use serde_json;
+use std::collections::BTreeMap;
+
+use {from_json_str, from_toml_str, Result, Value};
+
+/// Returns `true` if the two JSON strings represent structurally equal values.
+pub fn json_eq(a: &str, b: &str) -> Result<bool> {
+    let d = track!(json_diff(a, b))?;
+    Ok(d.is_none())
+}
+
+/// Compares the two JSON strings structurally and describes the first difference if any.
+///
+/// The description consists of the path to the difference and the expected (`a`) and actual (`b`) values.
+pub fn json_diff(a: &str, b: &str) -> Result<Option<String>> {
+    track!(json_diff_inner(a, b, None))
+}
+
+/// The same as `json_diff` except that floats are regarded as equal if their difference is at most `epsilon`.
+pub fn json_diff_with_epsilon(a: &str, b: &str, epsilon: f64) -> Result<Option<String>> {
+    track!(json_diff_inner(a, b, Some(epsilon)))
+}
+
+/// Returns `true` if the two TOML strings represent structurally equal values.
+pub fn toml_eq(a: &str, b: &str) -> Result<bool> {
+    let d = track!(toml_diff(a, b))?;
+    Ok(d.is_none())
+}
+
+/// Compares the two TOML strings structurally and describes the first difference if any.
+///
+/// The description consists of the path to the difference and the expected (`a`) and actual (`b`) values.
+pub fn toml_diff(a: &str, b: &str) -> Result<Option<String>> {
+    track!(toml_diff_inner(a, b, None))
+}
+
+/// The same as `toml_diff` except that floats are regarded as equal if their difference is at most `epsilon`.
+pub fn toml_diff_with_epsilon(a: &str, b: &str, epsilon: f64) -> Result<Option<String>> {
+    track!(toml_diff_inner(a, b, Some(epsilon)))
+}
+
+fn json_diff_inner(a: &str, b: &str, epsilon: Option<f64>) -> Result<Option<String>> {
+    let a: Value = track!(from_json_str(a))?;
+    let b: Value = track!(from_json_str(b))?;
+    Ok(diff_values(&a, &b, epsilon, &mut String::new()))
+}
+
+fn toml_diff_inner(a: &str, b: &str, epsilon: Option<f64>) -> Result<Option<String>> {
+    let a: Value = track!(from_toml_str(a))?;
+    let b: Value = track!(from_toml_str(b))?;
+    Ok(diff_values(&a, &b, epsilon, &mut String::new()))
+}
+
+fn diff_values(a: &Value, b: &Value, epsilon: Option<f64>, path: &mut String) -> Option<String> {
+    match (a, b) {
+        (Value::Array(a), Value::Array(b)) => {
+            for (i, (a, b)) in a.iter().zip(b.iter()).enumerate() {
+                let len = path.len();
+                path.push_str(&format!("[{}]", i));
+                let d = diff_values(a, b, epsilon, path);
+                path.truncate(len);
+                if d.is_some() {
+                    return d;
+                }
+            }
+            if a.len() != b.len() {
+                return Some(describe(
+                    path,
+                    &format!("array of length {}", a.len()),
+                    &format!("array of length {}", b.len()),
+                ));
+            }
+            None
+        }
+        (Value::Object(a), Value::Object(b)) => diff_objects(a, b, epsilon, path),
+        _ => {
+            if values_eq(a, b, epsilon) {
+                None
+            } else {
+                Some(describe(path, &to_json(a), &to_json(b)))
+            }
+        }
+    }
+}
+
+fn diff_objects(
+    a: &BTreeMap<String, Value>,
+    b: &BTreeMap<String, Value>,
+    epsilon: Option<f64>,
+    path: &mut String,
+) -> Option<String> {
+    for (k, a) in a {
+        let len = path.len();
+        if !path.is_empty() {
+            path.push('.');
+        }
+        path.push_str(k);
+        let d = match b.get(k) {
+            None => Some(describe(path, &to_json(a), "(missing)")),
+            Some(b) => diff_values(a, b, epsilon, path),
+        };
+        path.truncate(len);
+        if d.is_some() {
+            return d;
+        }
+    }
+    for (k, b) in b {
+        if !a.contains_key(k) {
+            let mut path = path.clone();
+            if !path.is_empty() {
+                path.push('.');
+            }
+            path.push_str(k);
+            return Some(describe(&path, "(missing)", &to_json(b)));
+        }
+    }
+    None
+}
+
+fn values_eq(a: &Value, b: &Value, epsilon: Option<f64>) -> bool {
+    let (a, b) = match (a, b) {
+        (&Value::Integer(a), &Value::Integer(b)) => return a == b,
+        (&Value::Integer(a), &Value::Float(b)) => (a as f64, b),
+        (&Value::Float(a), &Value::Integer(b)) => (a, b as f64),
+        (&Value::Float(a), &Value::Float(b)) => (a, b),
+        _ => return a == b,
+    };
+    match epsilon {
+        Some(epsilon) => a == b || (a - b).abs() <= epsilon,
+        None => a == b,
+    }
+}
+
+fn describe(path: &str, expected: &str, actual: &str) -> String {
+    let path = if path.is_empty() { "(root)" } else { path };
+    format!("{}: expected {}, actual {}", path, expected, actual)
+}
+
+fn to_json(value: &Value) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| format!("{:?}", value))
+}
@@ -1,17 +1,238 @@
+#[cfg(feature = "bson")]
+use bson;
+#[cfg(feature = "csv")]
+use csv;
 use rmp_serde;
 use serde_json;
+#[cfg(feature = "kdl")]
+use serde_kdl2;
+#[cfg(feature = "serde_path_to_error")]
+use serde_path_to_error;
+#[cfg(feature = "smile")]
+use serde_smile;
 use std;
+use std::fmt;
 use std::io;
+use std::ops::Deref;
 use toml;
+use toml_edit;
 use trackable::error::TrackableError;
 use trackable::error::{ErrorKind as TrackableErrorKind, ErrorKindExt};
+use trackable::{History, Location, Trackable};
+
+use Format;
 
 /// The error type for this crate.
-#[derive(Debug, Clone, TrackableError)]
-pub struct Error(TrackableError<ErrorKind>);
+///
+/// The underlying I/O or parse error (if any) is available via `std::error::Error::source`.
+///
+/// `Error` is `Send + Sync + 'static` (the underlying errors are stored as
+/// `Box<dyn std::error::Error + Send + Sync>`), so it can be passed across threads
+/// and converted to `Box<dyn std::error::Error + Send + Sync>`.
+///
+/// # Examples
+///
+/// ```
+/// use std::error::Error;
+///
+/// let error = serdeconv::from_json_str::<u8>("[").unwrap_err();
+/// assert!(error.source().is_some());
+///
+/// fn assert_send_sync<T: Send + Sync + 'static>() {}
+/// assert_send_sync::<serdeconv::Error>();
+///
+/// let message = error.to_string();
+/// let boxed: Box<dyn Error + Send + Sync> = error.into();
+/// assert_eq!(std::thread::spawn(move || boxed.to_string()).join().unwrap(), message);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Error {
+    inner: TrackableError<ErrorKind>,
+    format: Option<Format>,
+}
+impl Error {
+    /// Returns the kind of this error.
+    pub fn kind(&self) -> &ErrorKind {
+        self.inner.kind()
+    }
+
+    /// Returns the format being handled when this error occurred, if known.
+    ///
+    /// This is set by the functions taking a `Format` (e.g., `from_str_with_format` and `decode_to_value`),
+    /// and is `None` for the errors of the format-specific functions (e.g., `from_json_str`).
+    /// For the conversions between two formats (`transcode` and `convert_file`),
+    /// this is the source format if the input could not be decoded, and the destination format otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serdeconv::Format;
+    ///
+    /// let input = "{\"foo\": 1}";
+    /// let error = serdeconv::from_str_with_format::<serdeconv::Value>(Format::Toml, input).unwrap_err();
+    /// assert_eq!(error.format(), Some(Format::Toml));
+    /// assert!(error.is_invalid());
+    ///
+    /// let error = serdeconv::from_json_str::<u8>("[").unwrap_err();
+    /// assert_eq!(error.format(), None);
+    /// ```
+    pub fn format(&self) -> Option<Format> {
+        self.format
+    }
+
+    /// Sets the format of this error unless it is already set.
+    pub(crate) fn with_format(mut self, format: Format) -> Self {
+        self.format = self.format.or(Some(format));
+        self
+    }
+
+    /// Returns `true` if this error was caused by a missing file, otherwise `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let error = serdeconv::from_toml_file::<(), _>("/no/such/file.toml").unwrap_err();
+    /// assert!(error.is_not_found());
+    /// assert!(error.is_io());
+    /// assert!(!error.is_invalid());
+    /// ```
+    pub fn is_not_found(&self) -> bool {
+        *self.kind() == ErrorKind::NotFound
+    }
+
+    /// Returns `true` if this error was caused by an invalid input, otherwise `false`.
+    ///
+    /// Note that `ErrorKind::Utf8` errors are also regarded as invalid input errors.
+    pub fn is_invalid(&self) -> bool {
+        matches!(*self.kind(), ErrorKind::Invalid | ErrorKind::Utf8 { .. })
+    }
+
+    /// Returns `true` if this error was caused by an I/O failure, otherwise `false`.
+    ///
+    /// Note that `ErrorKind::NotFound` errors are also regarded as I/O errors.
+    pub fn is_io(&self) -> bool {
+        matches!(*self.kind(), ErrorKind::Io | ErrorKind::NotFound)
+    }
+}
+impl Deref for Error {
+    type Target = TrackableError<ErrorKind>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        macro_rules! try_cause {
+            ($($t:ty),*) => {
+                $(if let Some(e) = self.inner.concrete_cause::<$t>() {
+                    return Some(e);
+                })*
+            };
+        }
+        try_cause!(
+            io::Error,
+            toml::de::Error,
+            toml::ser::Error,
+            toml_edit::TomlError,
+            serde_json::Error,
+            rmp_serde::encode::Error,
+            rmp_serde::decode::Error,
+            std::str::Utf8Error
+        );
+        #[cfg(feature = "bson")]
+        try_cause!(bson::ser::Error, bson::de::Error);
+        #[cfg(feature = "csv")]
+        try_cause!(csv::Error);
+        #[cfg(feature = "kdl")]
+        try_cause!(serde_kdl2::Error);
+        #[cfg(feature = "smile")]
+        try_cause!(serde_smile::Error);
+        #[cfg(feature = "serde_path_to_error")]
+        try_cause!(
+            serde_path_to_error::Error<serde_json::Error>,
+            serde_path_to_error::Error<toml::de::Error>,
+            serde_path_to_error::Error<rmp_serde::decode::Error>
+        );
+        None
+    }
+}
+impl Trackable for Error {
+    type Event = Location;
+
+    fn history(&self) -> Option<&History<Self::Event>> {
+        self.inner.history()
+    }
+
+    fn history_mut(&mut self) -> Option<&mut History<Self::Event>> {
+        self.inner.history_mut()
+    }
+}
+impl From<TrackableError<ErrorKind>> for Error {
+    fn from(f: TrackableError<ErrorKind>) -> Self {
+        Error {
+            inner: f,
+            format: None,
+        }
+    }
+}
+impl From<Error> for TrackableError<ErrorKind> {
+    fn from(f: Error) -> Self {
+        f.inner
+    }
+}
+impl From<ErrorKind> for Error {
+    fn from(f: ErrorKind) -> Self {
+        f.error().into()
+    }
+}
 impl From<io::Error> for Error {
     fn from(f: io::Error) -> Self {
-        ErrorKind::Other.cause(f).into()
+        if f.kind() == io::ErrorKind::NotFound {
+            ErrorKind::NotFound.cause(f).into()
+        } else {
+            ErrorKind::Io.cause(f).into()
+        }
+    }
+}
+/// Converts the error to an `io::Error`.
+///
+/// `ErrorKind::NotFound` is mapped to `io::ErrorKind::NotFound`, and
+/// `ErrorKind::Io` is mapped to the kind of the underlying `io::Error` (if any).
+/// The other kinds are mapped to `io::ErrorKind::InvalidData`.
+/// The original error is kept as the inner error of the resulting `io::Error`.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+///
+/// let error = serdeconv::from_json_str::<u8>("[").unwrap_err();
+/// let io_error = io::Error::from(error);
+/// assert_eq!(io_error.kind(), io::ErrorKind::InvalidData);
+///
+/// let inner = io_error.into_inner().unwrap();
+/// let error = inner.downcast::<serdeconv::Error>().unwrap();
+/// assert!(error.is_invalid());
+///
+/// let error = serdeconv::from_json_file::<u8, _>("/no/such/file.json").unwrap_err();
+/// assert_eq!(io::Error::from(error).kind(), io::ErrorKind::NotFound);
+/// ```
+impl From<Error> for io::Error {
+    fn from(f: Error) -> Self {
+        let kind = match *f.kind() {
+            ErrorKind::NotFound => io::ErrorKind::NotFound,
+            ErrorKind::Io => f
+                .concrete_cause::<io::Error>()
+                .map_or(io::ErrorKind::Other, |e| e.kind()),
+            _ => io::ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, f)
     }
 }
 impl From<toml::de::Error> for Error {
@@ -24,6 +245,11 @@ impl From<toml::ser::Error> for Error {
         ErrorKind::Invalid.cause(f).into()
     }
 }
+impl From<toml_edit::TomlError> for Error {
+    fn from(f: toml_edit::TomlError) -> Self {
+        ErrorKind::Invalid.cause(f).into()
+    }
+}
 impl From<serde_json::Error> for Error {
     fn from(f: serde_json::Error) -> Self {
         ErrorKind::Invalid.cause(f).into()
@@ -39,18 +265,103 @@ impl From<rmp_serde::decode::Error> for Error {
         ErrorKind::Invalid.cause(f).into()
     }
 }
+#[cfg(feature = "bson")]
+impl From<bson::ser::Error> for Error {
+    fn from(f: bson::ser::Error) -> Self {
+        ErrorKind::Invalid.cause(f).into()
+    }
+}
+#[cfg(feature = "bson")]
+impl From<bson::de::Error> for Error {
+    fn from(f: bson::de::Error) -> Self {
+        ErrorKind::Invalid.cause(f).into()
+    }
+}
+#[cfg(feature = "csv")]
+impl From<csv::Error> for Error {
+    fn from(f: csv::Error) -> Self {
+        if f.is_io_error() {
+            ErrorKind::Io.cause(f).into()
+        } else {
+            ErrorKind::Invalid.cause(f).into()
+        }
+    }
+}
+#[cfg(feature = "kdl")]
+impl From<serde_kdl2::Error> for Error {
+    fn from(f: serde_kdl2::Error) -> Self {
+        ErrorKind::Invalid.cause(f).into()
+    }
+}
+#[cfg(feature = "smile")]
+impl From<serde_smile::Error> for Error {
+    fn from(f: serde_smile::Error) -> Self {
+        ErrorKind::Invalid.cause(f).into()
+    }
+}
+#[cfg(feature = "serde_path_to_error")]
+impl<E> From<serde_path_to_error::Error<E>> for Error
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    fn from(f: serde_path_to_error::Error<E>) -> Self {
+        ErrorKind::Invalid.cause(f).into()
+    }
+}
 impl From<std::str::Utf8Error> for Error {
     fn from(f: std::str::Utf8Error) -> Self {
-        ErrorKind::Invalid.cause(f).into()
+        ErrorKind::Utf8 {
+            offset: f.valid_up_to(),
+        }
+        .cause(f)
+        .into()
     }
 }
 
 /// A list of error kinds.
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// This enum is `#[non_exhaustive]`, so new kinds may be added in minor releases.
+/// A `match` on `ErrorKind` outside this crate must include a wildcard arm.
+///
+/// # Examples
+///
+/// ```
+/// use serdeconv::ErrorKind;
+/// use std::collections::HashSet;
+///
+/// let retryable = [ErrorKind::Io, ErrorKind::NotFound].iter().cloned().collect::<HashSet<_>>();
+///
+/// let error = serdeconv::from_json_file::<u8, _>("/no/such/file.json").unwrap_err();
+/// assert!(retryable.contains(error.kind()));
+///
+/// let error = serdeconv::from_json_str::<u8>("[").unwrap_err();
+/// assert!(!retryable.contains(error.kind()));
+///
+/// let description = match *error.kind() {
+///     ErrorKind::Invalid | ErrorKind::Utf8 { .. } => "invalid input",
+///     ErrorKind::NotFound | ErrorKind::Io => "I/O failure",
+///     _ => "other",
+/// };
+/// assert_eq!(description, "invalid input");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum ErrorKind {
     /// Invalid input.
     Invalid,
 
+    /// The input is not a valid UTF-8 sequence.
+    Utf8 {
+        /// The byte offset of the first invalid byte.
+        offset: usize,
+    },
+
+    /// The specified file was not found.
+    NotFound,
+
+    /// I/O error.
+    Io,
+
     /// Unknown error.
     Other,
 }
@@ -0,0 +1,358 @@
+use serde::ser::{self, Serialize, Serializer};
+use serde_json;
+use std::error;
+use std::fmt;
+
+use {ErrorKind, Result};
+
+/// Checks that the value does not contain non-finite floats (i.e., `NaN` or infinities).
+///
+/// If a non-finite float is found, an `ErrorKind::Invalid` error
+/// indicating the path to the float (e.g., `foo.bar[2]`) is returned.
+///
+/// This walks the value using its `Serialize` implementation without producing any output,
+/// so it can be used to validate values before passing them to any serializer.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// let mut map = BTreeMap::new();
+/// map.insert("foo", vec![1.0, 2.0]);
+/// assert!(serdeconv::check_finite_floats(&map).is_ok());
+///
+/// map.insert("bar", vec![1.0, std::f64::NAN]);
+/// let error = serdeconv::check_finite_floats(&map).unwrap_err();
+/// assert!(error.is_invalid());
+/// assert!(error.to_string().contains("bar[1]"));
+/// ```
+pub fn check_finite_floats<T>(value: &T) -> Result<()>
+where
+    T: ?Sized + Serialize,
+{
+    let mut checker = FiniteChecker {
+        path: String::new(),
+    };
+    if let Err(e) = value.serialize(&mut checker) {
+        track_panic!(ErrorKind::Invalid, "{}", e);
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+struct CheckError(String);
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+impl error::Error for CheckError {}
+impl ser::Error for CheckError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        CheckError(msg.to_string())
+    }
+}
+
+type CheckResult = ::std::result::Result<(), CheckError>;
+
+#[derive(Debug)]
+struct FiniteChecker {
+    path: String,
+}
+impl FiniteChecker {
+    fn check_float(&self, v: f64) -> CheckResult {
+        if v.is_finite() {
+            Ok(())
+        } else if self.path.is_empty() {
+            Err(CheckError(format!("Non-finite float: {}", v)))
+        } else {
+            Err(CheckError(format!(
+                "Non-finite float at {}: {}",
+                self.path, v
+            )))
+        }
+    }
+
+    fn with_segment<T, F>(&mut self, segment: T, f: F) -> CheckResult
+    where
+        T: fmt::Display,
+        F: FnOnce(&mut Self) -> CheckResult,
+    {
+        let len = self.path.len();
+        self.path.push_str(&segment.to_string());
+        let result = f(self);
+        self.path.truncate(len);
+        result
+    }
+
+    fn with_field<F>(&mut self, name: &str, f: F) -> CheckResult
+    where
+        F: FnOnce(&mut Self) -> CheckResult,
+    {
+        if self.path.is_empty() {
+            self.with_segment(name, f)
+        } else {
+            self.with_segment(format_args!(".{}", name), f)
+        }
+    }
+}
+
+struct Compound<'a> {
+    checker: &'a mut FiniteChecker,
+    index: usize,
+    key: Option<String>,
+}
+impl<'a> Compound<'a> {
+    fn new(checker: &'a mut FiniteChecker) -> Self {
+        Compound {
+            checker,
+            index: 0,
+            key: None,
+        }
+    }
+
+    fn element<T: ?Sized + Serialize>(&mut self, value: &T) -> CheckResult {
+        let index = self.index;
+        self.index += 1;
+        self.checker
+            .with_segment(format_args!("[{}]", index), |c| value.serialize(c))
+    }
+
+    fn field<T: ?Sized + Serialize>(&mut self, name: &str, value: &T) -> CheckResult {
+        self.checker.with_field(name, |c| value.serialize(c))
+    }
+}
+
+macro_rules! check_noop {
+    ($($method:ident($t:ty)),*) => {
+        $(fn $method(self, _v: $t) -> CheckResult {
+            Ok(())
+        })*
+    };
+}
+
+impl<'a> Serializer for &'a mut FiniteChecker {
+    type Ok = ();
+    type Error = CheckError;
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = Compound<'a>;
+    type SerializeMap = Compound<'a>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Compound<'a>;
+
+    check_noop!(
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+        serialize_unit_struct(&'static str)
+    );
+
+    fn serialize_f32(self, v: f32) -> CheckResult {
+        self.check_float(f64::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> CheckResult {
+        self.check_float(v)
+    }
+
+    fn serialize_none(self) -> CheckResult {
+        Ok(())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> CheckResult {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> CheckResult {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(self, _: &'static str, _: u32, _: &'static str) -> CheckResult {
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> CheckResult {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> CheckResult {
+        self.with_field(variant, |c| value.serialize(c))
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> ::std::result::Result<Compound<'a>, CheckError> {
+        Ok(Compound::new(self))
+    }
+
+    fn serialize_tuple(self, _: usize) -> ::std::result::Result<Compound<'a>, CheckError> {
+        Ok(Compound::new(self))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> ::std::result::Result<Compound<'a>, CheckError> {
+        Ok(Compound::new(self))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> ::std::result::Result<Compound<'a>, CheckError> {
+        Ok(Compound::new(self))
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> ::std::result::Result<Compound<'a>, CheckError> {
+        Ok(Compound::new(self))
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> ::std::result::Result<Compound<'a>, CheckError> {
+        Ok(Compound::new(self))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> ::std::result::Result<Compound<'a>, CheckError> {
+        Ok(Compound::new(self))
+    }
+}
+
+impl<'a> ser::SerializeSeq for Compound<'a> {
+    type Ok = ();
+    type Error = CheckError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> CheckResult {
+        self.element(value)
+    }
+
+    fn end(self) -> CheckResult {
+        Ok(())
+    }
+}
+impl<'a> ser::SerializeTuple for Compound<'a> {
+    type Ok = ();
+    type Error = CheckError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> CheckResult {
+        self.element(value)
+    }
+
+    fn end(self) -> CheckResult {
+        Ok(())
+    }
+}
+impl<'a> ser::SerializeTupleStruct for Compound<'a> {
+    type Ok = ();
+    type Error = CheckError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> CheckResult {
+        self.element(value)
+    }
+
+    fn end(self) -> CheckResult {
+        Ok(())
+    }
+}
+impl<'a> ser::SerializeTupleVariant for Compound<'a> {
+    type Ok = ();
+    type Error = CheckError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> CheckResult {
+        self.element(value)
+    }
+
+    fn end(self) -> CheckResult {
+        Ok(())
+    }
+}
+impl<'a> ser::SerializeMap for Compound<'a> {
+    type Ok = ();
+    type Error = CheckError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> CheckResult {
+        key.serialize(&mut *self.checker)?;
+        let key = match serde_json::to_value(key) {
+            Ok(serde_json::Value::String(s)) => s,
+            Ok(v) => v.to_string(),
+            Err(_) => "?".to_owned(),
+        };
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> CheckResult {
+        let key = self.key.take().unwrap_or_default();
+        self.field(&key, value)
+    }
+
+    fn end(self) -> CheckResult {
+        Ok(())
+    }
+}
+impl<'a> ser::SerializeStruct for Compound<'a> {
+    type Ok = ();
+    type Error = CheckError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        name: &'static str,
+        value: &T,
+    ) -> CheckResult {
+        self.field(name, value)
+    }
+
+    fn end(self) -> CheckResult {
+        Ok(())
+    }
+}
+impl<'a> ser::SerializeStructVariant for Compound<'a> {
+    type Ok = ();
+    type Error = CheckError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        name: &'static str,
+        value: &T,
+    ) -> CheckResult {
+        self.field(name, value)
+    }
+
+    fn end(self) -> CheckResult {
+        Ok(())
+    }
+}
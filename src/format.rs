@@ -0,0 +1,488 @@
+use serde::{Deserialize, Serialize};
+use serde_json;
+use serde_transcode;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+use toml;
+
+use util::{strip_bom, write_file_atomic};
+use {Error, ErrorKind, Result};
+
+/// Serialization formats supported by this crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    /// JSON.
+    Json,
+
+    /// TOML.
+    Toml,
+
+    /// MessagePack.
+    MessagePack,
+
+    /// Smile (binary JSON).
+    #[cfg(feature = "smile")]
+    Smile,
+}
+impl Format {
+    /// Returns all of the formats supported by this crate.
+    ///
+    /// The Smile format is included only if the `smile` feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serdeconv::Format;
+    ///
+    /// let names = Format::all()
+    ///     .iter()
+    ///     .map(|f| f.extensions()[0])
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(&names[..3], ["json", "toml", "msgpack"]);
+    ///
+    /// for format in Format::all() {
+    ///     assert!(!format.extensions().is_empty(), "{:?}", format);
+    ///     assert!(!format.mime_types().is_empty(), "{:?}", format);
+    ///     assert_eq!(format.mime_types()[0], format.mime());
+    /// }
+    /// ```
+    pub fn all() -> &'static [Format] {
+        &[
+            Format::Json,
+            Format::Toml,
+            Format::MessagePack,
+            #[cfg(feature = "smile")]
+            Format::Smile,
+        ]
+    }
+
+    /// Returns the file extensions (without the leading dot) of this format.
+    ///
+    /// The first one is the preferred extension.
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Format::Json => &["json"],
+            Format::Toml => &["toml"],
+            Format::MessagePack => &["msgpack", "mpk"],
+            #[cfg(feature = "smile")]
+            Format::Smile => &["sml", "smile"],
+        }
+    }
+
+    /// Returns the media types (MIME types) of this format.
+    ///
+    /// The first one is the canonical media type returned by `mime`.
+    pub fn mime_types(self) -> &'static [&'static str] {
+        match self {
+            Format::Json => &["application/json", "text/json"],
+            Format::Toml => &["application/toml", "text/toml"],
+            Format::MessagePack => &[
+                "application/msgpack",
+                "application/x-msgpack",
+                "application/vnd.msgpack",
+            ],
+            #[cfg(feature = "smile")]
+            Format::Smile => &["application/x-jackson-smile"],
+        }
+    }
+
+    /// Returns the canonical media type (MIME type) of this format.
+    pub fn mime(self) -> &'static str {
+        self.mime_types()[0]
+    }
+
+    /// Returns the format corresponding to the given media type (MIME type).
+    ///
+    /// Parameters (e.g., `; charset=utf-8`) are ignored and the comparison is case-insensitive.
+    /// Media types with the `+json` structured syntax suffix (e.g., `application/problem+json`)
+    /// are regarded as JSON.
+    ///
+    /// Returns `None` if the media type is not supported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serdeconv::Format;
+    ///
+    /// assert_eq!(Format::from_mime("application/json"), Some(Format::Json));
+    /// assert_eq!(Format::from_mime("Application/JSON; charset=utf-8"), Some(Format::Json));
+    /// assert_eq!(Format::from_mime("application/toml"), Some(Format::Toml));
+    /// assert_eq!(Format::from_mime("application/x-msgpack"), Some(Format::MessagePack));
+    /// assert_eq!(Format::from_mime("text/plain"), None);
+    /// ```
+    pub fn from_mime(media_type: &str) -> Option<Self> {
+        let essence = media_type
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_ascii_lowercase();
+        if let Some(&format) = Format::all()
+            .iter()
+            .find(|f| f.mime_types().contains(&essence.as_str()))
+        {
+            Some(format)
+        } else if essence.starts_with("application/") && essence.ends_with("+json") {
+            Some(Format::Json)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the format corresponding to the extension of the given path.
+    ///
+    /// The comparison is case-insensitive, and the extensions returned by `extensions` are supported:
+    ///
+    /// - JSON: `json`
+    /// - TOML: `toml`
+    /// - MessagePack: `msgpack` and `mpk`
+    /// - Smile: `sml` and `smile` (if the `smile` feature is enabled)
+    ///
+    /// Returns `None` if the path has no extension or the extension is not supported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serdeconv::Format;
+    ///
+    /// assert_eq!(Format::from_path("config.toml"), Some(Format::Toml));
+    /// assert_eq!(Format::from_path("/tmp/data.MSGPACK"), Some(Format::MessagePack));
+    /// assert_eq!(Format::from_path("README"), None);
+    /// ```
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        Format::all()
+            .iter()
+            .find(|f| f.extensions().contains(&extension.as_str()))
+            .cloned()
+    }
+}
+impl FromStr for Format {
+    type Err = Error;
+
+    /// Parses a format name such as a command line argument.
+    ///
+    /// The comparison is case-insensitive, and any of the extensions of a format
+    /// (e.g., `json` and `mpk`) as well as `messagepack` are accepted.
+    /// Otherwise, an `ErrorKind::Invalid` error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serdeconv::Format;
+    ///
+    /// assert_eq!("json".parse::<Format>().unwrap(), Format::Json);
+    /// assert_eq!("TOML".parse::<Format>().unwrap(), Format::Toml);
+    /// assert_eq!("MessagePack".parse::<Format>().unwrap(), Format::MessagePack);
+    /// assert!("yaml".parse::<Format>().unwrap_err().is_invalid());
+    /// ```
+    fn from_str(s: &str) -> Result<Self> {
+        let name = s.to_ascii_lowercase();
+        if name == "messagepack" {
+            return Ok(Format::MessagePack);
+        }
+        let format = Format::all()
+            .iter()
+            .find(|f| f.extensions().contains(&name.as_str()));
+        let format = track_assert_some!(format, ErrorKind::Invalid, "Unknown format: {:?}", s);
+        Ok(*format)
+    }
+}
+
+/// Converts from the bytes encoded in the format indicated by `content_type` to a value of `T` type.
+///
+/// `content_type` is a media type such as the value of a `Content-Type` HTTP header.
+/// See `Format::from_mime` for the supported media types.
+/// If the media type is not supported, an `ErrorKind::Invalid` error is returned.
+///
+/// # Examples
+///
+/// ```
+/// let value: Vec<u8> = serdeconv::from_content_type("application/json", b"[1, 2]").unwrap();
+/// assert_eq!(value, [1, 2]);
+///
+/// let error = serdeconv::from_content_type::<Vec<u8>>("text/plain", b"[1, 2]").unwrap_err();
+/// assert!(error.is_invalid());
+/// ```
+pub fn from_content_type<T>(content_type: &str, body: &[u8]) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let format = track_assert_some!(
+        Format::from_mime(content_type),
+        ErrorKind::Invalid,
+        "Unsupported content type: {:?}",
+        content_type
+    );
+    track!(from_slice_with_format(format, body))
+}
+
+/// Converts from the bytes encoded in the given format to a value of `T` type.
+///
+/// If this fails, `Error::format` of the resulting error returns `format`,
+/// which helps to tell which format failed when trying multiple formats in turn.
+///
+/// # Examples
+///
+/// ```
+/// use serdeconv::Format;
+///
+/// let value: Vec<u8> = serdeconv::from_slice_with_format(Format::MessagePack, &[0x92, 1, 2]).unwrap();
+/// assert_eq!(value, [1, 2]);
+/// ```
+pub fn from_slice_with_format<T>(format: Format, bytes: &[u8]) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let result = match format {
+        Format::Json => track!(::from_json_slice(bytes)),
+        Format::Toml => track!(::from_toml_slice(bytes)),
+        Format::MessagePack => track!(::from_msgpack_slice(bytes)),
+        #[cfg(feature = "smile")]
+        Format::Smile => track!(::from_smile_slice(bytes)),
+    };
+    result.map_err(|e| e.with_format(format))
+}
+
+/// Converts from the string in the given format to a value of `T` type.
+///
+/// This is the same as `from_slice_with_format(format, s.as_bytes())`,
+/// and mainly intended for the text formats (i.e., JSON and TOML).
+///
+/// # Examples
+///
+/// ```
+/// use serdeconv::Format;
+///
+/// let input = r#"{"foo": 1}"#;
+/// let mut value = None;
+/// for &format in &[Format::Toml, Format::Json] {
+///     match serdeconv::from_str_with_format::<serdeconv::Value>(format, input) {
+///         Ok(v) => {
+///             value = Some(v);
+///             break;
+///         }
+///         Err(e) => assert_eq!(e.format(), Some(Format::Toml)),
+///     }
+/// }
+/// assert_eq!(serdeconv::to_json_string(&value.unwrap()).unwrap(), r#"{"foo":1}"#);
+/// ```
+pub fn from_str_with_format<T>(format: Format, s: &str) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    track!(from_slice_with_format(format, s.as_bytes()))
+}
+
+/// Converts the value to bytes encoded in the given format.
+///
+/// Returns the media type of the format (e.g., `"application/json"`)
+/// suitable for a `Content-Type` HTTP header, together with the bytes.
+///
+/// # Examples
+///
+/// ```
+/// use serdeconv::Format;
+/// use std::collections::BTreeMap;
+///
+/// let mut value = BTreeMap::new();
+/// value.insert("foo".to_owned(), 1);
+///
+/// for &format in &[Format::Json, Format::Toml, Format::MessagePack] {
+///     let (content_type, body) = serdeconv::to_content_type(format, &value).unwrap();
+///     assert_eq!(content_type, format.mime());
+///
+///     let decoded: BTreeMap<String, u8> =
+///         serdeconv::from_content_type(&content_type, &body).unwrap();
+///     assert_eq!(decoded, value);
+/// }
+/// ```
+pub fn to_content_type<T>(format: Format, value: &T) -> Result<(String, Vec<u8>)>
+where
+    T: ?Sized + Serialize,
+{
+    let body = match format {
+        Format::Json => track!(::to_json_vec(value)),
+        Format::Toml => track!(::to_toml_vec(value)),
+        Format::MessagePack => track!(::to_msgpack_vec(value)),
+        #[cfg(feature = "smile")]
+        Format::Smile => track!(::to_smile_vec(value)),
+    };
+    let body = body.map_err(|e| e.with_format(format))?;
+    Ok((format.mime().to_owned(), body))
+}
+
+/// Decodes the bytes encoded in the given format to a `serde_json::Value`.
+///
+/// This allows to handle documents of a format chosen at runtime without naming any concrete type.
+/// TOML datetimes are converted to strings as `toml_value_to_json_value` does.
+/// Note that MessagePack binaries (the `bin` family) and extension types cannot be represented
+/// in a `serde_json::Value`, so they result in an `ErrorKind::Invalid` error.
+///
+/// # Examples
+///
+/// ```
+/// use serdeconv::Format;
+///
+/// let mut value = serdeconv::decode_to_value(Format::Toml, b"foo = [1, 2]").unwrap();
+/// value["bar"] = "baz".into();
+///
+/// let bytes = serdeconv::encode_from_value(Format::MessagePack, &value).unwrap();
+/// assert_eq!(serdeconv::decode_to_value(Format::MessagePack, &bytes).unwrap(), value);
+///
+/// let bytes = serdeconv::encode_from_value(Format::Json, &value).unwrap();
+/// assert_eq!(serdeconv::decode_to_value(Format::Json, &bytes).unwrap(), value);
+/// ```
+pub fn decode_to_value(format: Format, bytes: &[u8]) -> Result<serde_json::Value> {
+    if format == Format::Toml {
+        let toml: toml::Value = track!(from_slice_with_format(format, bytes))?;
+        Ok(::toml_value_to_json_value(toml))
+    } else {
+        track!(from_slice_with_format(format, bytes))
+    }
+}
+
+/// Encodes the `serde_json::Value` in the given format.
+///
+/// This is the inverse of `decode_to_value`.
+/// Note that TOML cannot represent `null` and requires the value to be an object,
+/// so the other values result in an `ErrorKind::Invalid` error if `format` is `Format::Toml`.
+pub fn encode_from_value(format: Format, value: &serde_json::Value) -> Result<Vec<u8>> {
+    let (_, bytes) = track!(to_content_type(format, value))?;
+    Ok(bytes)
+}
+
+/// Converts the file `from` to the file `to`, detecting both formats by the extensions of the paths.
+///
+/// See `Format::from_path` for the supported extensions.
+/// The content is transcoded by `transcode`, so no typed value is built.
+/// The exception is a TOML input converted to another format: it is converted via `decode_to_value`,
+/// so that TOML datetimes are written as plain strings (see `toml_value_to_json_value`).
+/// The output file is written atomically (see `to_json_file_atomic`),
+/// so it is left untouched if any error occurs.
+///
+/// The errors are reported as follows:
+///
+/// - an unsupported extension results in an `ErrorKind::Invalid` error,
+/// - a failure to read `from` (or to write `to`) results in an `ErrorKind::NotFound` or `ErrorKind::Io` error, and
+/// - a malformed input (or an input that cannot be represented in the output format)
+///   results in an `ErrorKind::Invalid` error.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// let dir = std::env::temp_dir();
+/// let from = dir.join("serdeconv_doctest_convert_file.msgpack");
+/// let to = dir.join("serdeconv_doctest_convert_file.json");
+///
+/// let mut value = BTreeMap::new();
+/// value.insert("foo", vec![1, 2, 3]);
+/// serdeconv::to_msgpack_file(&value, &from).unwrap();
+///
+/// serdeconv::convert_file(&from, &to).unwrap();
+/// assert_eq!(std::fs::read_to_string(&to).unwrap(), r#"{"foo":[1,2,3]}"#);
+///
+/// let toml = dir.join("serdeconv_doctest_convert_file.toml");
+/// std::fs::write(&toml, "t = 1979-05-27T07:32:00Z\n").unwrap();
+/// serdeconv::convert_file(&toml, &to).unwrap();
+/// assert_eq!(std::fs::read_to_string(&to).unwrap(), r#"{"t":"1979-05-27T07:32:00Z"}"#);
+///
+/// let error = serdeconv::convert_file(dir.join("serdeconv_no_such_file.json"), &from).unwrap_err();
+/// assert_eq!(*error.kind(), serdeconv::ErrorKind::NotFound);
+/// ```
+pub fn convert_file<P, Q>(from: P, to: Q) -> Result<()>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let (from, to) = (from.as_ref(), to.as_ref());
+    let from_format = track_assert_some!(
+        Format::from_path(from),
+        ErrorKind::Invalid,
+        "Unsupported file extension: {:?}",
+        from
+    );
+    let to_format = track_assert_some!(
+        Format::from_path(to),
+        ErrorKind::Invalid,
+        "Unsupported file extension: {:?}",
+        to
+    );
+    let input = track!(fs::read(from).map_err(Error::from), "path={:?}", from)?;
+    let output = if from_format == Format::Toml && to_format != Format::Toml {
+        let value = track!(decode_to_value(from_format, &input))?;
+        track!(encode_from_value(to_format, &value))?
+    } else {
+        track!(::transcode(&input, from_format, to_format))?
+    };
+    track!(write_file_atomic(to, |f| track!(f
+        .write_all(&output)
+        .map_err(Error::from))))
+}
+
+/// Parses the document and re-emits it in the pretty form of its own format.
+///
+/// This is useful for normalizing files whose schema is unknown (e.g., in a pre-commit hook).
+/// The per-format behavior is as follows:
+///
+/// - JSON: the document is re-emitted with two-space indentation, as `to_json_string_pretty` does.
+///   The order of object keys is preserved. The output has no trailing newline.
+/// - TOML: the document is parsed to a `toml::Value` and re-emitted with multi-line arrays.
+///   The keys in each table are sorted, and comments are discarded
+///   (use `reformat_toml` to normalize the whitespace while keeping the comments and the key order).
+/// - MessagePack (and Smile): the binary formats have no pretty form,
+///   so an `ErrorKind::Invalid` error is returned.
+///
+/// A malformed input also results in an `ErrorKind::Invalid` error.
+///
+/// # Examples
+///
+/// ```
+/// use serdeconv::Format;
+///
+/// let json = serdeconv::prettify(Format::Json, br#"{"b":[1,2],"a":null}"#).unwrap();
+/// assert_eq!(json, "{\n  \"b\": [\n    1,\n    2\n  ],\n  \"a\": null\n}");
+///
+/// let toml = serdeconv::prettify(Format::Toml, b"b = [1, 2]\n[t]\na = 'x'").unwrap();
+/// assert_eq!(toml, "b = [\n    1,\n    2,\n]\n\n[t]\na = \"x\"\n");
+///
+/// let error = serdeconv::prettify(Format::MessagePack, b"\x90").unwrap_err();
+/// assert!(error.is_invalid());
+/// ```
+pub fn prettify(format: Format, input: &[u8]) -> Result<String> {
+    track!(prettify_format(format, input)).map_err(|e| e.with_format(format))
+}
+
+fn prettify_format(format: Format, input: &[u8]) -> Result<String> {
+    match format {
+        Format::Json => {
+            let mut de = serde_json::Deserializer::from_slice(strip_bom(input));
+            let mut output = Vec::new();
+            {
+                let mut ser = serde_json::Serializer::pretty(&mut output);
+                track!(serde_transcode::transcode(&mut de, &mut ser).map_err(Error::from))?;
+            }
+            track!(de.end().map_err(Error::from))?;
+            let json = track!(String::from_utf8(output).map_err(|e| Error::from(e.utf8_error())))?;
+            Ok(json)
+        }
+        Format::Toml => {
+            let value: toml::Value = track!(::from_toml_slice(input))?;
+            let toml = track!(toml::to_string_pretty(&value).map_err(Error::from))?;
+            Ok(toml)
+        }
+        Format::MessagePack => track_panic!(
+            ErrorKind::Invalid,
+            "MessagePack is a binary format and has no pretty form"
+        ),
+        #[cfg(feature = "smile")]
+        Format::Smile => track_panic!(
+            ErrorKind::Invalid,
+            "Smile is a binary format and has no pretty form"
+        ),
+    }
+}
@@ -0,0 +1,348 @@
+//! Reusable (de)serialization helpers for the fields of configuration structs.
+//!
+//! Each submodule provides `serialize` and `deserialize` functions,
+//! so it can be referred by `#[serde(with = "...")]`.
+//! The helpers do not depend on a specific format and work with TOML, JSON and MessagePack.
+//!
+//! The byte helpers (`base64_bytes` and `hex_bytes`) are available if the `byte-encoding` feature is enabled,
+//! and the others are available if the `serde_with` feature (which implies `byte-encoding`) is enabled.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate serde;
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serdeconv;
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Key {
+//!     #[serde(with = "serdeconv::helpers::base64_bytes")]
+//!     secret: Vec<u8>,
+//!     #[serde(with = "serdeconv::helpers::hex_bytes")]
+//!     fingerprint: [u8; 4],
+//! }
+//!
+//! # fn main() {
+//! let key = Key {
+//!     secret: b"foo".to_vec(),
+//!     fingerprint: [0xde, 0xad, 0xbe, 0xef],
+//! };
+//! let json = serdeconv::to_json_string(&key).unwrap();
+//! assert_eq!(json, r#"{"secret":"Zm9v","fingerprint":"deadbeef"}"#);
+//! assert_eq!(serdeconv::from_json_str::<Key>(&json).unwrap(), key);
+//!
+//! let json = r#"{"secret":"Zm9v","fingerprint":"dead"}"#;
+//! assert!(serdeconv::from_json_str::<Key>(json).is_err());
+//! # }
+//! ```
+
+/// (De)serializes a `std::time::Duration` as a human-friendly string such as `"1h 30m"`.
+///
+/// Accepted input forms:
+///
+/// - Strings accepted by [`humantime::parse_duration`](https://docs.rs/humantime/2/humantime/fn.parse_duration.html),
+///   i.e., a sequence of numbers with units (e.g., `"30s"`, `"1h 30m"`, `"100ms"`, `"2days"`).
+/// - Non-negative integers, which are regarded as seconds (e.g., `30`).
+///
+/// The output is formatted by `humantime::format_duration` (e.g., `"1h 30m"`).
+#[cfg(feature = "serde_with")]
+pub mod humantime_duration {
+    use humantime;
+    use serde::de::{self, Visitor};
+    use serde::{Deserializer, Serializer};
+    use std::fmt;
+    use std::time::Duration;
+
+    /// Serializes the duration as a human-friendly string.
+    pub fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(&humantime::format_duration(*duration))
+    }
+
+    /// Deserializes a duration from a human-friendly string or an integer of seconds.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(DurationVisitor)
+    }
+
+    struct DurationVisitor;
+    impl<'de> Visitor<'de> for DurationVisitor {
+        type Value = Duration;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a duration such as \"30s\" or an integer of seconds")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+            humantime::parse_duration(v).map_err(E::custom)
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+            Ok(Duration::from_secs(v))
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+            if v < 0 {
+                return Err(E::invalid_value(de::Unexpected::Signed(v), &self));
+            }
+            Ok(Duration::from_secs(v as u64))
+        }
+    }
+}
+
+/// (De)serializes a `chrono::DateTime` as an RFC 3339 string such as `"2030-01-01T00:00:00+00:00"`.
+///
+/// Accepted input forms:
+///
+/// - RFC 3339 strings with an offset (e.g., `"2030-01-01T00:00:00Z"`, `"2030-01-01T09:00:00+09:00"`).
+/// - TOML offset date-times (e.g., `expires_at = 2030-01-01T00:00:00Z`).
+///
+/// The timezone of the deserialized value is `Utc` or `FixedOffset` (i.e., the type of the field),
+/// and the input is converted to it if needed.
+///
+/// # Examples
+///
+/// ```
+/// extern crate chrono;
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// use chrono::{DateTime, Utc};
+/// use std::time::Duration;
+///
+/// #[derive(Debug, Serialize, Deserialize)]
+/// struct Config {
+///     #[serde(with = "serdeconv::helpers::humantime_duration")]
+///     timeout: Duration,
+///     #[serde(with = "serdeconv::helpers::rfc3339_datetime")]
+///     expires_at: DateTime<Utc>,
+///     #[serde(with = "serdeconv::helpers::base64_bytes")]
+///     key: Vec<u8>,
+/// }
+///
+/// # fn main() {
+/// let toml = r#"
+/// timeout = "1m 30s"
+/// expires_at = 2030-01-01T09:00:00+09:00
+/// key = "AAEC"
+/// "#;
+/// let config: Config = serdeconv::from_toml_str(toml).unwrap();
+/// assert_eq!(config.timeout, Duration::from_secs(90));
+/// assert_eq!(config.expires_at.to_rfc3339(), "2030-01-01T00:00:00+00:00");
+/// assert_eq!(config.key, [0, 1, 2]);
+///
+/// let json = serdeconv::to_json_string(&config).unwrap();
+/// assert_eq!(
+///     json,
+///     r#"{"timeout":"1m 30s","expires_at":"2030-01-01T00:00:00+00:00","key":"AAEC"}"#
+/// );
+/// # }
+/// ```
+#[cfg(feature = "serde_with")]
+pub mod rfc3339_datetime {
+    use chrono::{DateTime, FixedOffset, TimeZone};
+    use serde::de::value::MapAccessDeserializer;
+    use serde::de::{self, MapAccess, Visitor};
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::fmt;
+    use std::marker::PhantomData;
+    use toml;
+
+    /// Serializes the datetime as an RFC 3339 string.
+    pub fn serialize<S, Tz>(datetime: &DateTime<Tz>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        Tz: TimeZone,
+        Tz::Offset: fmt::Display,
+    {
+        serializer.serialize_str(&datetime.to_rfc3339())
+    }
+
+    /// Deserializes a datetime from an RFC 3339 string or a TOML offset date-time.
+    pub fn deserialize<'de, D, Tz>(deserializer: D) -> Result<DateTime<Tz>, D::Error>
+    where
+        D: Deserializer<'de>,
+        Tz: TimeZone,
+        DateTime<Tz>: From<DateTime<FixedOffset>>,
+    {
+        deserializer
+            .deserialize_any(DatetimeVisitor(PhantomData))
+            .map(DateTime::from)
+    }
+
+    struct DatetimeVisitor(PhantomData<()>);
+    impl<'de> Visitor<'de> for DatetimeVisitor {
+        type Value = DateTime<FixedOffset>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "an RFC 3339 datetime")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+            DateTime::parse_from_rfc3339(v).map_err(E::custom)
+        }
+
+        fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>,
+        {
+            let datetime = toml::value::Datetime::deserialize(MapAccessDeserializer::new(map))?;
+            self.visit_str(&datetime.to_string())
+        }
+    }
+}
+
+/// (De)serializes a byte sequence (e.g., `Vec<u8>` or `[u8; N]`) as a base64 string.
+///
+/// Accepted input forms:
+///
+/// - Standard base64 strings (e.g., `"+/8="`).
+/// - URL-safe base64 strings (e.g., `"-_8="`).
+///
+/// The padding (`=`) is optional. The output is a standard base64 string with padding.
+///
+/// If the input is not a valid base64 string, or its length does not match the length of the field
+/// (for fixed-size arrays), deserialization fails with a serde error.
+/// Malformed inputs such as truncated strings (e.g., `"Q"`), non-zero unused trailing bits (e.g., `"QR=="`)
+/// and misplaced or excessive padding (e.g., `"QQ==="`, `"QQ==QQ"`) are rejected.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Key {
+///     #[serde(with = "serdeconv::helpers::base64_bytes")]
+///     secret: Vec<u8>,
+/// }
+///
+/// # fn main() {
+/// let decode = |s: &str| {
+///     let json = format!(r#"{{"secret":"{}"}}"#, s);
+///     serdeconv::from_json_str::<Key>(&json).map(|key| key.secret)
+/// };
+/// assert_eq!(decode("QQ==").unwrap(), b"A");
+/// assert_eq!(decode("QQ").unwrap(), b"A");
+/// assert_eq!(decode("-_8").unwrap(), [0xfb, 0xff]);
+/// assert_eq!(decode("").unwrap(), b"");
+///
+/// for malformed in &["Q", "QR==", "QQ=", "QQ===", "QUFB====", "QQ==garbage", "Q=Q=", "QQ!="] {
+///     assert!(decode(malformed).is_err(), "{:?}", malformed);
+/// }
+/// # }
+/// ```
+#[cfg(feature = "byte-encoding")]
+pub mod base64_bytes {
+    use serde::Deserializer;
+    use serde::Serializer;
+    use std::convert::TryFrom;
+
+    use super::deserialize_bytes;
+    use util::{decode_base64, encode_base64};
+
+    /// Serializes the bytes as a standard base64 string.
+    pub fn serialize<T, S>(bytes: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + AsRef<[u8]>,
+        S: Serializer,
+    {
+        serializer.serialize_str(&encode_base64(bytes.as_ref()))
+    }
+
+    /// Deserializes bytes from a standard or URL-safe base64 string.
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: TryFrom<Vec<u8>>,
+    {
+        deserialize_bytes(deserializer, "a base64 string", decode_base64)
+    }
+}
+
+/// An alias of `base64_bytes`.
+#[cfg(feature = "serde_with")]
+pub use self::base64_bytes as bytes_base64;
+
+/// (De)serializes a byte sequence (e.g., `Vec<u8>` or `[u8; N]`) as a hexadecimal string.
+///
+/// Accepted input forms:
+///
+/// - Hexadecimal strings of even length in either case (e.g., `"deadbeef"`, `"DEADBEEF"`).
+///
+/// The output is a lowercase hexadecimal string.
+///
+/// If the input is not a valid hexadecimal string, or its length does not match the length of the field
+/// (for fixed-size arrays), deserialization fails with a serde error.
+#[cfg(feature = "byte-encoding")]
+pub mod hex_bytes {
+    use serde::Deserializer;
+    use serde::Serializer;
+    use std::convert::TryFrom;
+
+    use super::deserialize_bytes;
+    use util::{decode_hex, encode_hex};
+
+    /// Serializes the bytes as a lowercase hexadecimal string.
+    pub fn serialize<T, S>(bytes: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + AsRef<[u8]>,
+        S: Serializer,
+    {
+        serializer.serialize_str(&encode_hex(bytes.as_ref()))
+    }
+
+    /// Deserializes bytes from a hexadecimal string.
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: TryFrom<Vec<u8>>,
+    {
+        deserialize_bytes(deserializer, "a hexadecimal string", decode_hex)
+    }
+}
+
+#[cfg(feature = "byte-encoding")]
+fn deserialize_bytes<'de, D, T>(
+    deserializer: D,
+    expecting: &'static str,
+    decode: fn(&str) -> Option<Vec<u8>>,
+) -> Result<T, D::Error>
+where
+    D: ::serde::Deserializer<'de>,
+    T: ::std::convert::TryFrom<Vec<u8>>,
+{
+    use serde::de::{self, Visitor};
+    use std::fmt;
+
+    struct BytesVisitor {
+        expecting: &'static str,
+        decode: fn(&str) -> Option<Vec<u8>>,
+    }
+    impl<'de> Visitor<'de> for BytesVisitor {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}", self.expecting)
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+            (self.decode)(v).ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))
+        }
+    }
+
+    let bytes = deserializer.deserialize_str(BytesVisitor { expecting, decode })?;
+    let len = bytes.len();
+    T::try_from(bytes)
+        .map_err(|_| de::Error::invalid_length(len, &"a byte sequence of the length of the field"))
+}
@@ -0,0 +1,116 @@
+use jsonschema;
+use serde::Deserialize;
+use serde_json;
+use std::path::Path;
+use toml;
+
+use {from_json_file, from_json_value, from_toml_file, from_toml_value, toml_value_to_json_value};
+use {ErrorKind, Result};
+
+/// Converts from the TOML file to a value of `T` type, after validating it against the JSON Schema file.
+///
+/// The TOML document is converted to a JSON value as `toml_value_to_json_value` does
+/// (i.e., TOML datetimes are regarded as RFC 3339 strings), and validated against the schema.
+/// `T` is deserialized only if the document satisfies the schema.
+///
+/// Note that this validates only against the schema;
+/// the `FromTomlValidated` hook is not run (use `from_toml_str_validated` for that).
+///
+/// This function is available only if the `jsonschema` feature is enabled.
+///
+/// # Errors
+///
+/// If the document violates the schema, an `ErrorKind::Invalid` error is returned.
+/// Its message lists all of the violations (not only the first one), one per line,
+/// each prefixed by the JSON Pointer to the offending value (e.g., `/server/port: 70000 is greater than the maximum of 65535`).
+/// An invalid schema also results in an `ErrorKind::Invalid` error.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Config {
+///     name: String,
+///     port: u32,
+/// }
+///
+/// # fn main() {
+/// let dir = std::env::temp_dir();
+/// let schema_path = dir.join("serdeconv_doctest_from_toml_file_with_schema.schema.json");
+/// let config_path = dir.join("serdeconv_doctest_from_toml_file_with_schema.toml");
+/// std::fs::write(&schema_path, r#"{
+///   "type": "object",
+///   "properties": {
+///     "name": {"type": "string", "pattern": "^[a-z]+$"},
+///     "port": {"type": "integer", "maximum": 65535}
+///   }
+/// }"#).unwrap();
+///
+/// std::fs::write(&config_path, "name = \"foo\"\nport = 8080").unwrap();
+/// let config: Config = serdeconv::from_toml_file_with_schema(&config_path, &schema_path).unwrap();
+/// assert_eq!(config.port, 8080);
+///
+/// std::fs::write(&config_path, "name = \"Foo\"\nport = 70000").unwrap();
+/// let error = serdeconv::from_toml_file_with_schema::<Config, _, _>(&config_path, &schema_path).unwrap_err();
+/// assert!(error.is_invalid());
+/// assert!(error.to_string().contains("/name: "));
+/// assert!(error.to_string().contains("/port: 70000 is greater than the maximum of 65535"));
+/// # }
+/// ```
+pub fn from_toml_file_with_schema<T, P, Q>(config_path: P, schema_path: Q) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let schema: serde_json::Value = track!(from_json_file(schema_path))?;
+    let toml: toml::Value = track!(from_toml_file(config_path))?;
+    track!(validate_json_schema(
+        &toml_value_to_json_value(toml.clone()),
+        &schema
+    ))?;
+    track!(from_toml_value(toml))
+}
+
+/// Converts from the JSON file to a value of `T` type, after validating it against the JSON Schema file.
+///
+/// See `from_toml_file_with_schema` for the details of the validation and the errors.
+///
+/// Note that this validates only against the schema;
+/// the `FromJsonValidated` hook is not run (use `from_json_str_validated` for that).
+///
+/// This function is available only if the `jsonschema` feature is enabled.
+pub fn from_json_file_with_schema<T, P, Q>(config_path: P, schema_path: Q) -> Result<T>
+where
+    T: for<'a> Deserialize<'a>,
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let schema: serde_json::Value = track!(from_json_file(schema_path))?;
+    let json: serde_json::Value = track!(from_json_file(config_path))?;
+    track!(validate_json_schema(&json, &schema))?;
+    track!(from_json_value(json))
+}
+
+fn validate_json_schema(instance: &serde_json::Value, schema: &serde_json::Value) -> Result<()> {
+    let validator = match jsonschema::validator_for(schema) {
+        Ok(validator) => validator,
+        Err(e) => track_panic!(ErrorKind::Invalid, "Invalid JSON Schema: {}", e),
+    };
+    let violations = validator
+        .iter_errors(instance)
+        .map(|e| format!("{}: {}", e.instance_path().as_str(), e))
+        .collect::<Vec<_>>();
+    track_assert!(
+        violations.is_empty(),
+        ErrorKind::Invalid,
+        "Schema violations:\n{}",
+        violations.join("\n")
+    );
+    Ok(())
+}
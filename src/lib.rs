@@ -21,7 +21,6 @@
 //!     bar: String,
 //!     baz: usize
 //! }
-//! impl FromToml for Foo {}
 //!
 //! # fn main() {
 //! // Converts from the TOML string to a `Foo` value.
@@ -34,30 +33,258 @@
 //! assert_eq!(foo.baz, 123);
 //! # }
 //! ```
+//!
+//! # Migration from 0.4
+//!
+//! The conversion traits (e.g., `FromToml` and `ToJson`) are now implemented for
+//! all (de)serializable types by blanket implementations.
+//! Remove manual implementations such as `impl FromToml for Foo {}`,
+//! as they conflict with the blanket ones.
+//!
+//! # Error tracking and performance
+//!
+//! The errors of this crate are built on [trackable](https://docs.rs/trackable),
+//! and the internal `track!` calls record the source locations that an error passed through
+//! (shown in the `Display` output of `Error`).
+//! For a successful result, `track!` is just a branch on the `Result`;
+//! the cost (allocating the history entry) is paid only when an error is propagated.
+//!
+//! If errors are frequent in a hot path (e.g., when probing inputs in multiple formats),
+//! enable the `no-trace` feature. It compiles the `track!` calls that record only a location
+//! down to plain `?` propagation. The calls that attach a context message
+//! (e.g., the line number of a malformed NDJSON record) are kept, so the messages are still available.
+//! Run `cargo bench --bench track` with and without the feature to measure the difference.
+//!
+//! # `no_std` environments
+//!
+//! This crate requires `std`.
+//! Its `Error` type is built on [trackable](https://docs.rs/trackable), which depends on `std`,
+//! and the `toml` crate does not support `no_std` either.
+//! In `no_std` + `alloc` environments, use `serde_json` (with its `alloc` feature)
+//! and `rmp-serde` directly instead.
 #![warn(missing_docs)]
+#[cfg(feature = "bson")]
+extern crate bson;
+#[cfg(feature = "chrono")]
+extern crate chrono;
+#[cfg(feature = "csv")]
+extern crate csv;
+#[cfg(feature = "erased-serde")]
+extern crate erased_serde;
+#[cfg(feature = "compression")]
+extern crate flate2;
+#[cfg(feature = "humantime")]
+extern crate humantime;
+#[cfg(feature = "jsonschema")]
+extern crate jsonschema;
+#[cfg(feature = "mmap")]
+extern crate memmap2;
 extern crate rmp_serde;
+#[cfg(feature = "rmpv")]
+extern crate rmpv;
+#[macro_use]
 extern crate serde;
 extern crate serde_json;
+#[cfg(feature = "kdl")]
+extern crate serde_kdl2;
+#[cfg(feature = "serde_path_to_error")]
+extern crate serde_path_to_error;
+#[cfg(feature = "smile")]
+extern crate serde_smile;
+extern crate serde_transcode;
+#[cfg(feature = "tokio")]
+extern crate tokio;
 extern crate toml;
+extern crate toml_edit;
 #[macro_use]
 extern crate trackable;
+#[cfg(feature = "compression")]
+extern crate zstd;
+
+// Shadows `trackable::track!` so that the location-only tracking can be compiled out.
+#[cfg(feature = "no-trace")]
+macro_rules! track {
+    ($target:expr) => {
+        $target
+    };
+    ($($arg:tt)+) => {
+        ::trackable::track!($($arg)+)
+    };
+}
 
-pub use convert_json::{from_json_file, from_json_reader, from_json_slice, from_json_str};
+pub use canonical::to_canonical_json_vec;
+pub use convert_env::{from_env_str, from_env_vars};
 pub use convert_json::{
-    to_json_file, to_json_string, to_json_string_pretty, to_json_writer, to_json_writer_pretty,
+    from_json_array_reader_with_progress, from_json_bytes, from_json_cow, from_json_file,
+    from_json_file_or_default, from_json_file_verbose, from_json_reader,
+    from_json_reader_depth_limited, from_json_reader_one, from_json_reader_size_limited,
+    from_json_reader_with, from_json_slice, from_json_slice_depth_limited, from_json_slice_timeout,
+    from_json_str, from_json_str_lenient_numbers, from_json_str_no_dup_keys,
+    from_json_str_or_default, from_json_str_partial, from_json_str_validated, from_json_value,
+    JsonDeserializeOptions,
+};
+pub use convert_json::{
+    to_json_array_writer, to_json_array_writer_pretty, to_json_file, to_json_file_atomic,
+    to_json_string, to_json_string_ascii, to_json_string_checked, to_json_string_html_safe,
+    to_json_string_pretty, to_json_string_pretty_with, to_json_string_sorted,
+    to_json_string_stringify_keys, to_json_value, to_json_vec, to_json_vec_pretty, to_json_writer,
+    to_json_writer_ascii, to_json_writer_counted, to_json_writer_html_safe, to_json_writer_pretty,
+    to_json_writer_pretty_with, JsonFormat, JsonIndent,
+};
+pub use convert_msgpack::{
+    from_msgpack_bytes, from_msgpack_file, from_msgpack_file_checksummed,
+    from_msgpack_file_or_default, from_msgpack_reader, from_msgpack_reader_size_limited,
+    from_msgpack_slice, from_msgpack_slice_or_default, from_msgpack_slice_partial, MsgPack,
+};
+pub use convert_msgpack::{
+    to_msgpack_array_writer, to_msgpack_file, to_msgpack_file_atomic, to_msgpack_file_checksummed,
+    to_msgpack_vec, to_msgpack_vec_interop, to_msgpack_vec_with, to_msgpack_writer,
+    to_msgpack_writer_counted,
+};
+pub use convert_ndjson::{
+    from_ndjson_reader, from_ndjson_reader_with_progress, NdjsonProgressReader, NdjsonReader,
+};
+pub use convert_toml::{
+    append_toml_table, to_toml_file, to_toml_file_atomic, to_toml_string, to_toml_string_finite,
+    to_toml_string_sorted, to_toml_string_with, to_toml_value, to_toml_vec, to_toml_writer,
+    to_toml_writer_counted, TomlFormat,
+};
+pub use convert_toml::{
+    from_toml_bytes, from_toml_cow, from_toml_file, from_toml_file_or_default,
+    from_toml_file_with_includes, from_toml_reader, from_toml_reader_size_limited, from_toml_slice,
+    from_toml_str, from_toml_str_collect_errors, from_toml_str_or_default, from_toml_str_strict,
+    from_toml_str_validated, from_toml_str_warn_unknown, from_toml_str_with_report,
+    from_toml_value, from_toml_with_defaults,
+};
+pub use deterministic::{
+    to_json_string_deterministic, to_msgpack_vec_deterministic, to_toml_string_deterministic,
 };
-pub use convert_msgpack::{from_msgpack_file, from_msgpack_reader, from_msgpack_slice};
-pub use convert_msgpack::{to_msgpack_file, to_msgpack_vec, to_msgpack_writer};
-pub use convert_toml::{from_toml_file, from_toml_reader, from_toml_slice, from_toml_str};
-pub use convert_toml::{to_toml_file, to_toml_string, to_toml_writer};
 pub use error::{Error, ErrorKind};
-pub use traits::{FromJson, FromMsgPack, FromToml, ToJson, ToMsgPack, ToToml};
+pub use finite::check_finite_floats;
+pub use format::{
+    convert_file, decode_to_value, encode_from_value, from_content_type, from_slice_with_format,
+    from_str_with_format, prettify, to_content_type, Format,
+};
+pub use patch::{apply_json_patch, merge_json_into};
+pub use reformat::{reformat_toml, reformat_toml_file};
+#[cfg(feature = "bson")]
+pub use traits::{FromBson, ToBson};
+pub use traits::{
+    FromJson, FromJsonBorrowed, FromJsonValidated, FromMsgPack, FromToml, FromTomlValidated,
+    ToJson, ToMsgPack, ToToml,
+};
+#[cfg(feature = "kdl")]
+pub use traits::{FromKdl, ToKdl};
+#[cfg(feature = "smile")]
+pub use traits::{FromSmile, ToSmile};
+pub use transcode::{
+    to_json_string_from_toml_value, to_json_string_pretty_from_toml_value,
+    to_json_writer_from_toml_value, to_json_writer_pretty_from_toml_value,
+    to_toml_string_from_json_value, to_toml_writer_from_json_value, toml_value_to_json_value,
+    toml_value_to_json_value_raw, transcode, transcode_toml_to_json, transcode_toml_to_json_raw,
+};
+pub use validate::{validate_json, validate_msgpack, validate_toml};
+pub use value::Value;
 
+#[cfg(feature = "compression")]
+pub use compression::Compression;
+#[cfg(feature = "tokio")]
+pub use convert_async::{from_json_file_async, from_msgpack_file_async, from_toml_file_async};
+#[cfg(feature = "tokio")]
+pub use convert_async::{to_json_file_async, to_msgpack_file_async, to_toml_file_async};
+#[cfg(feature = "bson")]
+pub use convert_bson::{from_bson_file, from_bson_reader, from_bson_slice};
+#[cfg(feature = "bson")]
+pub use convert_bson::{to_bson_file, to_bson_vec, to_bson_writer};
+#[cfg(feature = "csv")]
+pub use convert_csv::to_csv_writer_with_headers;
+#[cfg(feature = "mmap")]
+pub use convert_json::from_json_mmap;
+#[cfg(feature = "raw_value")]
+pub use convert_json::passthrough_json;
+#[cfg(feature = "erased-serde")]
+pub use convert_json::to_json_dyn_writer;
+#[cfg(feature = "compression")]
+pub use convert_json::{from_json_reader_compressed, to_json_writer_compressed};
+#[cfg(feature = "serde_path_to_error")]
+pub use convert_json::{from_json_slice_with_path, from_json_str_with_path};
+#[cfg(feature = "jsonc")]
+pub use convert_jsonc::{from_jsonc_file, from_jsonc_str, set_jsonc_value};
+#[cfg(feature = "kdl")]
+pub use convert_kdl::{from_kdl_file, from_kdl_reader, from_kdl_slice, from_kdl_str};
+#[cfg(feature = "kdl")]
+pub use convert_kdl::{to_kdl_file, to_kdl_string, to_kdl_writer};
+#[cfg(feature = "mmap")]
+pub use convert_msgpack::from_msgpack_mmap;
+#[cfg(feature = "serde_path_to_error")]
+pub use convert_msgpack::from_msgpack_slice_with_path;
+#[cfg(feature = "erased-serde")]
+pub use convert_msgpack::to_msgpack_dyn_writer;
+#[cfg(feature = "compression")]
+pub use convert_msgpack::{from_msgpack_reader_compressed, to_msgpack_writer_compressed};
+#[cfg(feature = "rmpv")]
+pub use convert_msgpack::{from_msgpack_slice_value, to_msgpack_vec_value};
+#[cfg(feature = "protojson")]
+pub use convert_protojson::{
+    from_protojson_slice, from_protojson_str, to_protojson_string, to_protojson_vec,
+};
+#[cfg(feature = "smile")]
+pub use convert_smile::{from_smile_file, from_smile_reader, from_smile_slice};
+#[cfg(feature = "smile")]
+pub use convert_smile::{to_smile_file, to_smile_vec, to_smile_writer};
+#[cfg(feature = "erased-serde")]
+pub use convert_toml::to_toml_dyn_writer;
+#[cfg(feature = "compression")]
+pub use convert_toml::{from_toml_reader_compressed, to_toml_writer_compressed};
+#[cfg(feature = "serde_path_to_error")]
+pub use convert_toml::{from_toml_str_enum_diagnostics, from_toml_str_with_path};
+#[cfg(feature = "jsonschema")]
+pub use json_schema::{from_json_file_with_schema, from_toml_file_with_schema};
+
+mod canonical;
+#[cfg(feature = "compression")]
+mod compression;
+#[cfg(feature = "tokio")]
+mod convert_async;
+#[cfg(feature = "bson")]
+mod convert_bson;
+#[cfg(feature = "csv")]
+mod convert_csv;
+mod convert_env;
 mod convert_json;
+#[cfg(feature = "jsonc")]
+mod convert_jsonc;
+#[cfg(feature = "kdl")]
+mod convert_kdl;
 mod convert_msgpack;
+mod convert_ndjson;
+#[cfg(feature = "protojson")]
+mod convert_protojson;
+#[cfg(feature = "smile")]
+mod convert_smile;
 mod convert_toml;
+mod deterministic;
 mod error;
+mod finite;
+mod format;
+#[cfg(feature = "jsonschema")]
+mod json_schema;
+mod patch;
+mod probe;
+mod reformat;
+mod stringify;
 mod traits;
+mod transcode;
+mod util;
+mod validate;
+
+pub mod diff;
+#[cfg(any(feature = "byte-encoding", feature = "serde_with"))]
+pub mod helpers;
+pub mod options;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod value;
 
 /// A specialized `Result` type for this crate.
 pub type Result<T> = ::std::result::Result<T, Error>;
@@ -0,0 +1,508 @@
+//! Builder-style APIs for configuring conversions.
+//!
+//! The free functions (e.g., `serdeconv::to_json_string`) cover the common cases.
+//! The builders in this module consolidate the less common settings
+//! (e.g., pretty printing, key sorting and input size limits) into a single place.
+use rmp_serde;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json;
+use std::io::{Read, Write};
+use std::str;
+use toml;
+use toml_edit;
+
+use convert_json::sort_json_value;
+use convert_toml::{format_toml_table, sort_toml_value};
+use deterministic::widen_msgpack_integers;
+use util::{
+    check_input_size, read_to_end_limited, skip_bom, strip_bom, strip_bom_str,
+    JsonDepthLimitedReader, JsonDepthScanner,
+};
+use {to_toml_string, Error, ErrorKind, JsonFormat, JsonIndent, Result, TomlFormat};
+
+/// Options for JSON conversions.
+///
+/// # Examples
+///
+/// ```
+/// use serdeconv::options::JsonOptions;
+/// use std::collections::HashMap;
+///
+/// let mut map = HashMap::new();
+/// map.insert("foo", 1);
+/// map.insert("bar", 2);
+///
+/// let options = JsonOptions::new().pretty(true).sort_keys(true);
+/// assert_eq!(options.to_string(&map).unwrap(), "{\n  \"bar\": 2,\n  \"foo\": 1\n}");
+///
+/// let options = JsonOptions::new().max_depth(1);
+/// assert!(options.from_str::<Vec<Vec<u8>>>("[[1]]").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct JsonOptions {
+    pretty: bool,
+    format: JsonFormat,
+    sort_keys: bool,
+    max_depth: Option<usize>,
+    max_bytes: Option<usize>,
+}
+impl JsonOptions {
+    /// Makes a new `JsonOptions` instance with the default settings.
+    ///
+    /// The default settings produce the same results as the simple free functions
+    /// (e.g., `to_json_string` and `from_json_str`).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether the output is pretty printed (default: `false`).
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+
+    /// Sets the layout of the pretty printed output (default: `JsonFormat::default()`).
+    ///
+    /// This is a shorthand for calling `indent` and `trailing_newline`.
+    pub fn format(mut self, format: JsonFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets the indentation style used for pretty printing (default: `JsonIndent::Spaces(2)`).
+    ///
+    /// This has no effect unless `pretty(true)` is specified.
+    pub fn indent(mut self, indent: JsonIndent) -> Self {
+        self.format.indent = indent;
+        self
+    }
+
+    /// Sets whether a newline is appended to the end of the output (default: `false`).
+    pub fn trailing_newline(mut self, trailing_newline: bool) -> Self {
+        self.format.trailing_newline = trailing_newline;
+        self
+    }
+
+    /// Sets whether the keys of objects are sorted in the output (default: `false`).
+    ///
+    /// Sorting requires the value to be serialized to a `serde_json::Value` first.
+    pub fn sort_keys(mut self, sort_keys: bool) -> Self {
+        self.sort_keys = sort_keys;
+        self
+    }
+
+    /// Sets the maximum nesting depth of arrays and objects in the input (default: unlimited).
+    ///
+    /// See the documentation of `from_json_slice_depth_limited` for details.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Sets the maximum size of the input in bytes (default: unlimited).
+    ///
+    /// If the input is larger than the limit, an `ErrorKind::Invalid` error is returned.
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Converts the value to a JSON string.
+    pub fn to_string<T>(&self, value: &T) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        let bytes = track!(self.to_vec(value))?;
+        let json = track!(String::from_utf8(bytes).map_err(|e| Error::from(e.utf8_error())))?;
+        Ok(json)
+    }
+
+    /// Converts the value to a JSON bytes.
+    pub fn to_vec<T>(&self, value: &T) -> Result<Vec<u8>>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut buf = Vec::new();
+        track!(self.to_writer(value, &mut buf))?;
+        Ok(buf)
+    }
+
+    /// Converts the value to a JSON string and writes it to the writer.
+    pub fn to_writer<T, W>(&self, value: &T, writer: W) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+        W: Write,
+    {
+        if self.sort_keys {
+            let value = track!(serde_json::to_value(value).map_err(Error::from))?;
+            track!(self.write(&sort_json_value(value), writer))
+        } else {
+            track!(self.write(value, writer))
+        }
+    }
+
+    fn write<T, W>(&self, value: &T, mut writer: W) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+        W: Write,
+    {
+        if self.pretty {
+            let indent = self.format.indent.to_string();
+            let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+            let mut serializer = serde_json::Serializer::with_formatter(&mut writer, formatter);
+            track!(value.serialize(&mut serializer).map_err(Error::from))?;
+        } else {
+            track!(serde_json::to_writer(&mut writer, value).map_err(Error::from))?;
+        }
+        if self.format.trailing_newline {
+            track!(writer.write_all(b"\n").map_err(Error::from))?;
+        }
+        Ok(())
+    }
+
+    /// Converts from the JSON string to a value of `T` type.
+    pub fn from_str<'a, T>(&self, json: &'a str) -> Result<T>
+    where
+        T: Deserialize<'a>,
+    {
+        track!(self.from_slice(json.as_bytes()))
+    }
+
+    /// Converts from the JSON bytes to a value of `T` type.
+    pub fn from_slice<'a, T>(&self, json: &'a [u8]) -> Result<T>
+    where
+        T: Deserialize<'a>,
+    {
+        let json = strip_bom(json);
+        if let Some(max_bytes) = self.max_bytes {
+            track!(check_input_size(json.len(), max_bytes))?;
+        }
+        if let Some(max_depth) = self.max_depth {
+            track!(JsonDepthScanner::new(max_depth).scan(json))?;
+        }
+        let value = track!(serde_json::from_slice(json).map_err(Error::from))?;
+        Ok(value)
+    }
+
+    /// Reads a JSON string from the reader and converts it to a value of `T` type.
+    pub fn from_reader<T, R>(&self, reader: R) -> Result<T>
+    where
+        T: for<'a> Deserialize<'a>,
+        R: Read,
+    {
+        if let Some(max_bytes) = self.max_bytes {
+            let json = track!(read_to_end_limited(reader, max_bytes))?;
+            return track!(self.from_slice(&json));
+        }
+        let reader = track!(skip_bom(reader))?;
+        let value = if let Some(max_depth) = self.max_depth {
+            let reader = JsonDepthLimitedReader::new(reader, max_depth);
+            track!(serde_json::from_reader(reader).map_err(Error::from))?
+        } else {
+            track!(serde_json::from_reader(reader).map_err(Error::from))?
+        };
+        Ok(value)
+    }
+}
+
+/// Options for TOML conversions.
+///
+/// # Examples
+///
+/// ```
+/// use serdeconv::options::TomlOptions;
+/// use std::collections::HashMap;
+///
+/// let mut map = HashMap::new();
+/// map.insert("foo", 1);
+/// map.insert("bar", 2);
+///
+/// let options = TomlOptions::new().sort_keys(true);
+/// assert_eq!(options.to_string(&map).unwrap(), "bar = 2\nfoo = 1\n");
+///
+/// let options = TomlOptions::new().max_bytes(4);
+/// assert!(options.from_str::<HashMap<String, usize>>("foo = 1").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TomlOptions {
+    format: TomlFormat,
+    sort_keys: bool,
+    max_bytes: Option<usize>,
+}
+impl TomlOptions {
+    /// Makes a new `TomlOptions` instance with the default settings.
+    ///
+    /// The default settings produce the same results as the simple free functions
+    /// (e.g., `to_toml_string` and `from_toml_str`).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the layout of the output (default: `TomlFormat::default()`).
+    pub fn format(mut self, format: TomlFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets the indentation string for nested entries (default: `""`).
+    ///
+    /// See `TomlFormat::indent` for details.
+    pub fn indent<S: Into<String>>(mut self, indent: S) -> Self {
+        self.format.indent = indent.into();
+        self
+    }
+
+    /// Sets whether the keys of tables are sorted in the output (default: `false`).
+    ///
+    /// Sorting requires the value to be serialized to a `toml::Value` first.
+    pub fn sort_keys(mut self, sort_keys: bool) -> Self {
+        self.sort_keys = sort_keys;
+        self
+    }
+
+    /// Sets the maximum size of the input in bytes (default: unlimited).
+    ///
+    /// If the input is larger than the limit, an `ErrorKind::Invalid` error is returned.
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Converts the value to a TOML string.
+    pub fn to_string<T>(&self, value: &T) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        let toml = if self.sort_keys {
+            let value = track!(toml::Value::try_from(value).map_err(Error::from))?;
+            track!(to_toml_string(&sort_toml_value(value)))?
+        } else {
+            track!(to_toml_string(value))?
+        };
+        if self.format == TomlFormat::default() {
+            return Ok(toml);
+        }
+
+        let mut doc: toml_edit::Document = track!(toml.parse().map_err(Error::from))?;
+        format_toml_table(doc.as_table_mut(), &self.format, 0);
+        Ok(doc.to_string())
+    }
+
+    /// Converts the value to a TOML bytes.
+    pub fn to_vec<T>(&self, value: &T) -> Result<Vec<u8>>
+    where
+        T: ?Sized + Serialize,
+    {
+        let toml = track!(self.to_string(value))?;
+        Ok(toml.into_bytes())
+    }
+
+    /// Converts the value to a TOML string and writes it to the writer.
+    pub fn to_writer<T, W>(&self, value: &T, mut writer: W) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+        W: Write,
+    {
+        let toml = track!(self.to_string(value))?;
+        track!(writer.write_all(toml.as_bytes()).map_err(Error::from))?;
+        Ok(())
+    }
+
+    /// Converts from the TOML string to a value of `T` type.
+    pub fn from_str<T>(&self, toml: &str) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        if let Some(max_bytes) = self.max_bytes {
+            track!(check_input_size(toml.len(), max_bytes))?;
+        }
+        let value = track!(toml::from_str(strip_bom_str(toml)).map_err(Error::from))?;
+        Ok(value)
+    }
+
+    /// Converts from the TOML bytes to a value of `T` type.
+    pub fn from_slice<T>(&self, toml: &[u8]) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        if let Some(max_bytes) = self.max_bytes {
+            track!(check_input_size(toml.len(), max_bytes))?;
+        }
+        track!(self.from_str(str::from_utf8(toml).map_err(Error::from)?))
+    }
+
+    /// Reads a TOML string from the reader and converts it to a value of `T` type.
+    pub fn from_reader<T, R>(&self, mut reader: R) -> Result<T>
+    where
+        T: DeserializeOwned,
+        R: Read,
+    {
+        let toml = if let Some(max_bytes) = self.max_bytes {
+            track!(read_to_end_limited(reader, max_bytes))?
+        } else {
+            let mut buf = Vec::new();
+            track!(reader.read_to_end(&mut buf).map_err(Error::from))?;
+            buf
+        };
+        track!(self.from_slice(&toml))
+    }
+}
+
+/// Options for MessagePack conversions.
+///
+/// # Examples
+///
+/// ```
+/// use serdeconv::options::MsgPackOptions;
+///
+/// let options = MsgPackOptions::new().max_bytes(2);
+/// let bytes = options.to_vec(&[1, 2]).unwrap();
+/// assert_eq!(bytes, [0x92, 0x01, 0x02]);
+/// assert!(options.from_slice::<Vec<u8>>(&bytes).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MsgPackOptions {
+    struct_map: bool,
+    human_readable: bool,
+    fixed_width_integers: bool,
+    max_bytes: Option<usize>,
+}
+impl MsgPackOptions {
+    /// Makes a new `MsgPackOptions` instance with the default settings.
+    ///
+    /// The default settings produce the same results as the simple free functions
+    /// (e.g., `to_msgpack_vec` and `from_msgpack_slice`).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether structs are encoded as maps keyed by the field names
+    /// rather than arrays of the field values (default: `false`).
+    ///
+    /// Maps are what the MessagePack libraries of other languages usually expect for objects,
+    /// and are robust against reordering of the fields, at the cost of larger output.
+    pub fn struct_map(mut self, struct_map: bool) -> Self {
+        self.struct_map = struct_map;
+        self
+    }
+
+    /// Sets whether the serializer reports itself as human-readable (default: `false`).
+    ///
+    /// Some types change their representations depending on this
+    /// (e.g., `std::net::IpAddr` is encoded as a string like `"127.0.0.1"` if `true`,
+    /// and as an array of the octets otherwise).
+    /// Consumers that are not written with serde usually expect the human-readable forms,
+    /// but note that the decoding functions of this crate assume the compact forms.
+    pub fn human_readable(mut self, human_readable: bool) -> Self {
+        self.human_readable = human_readable;
+        self
+    }
+
+    /// Sets whether integers are encoded in their smallest representations (default: `true`).
+    ///
+    /// If `false`, every integer (including map keys) is encoded in the 64-bit form
+    /// (`uint 64` for non-negative integers and `int 64` for negative ones) regardless of its type.
+    /// This makes the encoding of an integer independent of its value,
+    /// which some consumers and byte-level comparisons rely on, at the cost of larger output.
+    /// Every MessagePack decoder (including `from_msgpack_slice`) accepts both forms.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serdeconv::options::MsgPackOptions;
+    ///
+    /// let options = MsgPackOptions::new().compact_integers(false);
+    /// let bytes = options.to_vec(&(1, -1)).unwrap();
+    /// assert_eq!(
+    ///     bytes,
+    ///     [0x92, 0xcf, 0, 0, 0, 0, 0, 0, 0, 1, 0xd3, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]
+    /// );
+    /// assert_eq!(options.from_slice::<(u8, i8)>(&bytes).unwrap(), (1, -1));
+    /// ```
+    pub fn compact_integers(mut self, compact: bool) -> Self {
+        self.fixed_width_integers = !compact;
+        self
+    }
+
+    /// Sets the maximum size of the input in bytes (default: unlimited).
+    ///
+    /// If this is set, `from_reader` reads the reader until EOF.
+    /// If the input is larger than the limit, an `ErrorKind::Invalid` error is returned.
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Converts the value to a MessagePack bytes.
+    pub fn to_vec<T>(&self, value: &T) -> Result<Vec<u8>>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut buf = Vec::new();
+        track!(self.to_writer(value, &mut buf))?;
+        Ok(buf)
+    }
+
+    /// Converts the value to a MessagePack bytes and writes it to the writer.
+    pub fn to_writer<T, W>(&self, value: &T, mut writer: W) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+        W: Write,
+    {
+        if self.fixed_width_integers {
+            let bytes = track!(self.clone().compact_integers(true).to_vec(value))?;
+            let mut widened = Vec::with_capacity(bytes.len());
+            let end = track!(widen_msgpack_integers(&bytes, 0, &mut widened))?;
+            track_assert_eq!(end, bytes.len(), ErrorKind::Other);
+            track!(writer.write_all(&widened).map_err(Error::from))?;
+            return Ok(());
+        }
+
+        let mut serializer = rmp_serde::Serializer::new(&mut writer);
+        match (self.struct_map, self.human_readable) {
+            (false, false) => {
+                track!(value.serialize(&mut serializer).map_err(Error::from))?;
+            }
+            (true, false) => {
+                let mut serializer = serializer.with_struct_map();
+                track!(value.serialize(&mut serializer).map_err(Error::from))?;
+            }
+            (false, true) => {
+                let mut serializer = serializer.with_human_readable();
+                track!(value.serialize(&mut serializer).map_err(Error::from))?;
+            }
+            (true, true) => {
+                let mut serializer = serializer.with_struct_map().with_human_readable();
+                track!(value.serialize(&mut serializer).map_err(Error::from))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Converts from the MessagePack bytes to a value of `T` type.
+    pub fn from_slice<'a, T>(&self, bytes: &'a [u8]) -> Result<T>
+    where
+        T: Deserialize<'a>,
+    {
+        if let Some(max_bytes) = self.max_bytes {
+            track!(check_input_size(bytes.len(), max_bytes))?;
+        }
+        let value = track!(rmp_serde::from_slice(bytes).map_err(Error::from))?;
+        Ok(value)
+    }
+
+    /// Reads a MessagePack bytes from the reader and converts it to a value of `T` type.
+    pub fn from_reader<T, R>(&self, reader: R) -> Result<T>
+    where
+        T: for<'a> Deserialize<'a>,
+        R: Read,
+    {
+        if let Some(max_bytes) = self.max_bytes {
+            let bytes = track!(read_to_end_limited(reader, max_bytes))?;
+            return track!(self.from_slice(&bytes));
+        }
+        let value = track!(rmp_serde::decode::from_read(reader).map_err(Error::from))?;
+        Ok(value)
+    }
+}
@@ -0,0 +1,315 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{self, Map, Value};
+
+use {Error, ErrorKind, Result};
+
+/// Merges the JSON merge patch ([RFC 7386]) into `current` and returns the merged value.
+///
+/// `current` is converted to a JSON value, `patch` is applied to it,
+/// and then the result is converted back to a value of `T` type.
+///
+/// The fields omitted in `patch` are left unchanged, and nested objects are merged recursively.
+/// Note that a `null` in `patch` means "delete this field" rather than "set this field to `null`".
+/// Thus, a deleted field must be optional (or have a default value) in `T`,
+/// and there is no way to set a field to `null` using a merge patch.
+/// Arrays are not merged but replaced as a whole.
+///
+/// [RFC 7386]: https://tools.ietf.org/html/rfc7386
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct Config {
+///     name: String,
+///     port: u16,
+///     proxy: Option<String>,
+/// }
+///
+/// # fn main() {
+/// let current = Config {
+///     name: "foo".to_owned(),
+///     port: 8080,
+///     proxy: Some("localhost:3128".to_owned()),
+/// };
+/// let merged = serdeconv::merge_json_into(&current, r#"{"port": 80, "proxy": null}"#).unwrap();
+/// assert_eq!(
+///     merged,
+///     Config {
+///         name: "foo".to_owned(),
+///         port: 80,
+///         proxy: None,
+///     }
+/// );
+/// # }
+/// ```
+pub fn merge_json_into<T>(current: &T, patch: &str) -> Result<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let patch: Value = track!(serde_json::from_str(patch).map_err(Error::from))?;
+    let mut value = track!(serde_json::to_value(current).map_err(Error::from))?;
+    merge(&mut value, &patch);
+    track!(serde_json::from_value(value).map_err(Error::from))
+}
+
+/// Applies the JSON Patch ([RFC 6902]) to `current` and returns the patched value.
+///
+/// `current` is converted to a JSON value, the operations in `patch` are applied to it in order,
+/// and then the result is converted back to a value of `T` type.
+///
+/// All of the `add`, `remove`, `replace`, `move`, `copy` and `test` operations are supported.
+/// If any operation fails (e.g., the target location does not exist or a `test` operation fails),
+/// an `ErrorKind::Invalid` error is returned and `current` is left unchanged.
+///
+/// [RFC 6902]: https://tools.ietf.org/html/rfc6902
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct Config {
+///     name: String,
+///     hosts: Vec<String>,
+/// }
+///
+/// # fn main() {
+/// let current = Config {
+///     name: "foo".to_owned(),
+///     hosts: vec!["a".to_owned(), "c".to_owned()],
+/// };
+///
+/// let patch = r#"[
+///   {"op": "test", "path": "/name", "value": "foo"},
+///   {"op": "replace", "path": "/name", "value": "bar"},
+///   {"op": "add", "path": "/hosts/1", "value": "b"}
+/// ]"#;
+/// let patched = serdeconv::apply_json_patch(&current, patch).unwrap();
+/// assert_eq!(patched.name, "bar");
+/// assert_eq!(patched.hosts, ["a", "b", "c"]);
+///
+/// let patch = r#"[{"op": "test", "path": "/name", "value": "baz"}]"#;
+/// assert!(serdeconv::apply_json_patch(&current, patch).unwrap_err().is_invalid());
+/// # }
+/// ```
+pub fn apply_json_patch<T>(current: &T, patch: &str) -> Result<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let patch: Value = track!(serde_json::from_str(patch).map_err(Error::from))?;
+    let operations = track_assert_some!(
+        patch.as_array(),
+        ErrorKind::Invalid,
+        "A JSON Patch must be an array"
+    );
+    let mut value = track!(serde_json::to_value(current).map_err(Error::from))?;
+    for (i, operation) in operations.iter().enumerate() {
+        track!(apply_operation(&mut value, operation), "operation={}", i)?;
+    }
+    track!(serde_json::from_value(value).map_err(Error::from))
+}
+
+fn merge(target: &mut Value, patch: &Value) {
+    if let Value::Object(ref patch) = *patch {
+        if !target.is_object() {
+            *target = Value::Object(Map::new());
+        }
+        let target = target.as_object_mut().expect("never fails");
+        for (k, v) in patch {
+            if v.is_null() {
+                target.remove(k);
+            } else {
+                merge(target.entry(k.clone()).or_insert(Value::Null), v);
+            }
+        }
+    } else {
+        *target = patch.clone();
+    }
+}
+
+fn apply_operation(value: &mut Value, operation: &Value) -> Result<()> {
+    let op = track!(get_str(operation, "op"))?;
+    let path = track!(parse_pointer(track!(get_str(operation, "path"))?))?;
+    match op {
+        "add" => {
+            let v = track!(get_value(operation, "value"))?;
+            track!(add(value, &path, v.clone()))
+        }
+        "remove" => {
+            track!(remove(value, &path))?;
+            Ok(())
+        }
+        "replace" => {
+            let v = track!(get_value(operation, "value"))?;
+            let target = track!(lookup(value, &path))?;
+            *target = v.clone();
+            Ok(())
+        }
+        "move" => {
+            let from = track!(parse_pointer(track!(get_str(operation, "from"))?))?;
+            track_assert!(
+                !(path.len() > from.len() && path.starts_with(&from)),
+                ErrorKind::Invalid,
+                "Cannot move a value into one of its children"
+            );
+            let v = track!(remove(value, &from))?;
+            track!(add(value, &path, v))
+        }
+        "copy" => {
+            let from = track!(parse_pointer(track!(get_str(operation, "from"))?))?;
+            let v = track!(lookup(value, &from))?.clone();
+            track!(add(value, &path, v))
+        }
+        "test" => {
+            let v = track!(get_value(operation, "value"))?;
+            let actual = track!(lookup(value, &path))?;
+            track_assert_eq!(actual, v, ErrorKind::Invalid, "Test failed");
+            Ok(())
+        }
+        _ => track_panic!(ErrorKind::Invalid, "Unknown operation: {:?}", op),
+    }
+}
+
+fn get_value<'a>(operation: &'a Value, member: &str) -> Result<&'a Value> {
+    let v = track_assert_some!(
+        operation.get(member),
+        ErrorKind::Invalid,
+        "Missing {:?} member",
+        member
+    );
+    Ok(v)
+}
+
+fn get_str<'a>(operation: &'a Value, member: &str) -> Result<&'a str> {
+    let v = track!(get_value(operation, member))?;
+    let s = track_assert_some!(
+        v.as_str(),
+        ErrorKind::Invalid,
+        "{:?} must be a string",
+        member
+    );
+    Ok(s)
+}
+
+/// Parses the JSON Pointer ([RFC 6901]) into reference tokens.
+///
+/// [RFC 6901]: https://tools.ietf.org/html/rfc6901
+pub(crate) fn parse_pointer(pointer: &str) -> Result<Vec<String>> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    track_assert!(
+        pointer.starts_with('/'),
+        ErrorKind::Invalid,
+        "Invalid JSON Pointer: {:?}",
+        pointer
+    );
+    Ok(pointer[1..]
+        .split('/')
+        .map(|t| t.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+pub(crate) fn parse_index(token: &str, len: usize) -> Result<usize> {
+    track_assert!(
+        token == "0" || (!token.starts_with('0') && token.bytes().all(|b| b.is_ascii_digit())),
+        ErrorKind::Invalid,
+        "Invalid array index: {:?}",
+        token
+    );
+    let i = track_assert_some!(
+        token.parse::<usize>().ok(),
+        ErrorKind::Invalid,
+        "Invalid array index: {:?}",
+        token
+    );
+    track_assert!(
+        i < len,
+        ErrorKind::Invalid,
+        "Array index out of bounds: {}",
+        i
+    );
+    Ok(i)
+}
+
+fn lookup<'a>(value: &'a mut Value, path: &[String]) -> Result<&'a mut Value> {
+    let mut value = value;
+    for token in path {
+        value = match *value {
+            Value::Object(ref mut o) => {
+                track_assert_some!(
+                    o.get_mut(token),
+                    ErrorKind::Invalid,
+                    "No such member: {:?}",
+                    token
+                )
+            }
+            Value::Array(ref mut a) => {
+                let i = track!(parse_index(token, a.len()))?;
+                &mut a[i]
+            }
+            _ => track_panic!(ErrorKind::Invalid, "No such member: {:?}", token),
+        };
+    }
+    Ok(value)
+}
+
+fn add(value: &mut Value, path: &[String], v: Value) -> Result<()> {
+    let (last, parent) = match path.split_last() {
+        None => {
+            *value = v;
+            return Ok(());
+        }
+        Some(x) => x,
+    };
+    match *track!(lookup(value, parent))? {
+        Value::Object(ref mut o) => {
+            o.insert(last.clone(), v);
+        }
+        Value::Array(ref mut a) => {
+            if last == "-" {
+                a.push(v);
+            } else {
+                let i = track!(parse_index(last, a.len() + 1))?;
+                a.insert(i, v);
+            }
+        }
+        _ => track_panic!(ErrorKind::Invalid, "Not a container: {:?}", parent),
+    }
+    Ok(())
+}
+
+fn remove(value: &mut Value, path: &[String]) -> Result<Value> {
+    let (last, parent) = track_assert_some!(
+        path.split_last(),
+        ErrorKind::Invalid,
+        "Cannot remove the root value"
+    );
+    match *track!(lookup(value, parent))? {
+        Value::Object(ref mut o) => {
+            let v = track_assert_some!(
+                o.remove(last),
+                ErrorKind::Invalid,
+                "No such member: {:?}",
+                last
+            );
+            Ok(v)
+        }
+        Value::Array(ref mut a) => {
+            let i = track!(parse_index(last, a.len()))?;
+            Ok(a.remove(i))
+        }
+        _ => track_panic!(ErrorKind::Invalid, "Not a container: {:?}", parent),
+    }
+}
@@ -0,0 +1,418 @@
+//! Field-by-field probing of struct deserialization.
+//!
+//! A struct is deserialized multiple times from a table in which all fields but the probed ones
+//! are replaced by placeholder values, so that errors of the individual fields can be detected
+//! without knowing their types.
+use serde::de::value::StringDeserializer;
+use serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::{Deserialize, Deserializer};
+use toml;
+
+use Error;
+
+/// Deserializes a value of `T` from the table, collecting errors of all top-level fields.
+///
+/// Returns `None` if `T` is not a struct or if the errors cannot be attributed to fields.
+pub(crate) fn collect_field_errors<T>(table: &toml::value::Table) -> Option<Vec<Error>>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let fields = struct_fields::<T>()?;
+    let deserialize = |probed: Option<&str>| {
+        let mut entries = Vec::new();
+        for &field in fields {
+            let entry = match table.get(field) {
+                Some(v) if probed == Some(field) => Entry::Value(v.clone()),
+                Some(_) => Entry::Placeholder,
+                None if probed == Some(field) => continue,
+                None => Entry::Placeholder,
+            };
+            entries.push((field.to_owned(), entry));
+        }
+        for (key, value) in table {
+            if probed == Some(key) && !fields.contains(&key.as_str()) {
+                entries.push((key.clone(), Entry::Value(value.clone())));
+            }
+        }
+        T::deserialize(StructDeserializer(entries)).map(|_| ())
+    };
+
+    // Placeholders must be accepted by all fields
+    if deserialize(None).is_err() {
+        return None;
+    }
+
+    let mut errors = Vec::new();
+    let mut keys = fields.iter().map(|&f| f.to_owned()).collect::<Vec<_>>();
+    keys.extend(
+        table
+            .keys()
+            .filter(|k| !fields.contains(&k.as_str()))
+            .cloned(),
+    );
+    for key in keys {
+        if let Err(e) = deserialize(Some(&key)) {
+            errors.push(track!(Error::from(e); key));
+        }
+    }
+    if errors.is_empty() {
+        None
+    } else {
+        Some(errors)
+    }
+}
+
+fn struct_fields<T>() -> Option<&'static [&'static str]>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let mut fields = None;
+    let _ = T::deserialize(FieldsCollector(&mut fields));
+    fields
+}
+
+struct FieldsCollector<'a>(&'a mut Option<&'static [&'static str]>);
+impl<'de, 'a> Deserializer<'de> for FieldsCollector<'a> {
+    type Error = toml::de::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(
+        self,
+        _: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        Err(de::Error::custom("not a struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        _: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        *self.0 = Some(fields);
+        Err(de::Error::custom("fields collected"))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+enum Entry {
+    Value(toml::Value),
+    Placeholder,
+}
+
+struct StructDeserializer(Vec<(String, Entry)>);
+impl<'de> Deserializer<'de> for StructDeserializer {
+    type Error = toml::de::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        visitor.visit_map(EntriesAccess {
+            entries: self.0.into_iter(),
+            value: None,
+        })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct EntriesAccess<I> {
+    entries: I,
+    value: Option<Entry>,
+}
+impl<'de, I> de::MapAccess<'de> for EntriesAccess<I>
+where
+    I: Iterator<Item = (String, Entry)>,
+{
+    type Error = toml::de::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> ::std::result::Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if let Some((key, value)) = self.entries.next() {
+            self.value = Some(value);
+            let key: StringDeserializer<Self::Error> = key.into_deserializer();
+            seed.deserialize(key).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> ::std::result::Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            Some(Entry::Value(v)) => seed.deserialize(v),
+            Some(Entry::Placeholder) | None => seed.deserialize(Placeholder),
+        }
+    }
+}
+
+/// A deserializer which produces an arbitrary (zero-like) value of the requested type.
+struct Placeholder;
+impl<'de> Deserializer<'de> for Placeholder {
+    type Error = toml::de::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        visitor.visit_bool(false)
+    }
+
+    fn deserialize_i64<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        visitor.visit_i64(0)
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        visitor.visit_u64(0)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        visitor.visit_f64(0.0)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        visitor.visit_char('\0')
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        visitor.visit_str("")
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        visitor.visit_bytes(&[])
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        visitor.visit_none()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        self.deserialize_tuple(0, visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        visitor.visit_seq(PlaceholderSeq(len))
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        StructDeserializer(Vec::new()).deserialize_any(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        let entries = fields
+            .iter()
+            .map(|&f| (f.to_owned(), Entry::Placeholder))
+            .collect();
+        StructDeserializer(entries).deserialize_any(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        match variants.first() {
+            Some(&variant) => visitor.visit_enum(PlaceholderEnum(variant)),
+            None => Err(de::Error::custom("no variants")),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        unit unit_struct identifier ignored_any
+    }
+
+    fn deserialize_i8<V: Visitor<'de>>(self, v: V) -> ::std::result::Result<V::Value, Self::Error> {
+        self.deserialize_i64(v)
+    }
+
+    fn deserialize_i16<V: Visitor<'de>>(
+        self,
+        v: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        self.deserialize_i64(v)
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(
+        self,
+        v: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        self.deserialize_i64(v)
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, v: V) -> ::std::result::Result<V::Value, Self::Error> {
+        self.deserialize_u64(v)
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(
+        self,
+        v: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        self.deserialize_u64(v)
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(
+        self,
+        v: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        self.deserialize_u64(v)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(
+        self,
+        v: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        self.deserialize_f64(v)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(
+        self,
+        v: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        self.deserialize_str(v)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(
+        self,
+        v: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        self.deserialize_bytes(v)
+    }
+}
+
+struct PlaceholderSeq(usize);
+impl<'de> de::SeqAccess<'de> for PlaceholderSeq {
+    type Error = toml::de::Error;
+
+    fn next_element_seed<T>(
+        &mut self,
+        seed: T,
+    ) -> ::std::result::Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.0 == 0 {
+            Ok(None)
+        } else {
+            self.0 -= 1;
+            seed.deserialize(Placeholder).map(Some)
+        }
+    }
+}
+
+struct PlaceholderEnum(&'static str);
+impl<'de> de::EnumAccess<'de> for PlaceholderEnum {
+    type Error = toml::de::Error;
+    type Variant = Placeholder;
+
+    fn variant_seed<V>(self, seed: V) -> ::std::result::Result<(V::Value, Placeholder), Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant: de::value::StrDeserializer<Self::Error> = self.0.into_deserializer();
+        seed.deserialize(variant).map(|v| (v, Placeholder))
+    }
+}
+impl<'de> de::VariantAccess<'de> for Placeholder {
+    type Error = toml::de::Error;
+
+    fn unit_variant(self) -> ::std::result::Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> ::std::result::Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> ::std::result::Result<V::Value, Self::Error> {
+        self.deserialize_struct("", fields, visitor)
+    }
+}
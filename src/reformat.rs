@@ -0,0 +1,148 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use toml_edit::{Decor, Document, Item, Table};
+
+use util::write_file_atomic;
+use {Error, Result};
+
+/// Reformats the TOML document while preserving its comments and key ordering.
+///
+/// Unlike `to_toml_string`, this operates on the document model rather than on typed values,
+/// so nothing but whitespace is changed:
+///
+/// - indentation before keys, table headers and comments is removed,
+/// - key/value pairs are written as `key = value`,
+/// - runs of blank lines are collapsed into a single blank line,
+/// - every table header is preceded by exactly one blank line, and
+/// - trailing comments are separated from their values by a single space.
+///
+/// # Examples
+///
+/// ```
+/// let toml = "\n\n    # The name\n    name=\"foo\"     # trailing comment\n\n\n  [server]\n      port   =   8080\n[[rules]]\npath=\"/\"\n";
+/// assert_eq!(serdeconv::reformat_toml(toml).unwrap(), r#"# The name
+/// name = "foo" # trailing comment
+///
+/// [server]
+/// port = 8080
+///
+/// [[rules]]
+/// path = "/"
+/// "#);
+/// ```
+pub fn reformat_toml(toml: &str) -> Result<String> {
+    let mut doc: Document = track!(toml.parse().map_err(Error::from))?;
+    reformat_table(doc.as_table_mut());
+    let trailing = doc
+        .trailing()
+        .as_str()
+        .map(|s| normalize_prefix(&format!("{}\n", s), false));
+    doc.set_trailing(trailing.unwrap_or_default());
+
+    let formatted = doc.to_string();
+    let formatted = formatted.trim_matches('\n');
+    if formatted.is_empty() {
+        Ok(String::new())
+    } else {
+        Ok(format!("{}\n", formatted))
+    }
+}
+
+/// Reformats the TOML file in place.
+///
+/// See `reformat_toml` for the details of the formatting.
+/// The file is rewritten atomically, so it is left untouched if any error occurs.
+pub fn reformat_toml_file<P: AsRef<Path>>(path: P) -> Result<()> {
+    let path = path.as_ref();
+    let toml = track!(fs::read_to_string(path).map_err(Error::from))?;
+    let formatted = track!(reformat_toml(&toml))?;
+    track!(write_file_atomic(path, |f| {
+        track!(f.write_all(formatted.as_bytes()).map_err(Error::from))
+    }))
+}
+
+fn reformat_table(table: &mut Table) {
+    for (mut key, item) in table.iter_mut() {
+        match *item {
+            Item::None => {}
+            Item::Value(ref mut v) => {
+                normalize_decor(key.decor_mut(), false, " ");
+                let decor = v.decor_mut();
+                let suffix = normalize_suffix(decor);
+                decor.set_prefix(" ");
+                decor.set_suffix(suffix);
+            }
+            Item::Table(ref mut t) if t.is_dotted() => {
+                normalize_decor(key.decor_mut(), false, "");
+                reformat_table(t);
+            }
+            Item::Table(ref mut t) => {
+                key.decor_mut().clear();
+                reformat_header(t);
+            }
+            Item::ArrayOfTables(ref mut a) => {
+                key.decor_mut().clear();
+                for t in a.iter_mut() {
+                    reformat_header(t);
+                }
+            }
+        }
+    }
+}
+
+fn reformat_header(table: &mut Table) {
+    if !table.is_implicit() {
+        normalize_decor(table.decor_mut(), true, "");
+        let suffix = normalize_suffix(table.decor());
+        table.decor_mut().set_suffix(suffix);
+    }
+    reformat_table(table);
+}
+
+fn normalize_decor(decor: &mut Decor, blank_before: bool, suffix: &str) {
+    let prefix = decor
+        .prefix()
+        .and_then(|p| p.as_str())
+        .map_or_else(String::new, |p| normalize_prefix(p, blank_before));
+    let prefix = if blank_before && prefix.is_empty() {
+        "\n".to_owned()
+    } else {
+        prefix
+    };
+    decor.set_prefix(prefix);
+    decor.set_suffix(suffix);
+}
+
+/// Keeps the comment lines of `raw` (without indentation) and collapses runs of blank lines.
+fn normalize_prefix(raw: &str, blank_before: bool) -> String {
+    let mut lines: Vec<&str> = raw.split('\n').collect();
+    lines.pop(); // The indentation of the line on which the prefixed item resides
+
+    let mut normalized = String::new();
+    let mut pending_blank = blank_before;
+    for line in lines {
+        let line = line.trim();
+        if line.is_empty() {
+            pending_blank = true;
+        } else {
+            if pending_blank {
+                normalized.push('\n');
+                pending_blank = false;
+            }
+            normalized.push_str(line);
+            normalized.push('\n');
+        }
+    }
+    if pending_blank {
+        normalized.push('\n');
+    }
+    normalized
+}
+
+fn normalize_suffix(decor: &Decor) -> String {
+    match decor.suffix().and_then(|s| s.as_str()).map(|s| s.trim()) {
+        Some(comment) if !comment.is_empty() => format!(" {}", comment),
+        _ => String::new(),
+    }
+}
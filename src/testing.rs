@@ -0,0 +1,145 @@
+//! Assertions for testing the (de)serialization of user-defined types.
+//!
+//! Each function converts the value to a format and back,
+//! and panics if the conversion fails or the resulting value is not equal to the original one.
+//! The panic messages show both values (and the encoded document for the text formats),
+//! so they can be used as is in unit tests and property-based tests (e.g., `proptest` and `quickcheck`,
+//! which catch the panics and shrink the inputs).
+//!
+//! The values are compared by `PartialEq`, so `T` must implement it in a way that
+//! is consistent with its serialized form.
+//! For example, a value containing `f64::NAN` is never equal to itself,
+//! and fields skipped by `#[serde(skip)]` must be equal to their default values.
+//!
+//! This module is available only if the `testing` feature is enabled.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate serde;
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serdeconv;
+//!
+//! use serdeconv::testing;
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Config {
+//!     name: String,
+//!     ports: Vec<u16>,
+//! }
+//!
+//! # fn main() {
+//! let config = Config {
+//!     name: "foo".to_owned(),
+//!     ports: vec![80, 443],
+//! };
+//! testing::assert_json_roundtrip(&config);
+//! testing::assert_toml_roundtrip(&config);
+//! testing::assert_msgpack_roundtrip(&config);
+//! # }
+//! ```
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::{self, Debug};
+use std::str;
+
+use {from_slice_with_format, to_content_type, Format};
+
+/// Asserts that converting the value to the given format and back yields an equal value.
+///
+/// # Panics
+///
+/// Panics if the conversion fails or the resulting value is not equal to `value`.
+///
+/// # Examples
+///
+/// ```
+/// use serdeconv::testing;
+/// use serdeconv::Format;
+/// use std::collections::BTreeMap;
+/// use std::panic;
+///
+/// let mut map = BTreeMap::new();
+/// map.insert("foo".to_owned(), vec![1.5, 2.0]);
+/// for &format in Format::all() {
+///     testing::assert_roundtrip(format, &map);
+/// }
+///
+/// // `NaN` is not equal to itself.
+/// map.insert("bar".to_owned(), vec![std::f64::NAN]);
+/// let result = panic::catch_unwind(|| testing::assert_roundtrip(Format::MessagePack, &map));
+/// assert!(result.is_err());
+/// ```
+pub fn assert_roundtrip<T>(format: Format, value: &T)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let (_, bytes) = match to_content_type(format, value) {
+        Ok(encoded) => encoded,
+        Err(e) => panic!(
+            "Failed to encode the value as {:?}:\n  value: {:?}\n  error: {}",
+            format, value, e
+        ),
+    };
+    let decoded: T = match from_slice_with_format(format, &bytes) {
+        Ok(decoded) => decoded,
+        Err(e) => panic!(
+            "Failed to decode the value from {:?}:\n  value: {:?}\n  encoded: {}\n  error: {}",
+            format,
+            value,
+            Encoded(format, &bytes),
+            e
+        ),
+    };
+    assert!(
+        decoded == *value,
+        "The {:?} round trip changed the value:\n  original: {:?}\n  decoded: {:?}\n  encoded: {}",
+        format,
+        value,
+        decoded,
+        Encoded(format, &bytes)
+    );
+}
+
+/// Asserts that converting the value to JSON and back yields an equal value.
+///
+/// See `assert_roundtrip` for details.
+pub fn assert_json_roundtrip<T>(value: &T)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    assert_roundtrip(Format::Json, value)
+}
+
+/// Asserts that converting the value to TOML and back yields an equal value.
+///
+/// Note that TOML requires a table at the root, so `T` must be serialized as a struct or a map.
+/// See `assert_roundtrip` for details.
+pub fn assert_toml_roundtrip<T>(value: &T)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    assert_roundtrip(Format::Toml, value)
+}
+
+/// Asserts that converting the value to MessagePack and back yields an equal value.
+///
+/// See `assert_roundtrip` for details.
+pub fn assert_msgpack_roundtrip<T>(value: &T)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    assert_roundtrip(Format::MessagePack, value)
+}
+
+/// Shows an encoded document as a string (for text formats) or bytes (for binary formats).
+struct Encoded<'a>(Format, &'a [u8]);
+impl<'a> fmt::Display for Encoded<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.0, str::from_utf8(self.1)) {
+            (Format::Json, Ok(s)) | (Format::Toml, Ok(s)) => write!(f, "{:?}", s),
+            _ => write!(f, "{:?}", self.1),
+        }
+    }
+}
@@ -1,11 +1,15 @@
 use serde::{Deserialize, Serialize};
+use serde_json;
 use std::io::{Read, Write};
 use std::path::Path;
+use toml;
 
 use Result;
 
 /// This trait allows to convert TOML objects to deserializable values.
 ///
+/// This trait is implemented for all deserializable types, so there is no need to implement it manually.
+///
 /// # Examples
 ///
 /// ```
@@ -22,7 +26,6 @@ use Result;
 ///     bar: String,
 ///     baz: usize
 /// }
-/// impl FromToml for Foo {}
 ///
 /// # fn main() {
 /// // Converts from the TOML string to a `Foo` value.
@@ -55,10 +58,82 @@ pub trait FromToml: for<'a> Deserialize<'a> {
     fn from_toml_slice(toml: &[u8]) -> Result<Self> {
         track!(::from_toml_slice(toml))
     }
+
+    /// Converts from the `toml::Value` to an instance of this implementation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate serdeconv;
+    /// extern crate toml;
+    ///
+    /// use serdeconv::{FromToml, ToToml};
+    /// use std::collections::BTreeMap;
+    ///
+    /// # fn main() {
+    /// let mut map = BTreeMap::new();
+    /// map.insert("foo".to_owned(), vec![1, 2]);
+    ///
+    /// let mut value = map.to_toml_value().unwrap();
+    /// value["foo"].as_array_mut().unwrap().push(toml::Value::Integer(3));
+    ///
+    /// let map = BTreeMap::<String, Vec<u8>>::from_toml_value(value).unwrap();
+    /// assert_eq!(map["foo"], [1, 2, 3]);
+    /// # }
+    /// ```
+    fn from_toml_value(toml: toml::Value) -> Result<Self> {
+        track!(::from_toml_value(toml))
+    }
+}
+impl<T: for<'a> Deserialize<'a>> FromToml for T {}
+
+/// This trait allows to validate values after deserializing them from TOML objects.
+///
+/// Unlike `FromToml`, this trait is not implemented automatically;
+/// implement `validate` to check the constraints that cannot be expressed by the type itself
+/// (e.g., cross-field checks), and use `from_toml_str_validated` to deserialize and validate at once.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// #[macro_use]
+/// extern crate trackable;
+/// extern crate serdeconv;
+///
+/// use serdeconv::{ErrorKind, FromTomlValidated};
+///
+/// #[derive(Deserialize)]
+/// struct Range {
+///     min: u32,
+///     max: u32,
+/// }
+/// impl FromTomlValidated for Range {
+///     fn validate(&self) -> serdeconv::Result<()> {
+///         track_assert!(self.min <= self.max, ErrorKind::Invalid, "min must not exceed max");
+///         Ok(())
+///     }
+/// }
+///
+/// # fn main() {
+/// let range: Range = serdeconv::from_toml_str_validated("min = 1\nmax = 10").unwrap();
+/// assert_eq!(range.max, 10);
+///
+/// let result = serdeconv::from_toml_str_validated::<Range>("min = 10\nmax = 1");
+/// assert!(result.is_err());
+/// # }
+/// ```
+pub trait FromTomlValidated: for<'a> Deserialize<'a> {
+    /// Validates this deserialized value.
+    fn validate(&self) -> Result<()>;
 }
 
 /// This trait allows to convert serializable values to TOML objects.
 ///
+/// This trait is implemented for all serializable types, so there is no need to implement it manually.
+///
 /// # Examples
 ///
 /// ```
@@ -75,7 +150,6 @@ pub trait FromToml: for<'a> Deserialize<'a> {
 ///     bar: &'static str,
 ///     baz: usize
 /// }
-/// impl ToToml for Foo {}
 ///
 /// # fn main() {
 /// // Converts the `Foo` value to a TOML string.
@@ -102,10 +176,25 @@ pub trait ToToml: Serialize {
     fn to_toml_string(&self) -> Result<String> {
         track!(::to_toml_string(self))
     }
+
+    /// Converts this to a TOML bytes.
+    fn to_toml_vec(&self) -> Result<Vec<u8>> {
+        track!(::to_toml_vec(self))
+    }
+
+    /// Converts this to a `toml::Value`.
+    ///
+    /// See the documentation of `FromToml::from_toml_value` for an example.
+    fn to_toml_value(&self) -> Result<toml::Value> {
+        track!(::to_toml_value(self))
+    }
 }
+impl<T: ?Sized + Serialize> ToToml for T {}
 
 /// This trait allows to convert JSON objects to deserializable values.
 ///
+/// This trait is implemented for all deserializable types, so there is no need to implement it manually.
+///
 /// # Examples
 ///
 /// ```
@@ -122,7 +211,6 @@ pub trait ToToml: Serialize {
 ///     bar: String,
 ///     baz: usize
 /// }
-/// impl FromJson for Foo {}
 ///
 /// # fn main() {
 /// // Converts from the JSON string to a `Foo` value.
@@ -142,6 +230,9 @@ pub trait FromJson: for<'a> Deserialize<'a> {
     }
 
     /// Reads a JSON string from the reader and converts it to an instance of this implementation.
+    ///
+    /// The implementation cannot borrow from the input read from the reader.
+    /// Use `FromJsonBorrowed::from_json_slice_borrowed` to borrow from a buffer instead.
     fn from_json_reader<R: Read>(reader: R) -> Result<Self> {
         track!(::from_json_reader(reader))
     }
@@ -155,10 +246,99 @@ pub trait FromJson: for<'a> Deserialize<'a> {
     fn from_json_slice(json: &[u8]) -> Result<Self> {
         track!(::from_json_slice(json))
     }
+
+    /// Converts from the `serde_json::Value` to an instance of this implementation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate serde;
+    /// #[macro_use]
+    /// extern crate serde_derive;
+    /// extern crate serde_json;
+    /// extern crate serdeconv;
+    ///
+    /// use serdeconv::{FromJson, ToJson};
+    ///
+    /// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    /// struct Foo {
+    ///     bar: String,
+    ///     baz: usize,
+    /// }
+    ///
+    /// # fn main() {
+    /// let foo = Foo { bar: "aaa".to_owned(), baz: 123 };
+    ///
+    /// let mut value = foo.to_json_value().unwrap();
+    /// value["baz"] = serde_json::Value::from(456);
+    ///
+    /// let foo = Foo::from_json_value(value).unwrap();
+    /// assert_eq!(foo, Foo { bar: "aaa".to_owned(), baz: 456 });
+    /// # }
+    /// ```
+    fn from_json_value(json: serde_json::Value) -> Result<Self> {
+        track!(::from_json_value(json))
+    }
+}
+impl<T: for<'a> Deserialize<'a>> FromJson for T {}
+
+/// This trait allows to convert JSON objects to deserializable values that borrow from the input.
+///
+/// Unlike `FromJson`, this trait does not require the implementation to own all of its data,
+/// so types holding references (e.g., `&'a str`) to the input can be deserialized.
+///
+/// This trait is implemented for all deserializable types, so there is no need to implement it manually.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// use serdeconv::FromJsonBorrowed;
+///
+/// // Defines a deserializable struct which borrows from the input.
+/// #[derive(Deserialize)]
+/// struct Foo<'a> {
+///     bar: &'a str,
+///     baz: usize
+/// }
+///
+/// # fn main() {
+/// let json = r#"{"bar": "aaa", "baz": 123}"#;
+/// let foo = Foo::from_json_str_borrowed(json).unwrap();
+/// assert_eq!(foo.bar, "aaa");
+/// assert_eq!(foo.baz, 123);
+/// # }
+/// ```
+pub trait FromJsonBorrowed<'a>: Deserialize<'a> {
+    /// Converts from the JSON string to an instance of this implementation.
+    fn from_json_str_borrowed(json: &'a str) -> Result<Self> {
+        track!(::from_json_str(json))
+    }
+
+    /// Converts from the JSON bytes to an instance of this implementation.
+    fn from_json_slice_borrowed(json: &'a [u8]) -> Result<Self> {
+        track!(::from_json_slice(json))
+    }
+}
+impl<'a, T: Deserialize<'a>> FromJsonBorrowed<'a> for T {}
+
+/// This trait allows to validate values after deserializing them from JSON objects.
+///
+/// This is the JSON counterpart of `FromTomlValidated`;
+/// implement `validate` and use `from_json_str_validated` to deserialize and validate at once.
+pub trait FromJsonValidated: for<'a> Deserialize<'a> {
+    /// Validates this deserialized value.
+    fn validate(&self) -> Result<()>;
 }
 
 /// This trait allows to convert serializable values to JSON objects.
 ///
+/// This trait is implemented for all serializable types, so there is no need to implement it manually.
+///
 /// # Examples
 ///
 /// ```
@@ -175,7 +355,6 @@ pub trait FromJson: for<'a> Deserialize<'a> {
 ///     bar: &'static str,
 ///     baz: usize
 /// }
-/// impl ToJson for Foo {}
 ///
 /// # fn main() {
 /// // Converts the `Foo` value to a JSON string.
@@ -209,9 +388,29 @@ pub trait ToJson: Serialize {
     fn to_json_string_pretty(&self) -> Result<String> {
         track!(::to_json_string_pretty(self))
     }
+
+    /// Converts this to a `serde_json::Value`.
+    ///
+    /// See the documentation of `FromJson::from_json_value` for an example.
+    fn to_json_value(&self) -> Result<serde_json::Value> {
+        track!(::to_json_value(self))
+    }
+
+    /// Converts this to a JSON bytes.
+    fn to_json_vec(&self) -> Result<Vec<u8>> {
+        track!(::to_json_vec(self))
+    }
+
+    /// Converts this to a pretty printed JSON bytes.
+    fn to_json_vec_pretty(&self) -> Result<Vec<u8>> {
+        track!(::to_json_vec_pretty(self))
+    }
 }
+impl<T: ?Sized + Serialize> ToJson for T {}
 
 /// This trait allows to convert MessagePack binaries to deserializable values.
+///
+/// This trait is implemented for all deserializable types, so there is no need to implement it manually.
 pub trait FromMsgPack: for<'a> Deserialize<'a> {
     /// Converts from the MessagePack file to an instance of this implementation.
     fn from_msgpack_file<P: AsRef<Path>>(path: P) -> Result<Self> {
@@ -220,6 +419,17 @@ pub trait FromMsgPack: for<'a> Deserialize<'a> {
 
     /// Reads a MessagePack bytes from the reader and
     /// converts it to an instance of this implementation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serdeconv::FromMsgPack;
+    /// use std::io::Cursor;
+    ///
+    /// let reader = Cursor::new(vec![0x92, 0xa3, b'f', b'o', b'o', 0x07]);
+    /// let value = <(String, u8)>::from_msgpack_reader(reader).unwrap();
+    /// assert_eq!(value, ("foo".to_owned(), 7));
+    /// ```
     fn from_msgpack_reader<R: Read>(reader: R) -> Result<Self> {
         track!(::from_msgpack_reader(reader))
     }
@@ -229,8 +439,11 @@ pub trait FromMsgPack: for<'a> Deserialize<'a> {
         track!(::from_msgpack_slice(toml))
     }
 }
+impl<T: for<'a> Deserialize<'a>> FromMsgPack for T {}
 
 /// This trait allows to convert serializable values to MessagePack binaries.
+///
+/// This trait is implemented for all serializable types, so there is no need to implement it manually.
 pub trait ToMsgPack: Serialize {
     /// Converts this to a MessagePack bytes and writes it to the speficied file.
     fn to_msgpack_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
@@ -238,6 +451,16 @@ pub trait ToMsgPack: Serialize {
     }
 
     /// Converts this to a MessagePack bytes and writes it to the writer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serdeconv::ToMsgPack;
+    ///
+    /// let mut buf = Vec::new();
+    /// ("foo", 7).to_msgpack_writer(&mut buf).unwrap();
+    /// assert_eq!(buf, [0x92, 0xa3, b'f', b'o', b'o', 0x07]);
+    /// ```
     fn to_msgpack_writer<W: Write>(&self, writer: W) -> Result<()> {
         track!(::to_msgpack_writer(self, writer))
     }
@@ -247,3 +470,150 @@ pub trait ToMsgPack: Serialize {
         track!(::to_msgpack_vec(self))
     }
 }
+impl<T: ?Sized + Serialize> ToMsgPack for T {}
+
+/// This trait allows to convert BSON binaries to deserializable values.
+///
+/// This trait is implemented for all deserializable types, so there is no need to implement it manually.
+#[cfg(feature = "bson")]
+pub trait FromBson: for<'a> Deserialize<'a> {
+    /// Converts from the BSON file to an instance of this implementation.
+    fn from_bson_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        track!(::from_bson_file(path))
+    }
+
+    /// Reads a BSON bytes from the reader and
+    /// converts it to an instance of this implementation.
+    fn from_bson_reader<R: Read>(reader: R) -> Result<Self> {
+        track!(::from_bson_reader(reader))
+    }
+
+    /// Converts from the BSON bytes to an instance of this implementation.
+    fn from_bson_slice(bytes: &[u8]) -> Result<Self> {
+        track!(::from_bson_slice(bytes))
+    }
+}
+#[cfg(feature = "bson")]
+impl<T: for<'a> Deserialize<'a>> FromBson for T {}
+
+/// This trait allows to convert serializable values to BSON binaries.
+///
+/// This trait is implemented for all serializable types, so there is no need to implement it manually.
+#[cfg(feature = "bson")]
+pub trait ToBson: Serialize {
+    /// Converts this to a BSON bytes and writes it to the speficied file.
+    fn to_bson_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        track!(::to_bson_file(self, path))
+    }
+
+    /// Converts this to a BSON bytes and writes it to the writer.
+    fn to_bson_writer<W: Write>(&self, writer: W) -> Result<()> {
+        track!(::to_bson_writer(self, writer))
+    }
+
+    /// Converts this to a BSON bytes.
+    fn to_bson_vec(&self) -> Result<Vec<u8>> {
+        track!(::to_bson_vec(self))
+    }
+}
+#[cfg(feature = "bson")]
+impl<T: ?Sized + Serialize> ToBson for T {}
+
+/// This trait allows to convert Smile binaries to deserializable values.
+///
+/// This trait is implemented for all deserializable types, so there is no need to implement it manually.
+#[cfg(feature = "smile")]
+pub trait FromSmile: for<'a> Deserialize<'a> {
+    /// Converts from the Smile file to an instance of this implementation.
+    fn from_smile_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        track!(::from_smile_file(path))
+    }
+
+    /// Reads a Smile bytes from the reader and
+    /// converts it to an instance of this implementation.
+    fn from_smile_reader<R: Read>(reader: R) -> Result<Self> {
+        track!(::from_smile_reader(reader))
+    }
+
+    /// Converts from the Smile bytes to an instance of this implementation.
+    fn from_smile_slice(bytes: &[u8]) -> Result<Self> {
+        track!(::from_smile_slice(bytes))
+    }
+}
+#[cfg(feature = "smile")]
+impl<T: for<'a> Deserialize<'a>> FromSmile for T {}
+
+/// This trait allows to convert serializable values to Smile binaries.
+///
+/// This trait is implemented for all serializable types, so there is no need to implement it manually.
+#[cfg(feature = "smile")]
+pub trait ToSmile: Serialize {
+    /// Converts this to a Smile bytes and writes it to the speficied file.
+    fn to_smile_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        track!(::to_smile_file(self, path))
+    }
+
+    /// Converts this to a Smile bytes and writes it to the writer.
+    fn to_smile_writer<W: Write>(&self, writer: W) -> Result<()> {
+        track!(::to_smile_writer(self, writer))
+    }
+
+    /// Converts this to a Smile bytes.
+    fn to_smile_vec(&self) -> Result<Vec<u8>> {
+        track!(::to_smile_vec(self))
+    }
+}
+#[cfg(feature = "smile")]
+impl<T: ?Sized + Serialize> ToSmile for T {}
+
+/// This trait allows to convert KDL documents to deserializable values.
+///
+/// This trait is implemented for all deserializable types, so there is no need to implement it manually.
+#[cfg(feature = "kdl")]
+pub trait FromKdl: for<'a> Deserialize<'a> {
+    /// Converts from the KDL file to an instance of this implementation.
+    fn from_kdl_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        track!(::from_kdl_file(path))
+    }
+
+    /// Reads a KDL string from the reader and
+    /// converts it to an instance of this implementation.
+    fn from_kdl_reader<R: Read>(reader: R) -> Result<Self> {
+        track!(::from_kdl_reader(reader))
+    }
+
+    /// Converts from the KDL string to an instance of this implementation.
+    fn from_kdl_str(kdl: &str) -> Result<Self> {
+        track!(::from_kdl_str(kdl))
+    }
+
+    /// Converts from the KDL bytes to an instance of this implementation.
+    fn from_kdl_slice(kdl: &[u8]) -> Result<Self> {
+        track!(::from_kdl_slice(kdl))
+    }
+}
+#[cfg(feature = "kdl")]
+impl<T: for<'a> Deserialize<'a>> FromKdl for T {}
+
+/// This trait allows to convert serializable values to KDL documents.
+///
+/// This trait is implemented for all serializable types, so there is no need to implement it manually.
+#[cfg(feature = "kdl")]
+pub trait ToKdl: Serialize {
+    /// Converts this to a KDL string and writes it to the speficied file.
+    fn to_kdl_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        track!(::to_kdl_file(self, path))
+    }
+
+    /// Converts this to a KDL string and writes it to the writer.
+    fn to_kdl_writer<W: Write>(&self, writer: W) -> Result<()> {
+        track!(::to_kdl_writer(self, writer))
+    }
+
+    /// Converts this to a KDL string.
+    fn to_kdl_string(&self) -> Result<String> {
+        track!(::to_kdl_string(self))
+    }
+}
+#[cfg(feature = "kdl")]
+impl<T: ?Sized + Serialize> ToKdl for T {}
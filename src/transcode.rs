@@ -0,0 +1,294 @@
+use rmp_serde;
+use serde::de::IgnoredAny;
+use serde::Deserializer;
+use serde_json;
+#[cfg(feature = "smile")]
+use serde_smile;
+use serde_transcode;
+use std::io::Write;
+use std::str;
+use toml;
+
+use util::strip_bom;
+use {
+    from_slice_with_format, from_toml_str, to_json_string, to_json_string_pretty, to_json_writer,
+    to_json_writer_pretty, to_toml_string, to_toml_writer, Error, Format, Result,
+};
+
+/// Converts the bytes encoded in the `from` format to the bytes encoded in the `to` format.
+///
+/// The input is transcoded directly from the deserializer of `from` to the serializer of `to`,
+/// so no intermediate value (neither a typed value nor a value such as `serde_json::Value`) is built.
+///
+/// The data models of the formats differ, so some conversions fail
+/// (e.g., a JSON document containing `null` or having a non-object top-level value cannot be converted to TOML).
+/// Note that TOML datetimes are transcoded as the raw objects that the `toml` crate uses internally
+/// (see `toml_value_to_json_value_raw`).
+///
+/// # Examples
+///
+/// ```
+/// use serdeconv::Format;
+///
+/// let msgpack = serdeconv::transcode(br#"{"foo": [1, "bar"]}"#, Format::Json, Format::MessagePack).unwrap();
+/// let toml = serdeconv::transcode(&msgpack, Format::MessagePack, Format::Toml).unwrap();
+/// assert_eq!(String::from_utf8(toml).unwrap(), "foo = [1, \"bar\"]\n");
+///
+/// // `Error::format` tells which side failed.
+/// let error = serdeconv::transcode(b"[1,", Format::Json, Format::MessagePack).unwrap_err();
+/// assert_eq!(error.format(), Some(Format::Json));
+///
+/// let error = serdeconv::transcode(b"[1, 2]", Format::Json, Format::Toml).unwrap_err();
+/// assert_eq!(error.format(), Some(Format::Toml));
+/// ```
+pub fn transcode(input: &[u8], from: Format, to: Format) -> Result<Vec<u8>> {
+    track!(transcode_from(input, from, to)).map_err(|e| {
+        // `serde_transcode` reports the errors of the deserializer as ones of the serializer,
+        // so the failed side is determined by checking whether the input can be decoded.
+        if from_slice_with_format::<IgnoredAny>(from, input).is_err() {
+            e.with_format(from)
+        } else {
+            e.with_format(to)
+        }
+    })
+}
+
+fn transcode_from(input: &[u8], from: Format, to: Format) -> Result<Vec<u8>> {
+    match from {
+        Format::Json => {
+            let mut de = serde_json::Deserializer::from_slice(strip_bom(input));
+            let output = track!(transcode_to(&mut de, to))?;
+            track!(de.end().map_err(Error::from))?;
+            Ok(output)
+        }
+        Format::Toml => {
+            let toml = track!(str::from_utf8(strip_bom(input)).map_err(Error::from))?;
+            track!(transcode_to(toml::Deserializer::new(toml), to))
+        }
+        Format::MessagePack => {
+            let mut de = rmp_serde::Deserializer::from_read_ref(input);
+            track!(transcode_to(&mut de, to))
+        }
+        #[cfg(feature = "smile")]
+        Format::Smile => {
+            let mut de = serde_smile::Deserializer::from_slice(input);
+            let output = track!(transcode_to(&mut de, to))?;
+            track!(de.end().map_err(Error::from))?;
+            Ok(output)
+        }
+    }
+}
+
+fn transcode_to<'de, D>(de: D, to: Format) -> Result<Vec<u8>>
+where
+    D: Deserializer<'de>,
+{
+    let mut output = Vec::new();
+    match to {
+        Format::Json => {
+            let mut ser = serde_json::Serializer::new(&mut output);
+            track!(serde_transcode::transcode(de, &mut ser).map_err(Error::from))?;
+        }
+        Format::Toml => {
+            let mut toml = String::new();
+            let ser = toml::Serializer::new(&mut toml);
+            track!(serde_transcode::transcode(de, ser).map_err(Error::from))?;
+            output = toml.into_bytes();
+        }
+        Format::MessagePack => {
+            let mut ser = rmp_serde::Serializer::new(&mut output);
+            track!(serde_transcode::transcode(de, &mut ser).map_err(Error::from))?;
+        }
+        #[cfg(feature = "smile")]
+        Format::Smile => {
+            let mut ser = serde_smile::Serializer::new(&mut output);
+            track!(serde_transcode::transcode(de, &mut ser).map_err(Error::from))?;
+        }
+    }
+    Ok(output)
+}
+
+/// Converts the TOML value to a JSON value.
+///
+/// TOML datetimes are converted to plain strings in RFC 3339 format
+/// (local datetimes, local dates and local times are converted to the corresponding
+/// subset of RFC 3339, e.g., `"1979-05-27T07:32:00"`, `"1979-05-27"` and `"07:32:00"`).
+/// Use `toml_value_to_json_value_raw` if the datetimes need to be restored from the JSON value.
+///
+/// Non-finite floats cannot be represented in JSON, and are converted to `null`.
+///
+/// # Examples
+///
+/// ```
+/// let toml: toml::Value = serdeconv::from_toml_str(
+///     "odt = 1979-05-27T07:32:00-08:00\nldt = 1979-05-27T07:32:00\nld = 1979-05-27",
+/// )
+/// .unwrap();
+/// let json = serdeconv::toml_value_to_json_value(toml);
+/// assert_eq!(
+///     json.to_string(),
+///     r#"{"ld":"1979-05-27","ldt":"1979-05-27T07:32:00","odt":"1979-05-27T07:32:00-08:00"}"#
+/// );
+/// ```
+pub fn toml_value_to_json_value(value: toml::Value) -> serde_json::Value {
+    match value {
+        toml::Value::Boolean(v) => serde_json::Value::Bool(v),
+        toml::Value::Integer(v) => serde_json::Value::from(v),
+        toml::Value::Float(v) => serde_json::Value::from(v),
+        toml::Value::String(v) => serde_json::Value::String(v),
+        toml::Value::Datetime(v) => serde_json::Value::String(v.to_string()),
+        toml::Value::Array(v) => {
+            serde_json::Value::Array(v.into_iter().map(toml_value_to_json_value).collect())
+        }
+        toml::Value::Table(v) => serde_json::Value::Object(
+            v.into_iter()
+                .map(|(k, v)| (k, toml_value_to_json_value(v)))
+                .collect(),
+        ),
+    }
+}
+
+/// Converts the TOML value to a JSON value, keeping the raw representation of datetimes.
+///
+/// TOML datetimes are converted to objects of the form `{"$__toml_private_datetime": "..."}`
+/// that the `toml` crate uses internally.
+/// The output is not portable, but it can be converted back to the same TOML value.
+///
+/// Non-finite floats cannot be represented in JSON, and result in an `ErrorKind::Invalid` error.
+///
+/// # Examples
+///
+/// ```
+/// let toml: toml::Value = serdeconv::from_toml_str("ld = 1979-05-27").unwrap();
+/// let json = serdeconv::toml_value_to_json_value_raw(toml.clone()).unwrap();
+/// assert_eq!(json.to_string(), r#"{"ld":{"$__toml_private_datetime":"1979-05-27"}}"#);
+///
+/// let restored: toml::Value = serdeconv::from_json_value(json).unwrap();
+/// assert_eq!(restored, toml);
+/// ```
+pub fn toml_value_to_json_value_raw(value: toml::Value) -> Result<serde_json::Value> {
+    track!(serde_json::to_value(value).map_err(Error::from))
+}
+
+/// Converts the TOML string to a JSON string.
+///
+/// TOML datetimes are converted to plain strings as `toml_value_to_json_value` does.
+///
+/// # Examples
+///
+/// ```
+/// let json = serdeconv::transcode_toml_to_json("[a]\nb = 1\nc = 2024-01-02T03:04:05Z").unwrap();
+/// assert_eq!(json, r#"{"a":{"b":1,"c":"2024-01-02T03:04:05Z"}}"#);
+/// ```
+pub fn transcode_toml_to_json(toml: &str) -> Result<String> {
+    let value: toml::Value = track!(from_toml_str(toml))?;
+    let json =
+        track!(serde_json::to_string(&toml_value_to_json_value(value)).map_err(Error::from))?;
+    Ok(json)
+}
+
+/// Converts the TOML string to a JSON string, keeping the raw representation of datetimes.
+///
+/// See `toml_value_to_json_value_raw` for the representation of datetimes.
+pub fn transcode_toml_to_json_raw(toml: &str) -> Result<String> {
+    let value: toml::Value = track!(from_toml_str(toml))?;
+    let json = track!(serde_json::to_string(&value).map_err(Error::from))?;
+    Ok(json)
+}
+
+/// Converts the TOML value to a JSON string and writes it to the writer.
+///
+/// TOML datetimes are converted to plain strings as `toml_value_to_json_value` does,
+/// so this is handy for dumping a TOML document of an unknown schema as JSON.
+///
+/// # Examples
+///
+/// ```
+/// let toml: toml::Value = serdeconv::from_toml_str(r#"
+/// name = "foo"
+///
+/// [server]
+/// ports = [80, 443]
+/// started = 2024-01-02T03:04:05Z
+/// "#).unwrap();
+///
+/// let mut buf = Vec::new();
+/// serdeconv::to_json_writer_from_toml_value(&toml, &mut buf).unwrap();
+/// assert_eq!(
+///     String::from_utf8(buf).unwrap(),
+///     r#"{"name":"foo","server":{"ports":[80,443],"started":"2024-01-02T03:04:05Z"}}"#
+/// );
+///
+/// let json = serdeconv::to_json_string_pretty_from_toml_value(&toml).unwrap();
+/// assert!(json.contains("\n  \"server\": {\n    \"ports\": [\n      80,"));
+/// ```
+pub fn to_json_writer_from_toml_value<W: Write>(value: &toml::Value, writer: W) -> Result<()> {
+    track!(to_json_writer(
+        &toml_value_to_json_value(value.clone()),
+        writer
+    ))
+}
+
+/// Converts the TOML value to a pretty printed JSON string and writes it to the writer.
+///
+/// See `to_json_writer_from_toml_value` for the conversion of the value.
+pub fn to_json_writer_pretty_from_toml_value<W: Write>(
+    value: &toml::Value,
+    writer: W,
+) -> Result<()> {
+    track!(to_json_writer_pretty(
+        &toml_value_to_json_value(value.clone()),
+        writer
+    ))
+}
+
+/// Converts the TOML value to a JSON string.
+///
+/// See `to_json_writer_from_toml_value` for the conversion of the value.
+pub fn to_json_string_from_toml_value(value: &toml::Value) -> Result<String> {
+    track!(to_json_string(&toml_value_to_json_value(value.clone())))
+}
+
+/// Converts the TOML value to a pretty printed JSON string.
+///
+/// See `to_json_writer_from_toml_value` for the conversion of the value.
+pub fn to_json_string_pretty_from_toml_value(value: &toml::Value) -> Result<String> {
+    track!(to_json_string_pretty(&toml_value_to_json_value(
+        value.clone()
+    )))
+}
+
+/// Converts the JSON value to a TOML string and writes it to the writer.
+///
+/// The value must be an object, and must not contain `null`s
+/// (TOML has no counterparts of them); otherwise an error is returned.
+///
+/// # Examples
+///
+/// ```
+/// let json: serde_json::Value =
+///     serdeconv::from_json_str(r#"{"server": {"ports": [80, 443]}, "name": "foo"}"#).unwrap();
+///
+/// let mut buf = Vec::new();
+/// serdeconv::to_toml_writer_from_json_value(&json, &mut buf).unwrap();
+/// assert_eq!(
+///     String::from_utf8(buf).unwrap(),
+///     "name = \"foo\"\n\n[server]\nports = [80, 443]\n"
+/// );
+///
+/// let json: serde_json::Value = serdeconv::from_json_str(r#"{"foo": null}"#).unwrap();
+/// assert!(serdeconv::to_toml_string_from_json_value(&json).is_err());
+/// ```
+pub fn to_toml_writer_from_json_value<W: Write>(
+    value: &serde_json::Value,
+    writer: W,
+) -> Result<()> {
+    track!(to_toml_writer(value, writer))
+}
+
+/// Converts the JSON value to a TOML string.
+///
+/// See `to_toml_writer_from_json_value` for the requirements on the value.
+pub fn to_toml_string_from_json_value(value: &serde_json::Value) -> Result<String> {
+    track!(to_toml_string(value))
+}
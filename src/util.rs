@@ -0,0 +1,351 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use {Error, ErrorKind, Result};
+
+/// A writer which counts the number of bytes written to the inner writer.
+#[derive(Debug)]
+pub(crate) struct CountingWriter<W> {
+    inner: W,
+    count: usize,
+}
+impl<W: Write> CountingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        CountingWriter { inner, count: 0 }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let size = self.inner.write(buf)?;
+        self.count += size;
+        Ok(size)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A scanner which tracks the nesting depth of arrays and objects in a JSON text.
+#[derive(Debug)]
+pub(crate) struct JsonDepthScanner {
+    max_depth: usize,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+}
+impl JsonDepthScanner {
+    pub fn new(max_depth: usize) -> Self {
+        JsonDepthScanner {
+            max_depth,
+            depth: 0,
+            in_string: false,
+            escaped: false,
+        }
+    }
+
+    pub fn scan(&mut self, bytes: &[u8]) -> Result<()> {
+        for &b in bytes {
+            if self.in_string {
+                if self.escaped {
+                    self.escaped = false;
+                } else if b == b'\\' {
+                    self.escaped = true;
+                } else if b == b'"' {
+                    self.in_string = false;
+                }
+                continue;
+            }
+            match b {
+                b'"' => self.in_string = true,
+                b'[' | b'{' => {
+                    self.depth += 1;
+                    track_assert!(
+                        self.depth <= self.max_depth,
+                        ErrorKind::Invalid,
+                        "Maximum nesting depth exceeded: max_depth={}",
+                        self.max_depth
+                    );
+                }
+                b']' | b'}' => self.depth = self.depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A reader which fails if the nesting depth of the JSON text read from the inner reader exceeds the limit.
+#[derive(Debug)]
+pub(crate) struct JsonDepthLimitedReader<R> {
+    inner: R,
+    scanner: JsonDepthScanner,
+}
+impl<R: Read> JsonDepthLimitedReader<R> {
+    pub fn new(inner: R, max_depth: usize) -> Self {
+        JsonDepthLimitedReader {
+            inner,
+            scanner: JsonDepthScanner::new(max_depth),
+        }
+    }
+}
+impl<R: Read> Read for JsonDepthLimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let size = self.inner.read(buf)?;
+        self.scanner
+            .scan(&buf[..size])
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(size)
+    }
+}
+
+/// Reads all bytes from the reader, failing if the size exceeds `max_bytes`.
+pub(crate) fn read_to_end_limited<R: Read>(reader: R, max_bytes: usize) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    track!(reader
+        .take(max_bytes as u64 + 1)
+        .read_to_end(&mut buf)
+        .map_err(Error::from))?;
+    track!(check_input_size(buf.len(), max_bytes))?;
+    Ok(buf)
+}
+
+/// Fails if the input size `len` exceeds `max_bytes`.
+pub(crate) fn check_input_size(len: usize, max_bytes: usize) -> Result<()> {
+    track_assert!(
+        len <= max_bytes,
+        ErrorKind::Invalid,
+        "Input size limit exceeded: max_bytes={}",
+        max_bytes
+    );
+    Ok(())
+}
+
+/// Writes a file atomically.
+///
+/// `f` writes the contents to a newly created temporary sibling file
+/// (named uniquely per process and call, so that concurrent writers never share it),
+/// which is then flushed, synced to disk and renamed to `path`.
+/// On Unix, the parent directory is also synced so that the rename itself is durable.
+/// The temporary file is removed if any of the steps fails.
+pub(crate) fn write_file_atomic<F>(path: &Path, f: F) -> Result<()>
+where
+    F: FnOnce(&mut File) -> Result<()>,
+{
+    static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let file_name = track_assert_some!(
+        path.file_name(),
+        ErrorKind::Invalid,
+        "Not a file path: {:?}",
+        path
+    );
+    let (temp_path, mut file) = loop {
+        let mut temp_file_name = file_name.to_os_string();
+        temp_file_name.push(format!(
+            ".{}.{}.tmp",
+            process::id(),
+            TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let temp_path = path.with_file_name(temp_file_name);
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)
+        {
+            Ok(file) => break (temp_path, file),
+            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => track!(Err(Error::from(e)), "path={:?}", temp_path)?,
+        }
+    };
+
+    let result: Result<()> = (|| {
+        track!(f(&mut file))?;
+        track!(file.flush().map_err(Error::from))?;
+        track!(file.sync_all().map_err(Error::from))?;
+        track!(fs::rename(&temp_path, path).map_err(Error::from))?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    track!(result)?;
+    track!(sync_parent_dir(path))
+}
+
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let dir = track!(File::open(dir).map_err(Error::from))?;
+    track!(dir.sync_all().map_err(Error::from))
+}
+
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Strips a leading UTF-8 BOM (if any) from the bytes.
+pub(crate) fn strip_bom(bytes: &[u8]) -> &[u8] {
+    if bytes.starts_with(UTF8_BOM) {
+        &bytes[UTF8_BOM.len()..]
+    } else {
+        bytes
+    }
+}
+
+/// Strips a leading UTF-8 BOM (if any) from the string.
+pub(crate) fn strip_bom_str(s: &str) -> &str {
+    s.strip_prefix('\u{FEFF}').unwrap_or(s)
+}
+
+/// Returns a reader which yields the same bytes as `reader` except for a leading UTF-8 BOM.
+pub(crate) fn skip_bom<R: Read>(mut reader: R) -> Result<io::Chain<io::Cursor<Vec<u8>>, R>> {
+    let mut head = Vec::with_capacity(UTF8_BOM.len());
+    track!((&mut reader)
+        .take(UTF8_BOM.len() as u64)
+        .read_to_end(&mut head)
+        .map_err(Error::from))?;
+    if head == UTF8_BOM {
+        head.clear();
+    }
+    Ok(io::Cursor::new(head).chain(reader))
+}
+
+#[cfg(any(feature = "protojson", feature = "byte-encoding"))]
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes the bytes as a standard base64 string (with padding).
+#[cfg(any(feature = "protojson", feature = "byte-encoding"))]
+pub(crate) fn encode_base64(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (u32::from(b) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                s.push(BASE64_CHARS[(n >> (18 - 6 * i)) as usize & 0x3f] as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
+/// Decodes the standard or URL-safe base64 string (with or without padding).
+///
+/// Returns `None` if the string is malformed, i.e., if it contains characters out of the alphabets,
+/// its length is impossible (`len % 4 == 1`), the unused trailing bits are not zero,
+/// or the padding is misplaced, excessive or followed by other characters.
+#[cfg(any(feature = "protojson", feature = "byte-encoding"))]
+pub(crate) fn decode_base64(s: &str) -> Option<Vec<u8>> {
+    let (data, padding) = match s.find('=') {
+        Some(i) => (&s[..i], &s[i..]),
+        None => (s, ""),
+    };
+    let remainder = data.len() % 4;
+    if remainder == 1 {
+        return None;
+    }
+    if !padding.is_empty()
+        && (padding.bytes().any(|c| c != b'=') || remainder == 0 || padding.len() != 4 - remainder)
+    {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(data.len() / 4 * 3 + 2);
+    let mut n = 0u32;
+    let mut bits = 0;
+    for c in data.bytes() {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+        n = ((n << 6) | u32::from(v)) & 0xFFFF;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((n >> bits) as u8);
+        }
+    }
+    if n & ((1 << bits) - 1) != 0 {
+        return None;
+    }
+    Some(bytes)
+}
+
+/// Encodes the bytes as a lowercase hexadecimal string.
+#[cfg(feature = "byte-encoding")]
+pub(crate) fn encode_hex(bytes: &[u8]) -> String {
+    const HEX_CHARS: &[u8; 16] = b"0123456789abcdef";
+    let mut s = String::with_capacity(bytes.len() * 2);
+    for &b in bytes {
+        s.push(HEX_CHARS[usize::from(b >> 4)] as char);
+        s.push(HEX_CHARS[usize::from(b & 0x0f)] as char);
+    }
+    s
+}
+
+/// Decodes the hexadecimal string (case-insensitive).
+#[cfg(feature = "byte-encoding")]
+pub(crate) fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let hi = (pair[0] as char).to_digit(16)?;
+            let lo = (pair[1] as char).to_digit(16)?;
+            Some((hi << 4 | lo) as u8)
+        })
+        .collect()
+}
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 == 1 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
+
+/// Calculates the CRC-32 (IEEE 802.3) checksum of the bytes.
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |c, &b| {
+        CRC32_TABLE[((c ^ u32::from(b)) & 0xff) as usize] ^ (c >> 8)
+    })
+}
@@ -0,0 +1,66 @@
+use rmp_serde;
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use serde_json;
+use std::str;
+use toml;
+
+use util::strip_bom;
+use {Error, ErrorKind, Result};
+
+/// Checks that the bytes are a syntactically valid JSON text.
+///
+/// The input is parsed without building any value, so this is cheaper than
+/// deserializing it into a concrete type or `serde_json::Value`.
+///
+/// # Examples
+///
+/// ```
+/// assert!(serdeconv::validate_json(br#"{"foo": [1, 2]}"#).is_ok());
+/// assert!(serdeconv::validate_json(br#"{"foo": [1, 2}"#).unwrap_err().is_invalid());
+/// assert!(serdeconv::validate_json(b"1 2").is_err());
+/// assert!(serdeconv::validate_json(b"\xEF\xBB\xBF{\"foo\": 1}").is_ok());
+/// ```
+pub fn validate_json(json: &[u8]) -> Result<()> {
+    track!(serde_json::from_slice::<IgnoredAny>(strip_bom(json)).map_err(Error::from))?;
+    Ok(())
+}
+
+/// Checks that the bytes are a syntactically valid TOML document.
+///
+/// # Examples
+///
+/// ```
+/// assert!(serdeconv::validate_toml(b"[foo]\nbar = 1").is_ok());
+/// assert!(serdeconv::validate_toml(b"[foo]\nbar = ").unwrap_err().is_invalid());
+/// assert!(serdeconv::validate_toml(b"foo = 1\nfoo = 2").is_err());
+/// ```
+pub fn validate_toml(toml: &[u8]) -> Result<()> {
+    let toml = track!(str::from_utf8(toml).map_err(Error::from))?;
+    track!(toml::from_str::<IgnoredAny>(toml).map_err(Error::from))?;
+    Ok(())
+}
+
+/// Checks that the bytes are exactly one syntactically valid MessagePack value.
+///
+/// Trailing bytes after the value are regarded as an error.
+///
+/// # Examples
+///
+/// ```
+/// assert!(serdeconv::validate_msgpack(&[0x92, 0x01, 0x02]).is_ok());
+/// assert!(serdeconv::validate_msgpack(&[0x92, 0x01]).unwrap_err().is_invalid());
+/// assert!(serdeconv::validate_msgpack(&[0x01, 0x02]).is_err());
+/// ```
+pub fn validate_msgpack(bytes: &[u8]) -> Result<()> {
+    let mut deserializer = rmp_serde::Deserializer::new(bytes);
+    track!(IgnoredAny::deserialize(&mut deserializer).map_err(Error::from))?;
+    let rest = deserializer.get_ref().len();
+    track_assert_eq!(
+        rest,
+        0,
+        ErrorKind::Invalid,
+        "Trailing bytes after the MessagePack value"
+    );
+    Ok(())
+}
@@ -0,0 +1,800 @@
+//! Value types which complement the ones provided by the underlying format crates.
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use toml;
+
+use {Error, ErrorKind};
+
+/// A format-neutral value.
+///
+/// This can be converted from/to `serde_json::Value` and `toml::Value`,
+/// and also can be used as the target type of any deserialization function in this crate
+/// (e.g., `serdeconv::from_toml_str::<Value>(...)`).
+///
+/// # Lossy conversions
+///
+/// The formats have different data models, so some conversions are lossy or fallible:
+///
+/// - TOML has no null: converting a `Value` containing `Null` to a `toml::Value` fails.
+/// - TOML datetimes are converted to `Value::String` in RFC 3339 format.
+/// - JSON does not distinguish integers from floats strictly:
+///   numbers that fit in `i64` become `Value::Integer`, and the others become `Value::Float`
+///   (thus `u64` values greater than `i64::MAX` lose precision).
+/// - JSON cannot represent non-finite floats: converting them to a `serde_json::Value` fails.
+/// - Neither JSON nor TOML have a bytes type: `Value::Bytes` is converted to an array of integers.
+///
+/// # Examples
+///
+/// ```
+/// use serdeconv::Value;
+/// use std::convert::TryFrom;
+///
+/// let value: Value = serdeconv::from_toml_str("foo = [1, 2.5, \"bar\"]\nqux = 1979-05-27").unwrap();
+/// let json = serde_json::Value::try_from(value).unwrap();
+/// assert_eq!(json.to_string(), r#"{"foo":[1,2.5,"bar"],"qux":"1979-05-27"}"#);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// Null value.
+    Null,
+
+    /// Boolean value.
+    Bool(bool),
+
+    /// Integer value.
+    Integer(i64),
+
+    /// Floating point number value.
+    Float(f64),
+
+    /// String value.
+    String(String),
+
+    /// Array value.
+    Array(Vec<Value>),
+
+    /// Object (a.k.a., map or table) value.
+    Object(BTreeMap<String, Value>),
+
+    /// Byte array value.
+    Bytes(Vec<u8>),
+}
+impl From<serde_json::Value> for Value {
+    fn from(f: serde_json::Value) -> Self {
+        match f {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(v) => Value::Bool(v),
+            serde_json::Value::Number(v) => {
+                if let Some(v) = v.as_i64() {
+                    Value::Integer(v)
+                } else {
+                    Value::Float(v.as_f64().unwrap_or(f64::NAN))
+                }
+            }
+            serde_json::Value::String(v) => Value::String(v),
+            serde_json::Value::Array(v) => Value::Array(v.into_iter().map(Value::from).collect()),
+            serde_json::Value::Object(v) => {
+                Value::Object(v.into_iter().map(|(k, v)| (k, Value::from(v))).collect())
+            }
+        }
+    }
+}
+impl TryFrom<Value> for serde_json::Value {
+    type Error = Error;
+
+    fn try_from(f: Value) -> Result<Self, Error> {
+        Ok(match f {
+            Value::Null => serde_json::Value::Null,
+            Value::Bool(v) => serde_json::Value::Bool(v),
+            Value::Integer(v) => serde_json::Value::from(v),
+            Value::Float(v) => {
+                let v = track_assert_some!(
+                    serde_json::Number::from_f64(v),
+                    ErrorKind::Invalid,
+                    "JSON cannot represent a non-finite float: {}",
+                    v
+                );
+                serde_json::Value::Number(v)
+            }
+            Value::String(v) => serde_json::Value::String(v),
+            Value::Array(v) => serde_json::Value::Array(track!(v
+                .into_iter()
+                .map(serde_json::Value::try_from)
+                .collect::<Result<_, _>>())?),
+            Value::Object(v) => serde_json::Value::Object(track!(v
+                .into_iter()
+                .map(|(k, v)| serde_json::Value::try_from(v).map(|v| (k, v)))
+                .collect::<Result<_, _>>())?),
+            Value::Bytes(v) => serde_json::Value::from(v),
+        })
+    }
+}
+impl From<toml::Value> for Value {
+    fn from(f: toml::Value) -> Self {
+        match f {
+            toml::Value::Boolean(v) => Value::Bool(v),
+            toml::Value::Integer(v) => Value::Integer(v),
+            toml::Value::Float(v) => Value::Float(v),
+            toml::Value::String(v) => Value::String(v),
+            toml::Value::Datetime(v) => Value::String(v.to_string()),
+            toml::Value::Array(v) => Value::Array(v.into_iter().map(Value::from).collect()),
+            toml::Value::Table(v) => {
+                Value::Object(v.into_iter().map(|(k, v)| (k, Value::from(v))).collect())
+            }
+        }
+    }
+}
+impl TryFrom<Value> for toml::Value {
+    type Error = Error;
+
+    fn try_from(f: Value) -> Result<Self, Error> {
+        Ok(match f {
+            Value::Null => track_panic!(ErrorKind::Invalid, "TOML cannot represent a null value"),
+            Value::Bool(v) => toml::Value::Boolean(v),
+            Value::Integer(v) => toml::Value::Integer(v),
+            Value::Float(v) => toml::Value::Float(v),
+            Value::String(v) => toml::Value::String(v),
+            Value::Array(v) => toml::Value::Array(track!(v
+                .into_iter()
+                .map(TryFrom::try_from)
+                .collect::<Result<_, _>>())?),
+            Value::Object(v) => toml::Value::Table(track!(v
+                .into_iter()
+                .map(|(k, v)| TryFrom::try_from(v).map(|v| (k, v)))
+                .collect::<Result<_, _>>())?),
+            Value::Bytes(v) => toml::Value::Array(
+                v.into_iter()
+                    .map(|b| toml::Value::Integer(b.into()))
+                    .collect(),
+            ),
+        })
+    }
+}
+impl Serialize for Value {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            Value::Null => serializer.serialize_unit(),
+            Value::Bool(v) => serializer.serialize_bool(v),
+            Value::Integer(v) => serializer.serialize_i64(v),
+            Value::Float(v) => serializer.serialize_f64(v),
+            Value::String(ref v) => serializer.serialize_str(v),
+            Value::Array(ref v) => {
+                let mut seq = serializer.serialize_seq(Some(v.len()))?;
+                for e in v {
+                    seq.serialize_element(e)?;
+                }
+                seq.end()
+            }
+            Value::Object(ref v) => {
+                let mut map = serializer.serialize_map(Some(v.len()))?;
+                for (k, v) in v {
+                    map.serialize_entry(k, v)?;
+                }
+                map.end()
+            }
+            Value::Bytes(ref v) => serializer.serialize_bytes(v),
+        }
+    }
+}
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+const TOML_DATETIME_FIELD: &str = "$__toml_private_datetime";
+
+struct ValueVisitor;
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "any value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(Value::Integer(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        if let Ok(v) = i64::try_from(v) {
+            Ok(Value::Integer(v))
+        } else {
+            Ok(Value::Float(v as f64))
+        }
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(Value::Float(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(Value::String(v.to_owned()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(Value::Bytes(v.to_owned()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(Value::Bytes(v))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut values = Vec::new();
+        while let Some(v) = seq.next_element()? {
+            values.push(v);
+        }
+        Ok(Value::Array(values))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut values = BTreeMap::new();
+        while let Some((k, v)) = map.next_entry::<String, _>()? {
+            values.insert(k, v);
+        }
+        if values.len() == 1 {
+            // TOML datetimes are deserialized as a special single-entry map.
+            if let Some(&Value::String(_)) = values.get(TOML_DATETIME_FIELD) {
+                return Ok(values.remove(TOML_DATETIME_FIELD).expect("never fails"));
+            }
+        }
+        Ok(Value::Object(values))
+    }
+}
+
+/// A TOML datetime value.
+///
+/// This is a thin wrapper of `toml::value::Datetime` and can be used as a field type of
+/// a struct that is deserialized by `from_toml_str` and its friends.
+///
+/// TOML distinguishes the following four kinds of datetimes:
+///
+/// | Kind             | Example                       | `chrono` type (`chrono` feature) |
+/// |------------------|-------------------------------|----------------------------------|
+/// | Offset Date-Time | `1979-05-27T07:32:00-08:00`   | `DateTime<FixedOffset>`          |
+/// | Local Date-Time  | `1979-05-27T07:32:00`         | `NaiveDateTime`                  |
+/// | Local Date       | `1979-05-27`                  | `NaiveDate`                      |
+/// | Local Time       | `07:32:00`                    | `NaiveTime`                      |
+///
+/// If the `chrono` feature is enabled, `Datetime` can be converted from/to
+/// the corresponding `chrono` types via `TryFrom`.
+/// Converting a datetime to a `chrono` type of a different kind results in an `ErrorKind::Invalid` error.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde;
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serdeconv;
+///
+/// use serdeconv::value::Datetime;
+///
+/// #[derive(Deserialize)]
+/// struct Foo {
+///     created_at: Datetime,
+/// }
+///
+/// # fn main() {
+/// let foo: Foo = serdeconv::from_toml_str("created_at = 1979-05-27T07:32:00Z").unwrap();
+/// assert_eq!(foo.created_at.to_string(), "1979-05-27T07:32:00Z");
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Datetime(pub toml::value::Datetime);
+impl From<toml::value::Datetime> for Datetime {
+    fn from(f: toml::value::Datetime) -> Self {
+        Datetime(f)
+    }
+}
+impl From<Datetime> for toml::value::Datetime {
+    fn from(f: Datetime) -> Self {
+        f.0
+    }
+}
+impl fmt::Display for Datetime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+impl Serialize for Datetime {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+impl<'de> Deserialize<'de> for Datetime {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        toml::value::Datetime::deserialize(deserializer).map(Datetime)
+    }
+}
+
+/// A point in time which is encoded as the timestamp extension type (`-1`) in MessagePack.
+///
+/// In non human-readable formats (i.e., MessagePack), this is (de)serialized as
+/// the timestamp extension defined in the MessagePack specification,
+/// which is understood by the MessagePack libraries of other languages.
+/// In human-readable formats (i.e., JSON and TOML), this is (de)serialized as `SystemTime` is.
+///
+/// # Examples
+///
+/// ```
+/// use serdeconv::value::Timestamp;
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let t = Timestamp(UNIX_EPOCH + Duration::from_secs(1));
+/// let bytes = serdeconv::to_msgpack_vec(&t).unwrap();
+/// assert_eq!(bytes, [0xd6, 0xff, 0, 0, 0, 1]);
+/// assert_eq!(serdeconv::from_msgpack_slice::<Timestamp>(&bytes).unwrap(), t);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(pub SystemTime);
+impl Timestamp {
+    const EXT_TYPE: i8 = -1;
+
+    fn to_ext_payload(self) -> Vec<u8> {
+        let (secs, nanos) = match self.0.duration_since(UNIX_EPOCH) {
+            Ok(d) => (d.as_secs() as i64, d.subsec_nanos()),
+            Err(e) => {
+                let d = e.duration();
+                if d.subsec_nanos() == 0 {
+                    (-(d.as_secs() as i64), 0)
+                } else {
+                    (-(d.as_secs() as i64) - 1, 1_000_000_000 - d.subsec_nanos())
+                }
+            }
+        };
+        if secs >> 34 == 0 {
+            let v = (u64::from(nanos) << 34) | secs as u64;
+            if v >> 32 == 0 {
+                (v as u32).to_be_bytes().to_vec()
+            } else {
+                v.to_be_bytes().to_vec()
+            }
+        } else {
+            let mut payload = nanos.to_be_bytes().to_vec();
+            payload.extend_from_slice(&secs.to_be_bytes());
+            payload
+        }
+    }
+
+    fn from_ext_payload(payload: &[u8]) -> Option<Self> {
+        let (secs, nanos) = match payload.len() {
+            4 => {
+                let mut buf = [0; 4];
+                buf.copy_from_slice(payload);
+                (i64::from(u32::from_be_bytes(buf)), 0)
+            }
+            8 => {
+                let mut buf = [0; 8];
+                buf.copy_from_slice(payload);
+                let v = u64::from_be_bytes(buf);
+                ((v & 0x3_ffff_ffff) as i64, (v >> 34) as u32)
+            }
+            12 => {
+                let mut nanos = [0; 4];
+                let mut secs = [0; 8];
+                nanos.copy_from_slice(&payload[..4]);
+                secs.copy_from_slice(&payload[4..]);
+                (i64::from_be_bytes(secs), u32::from_be_bytes(nanos))
+            }
+            _ => return None,
+        };
+        if nanos >= 1_000_000_000 {
+            return None;
+        }
+        let t = if secs >= 0 {
+            UNIX_EPOCH.checked_add(Duration::new(secs as u64, nanos))
+        } else {
+            UNIX_EPOCH
+                .checked_sub(Duration::from_secs(secs.unsigned_abs()))
+                .and_then(|t| t.checked_add(Duration::from_nanos(u64::from(nanos))))
+        };
+        t.map(Timestamp)
+    }
+}
+impl From<SystemTime> for Timestamp {
+    fn from(f: SystemTime) -> Self {
+        Timestamp(f)
+    }
+}
+impl From<Timestamp> for SystemTime {
+    fn from(f: Timestamp) -> Self {
+        f.0
+    }
+}
+impl Serialize for Timestamp {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            self.0.serialize(serializer)
+        } else {
+            let payload = ExtPayload(self.to_ext_payload());
+            serializer.serialize_newtype_struct(EXT_STRUCT_NAME, &(Self::EXT_TYPE, payload))
+        }
+    }
+}
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            SystemTime::deserialize(deserializer).map(Timestamp)
+        } else {
+            deserializer.deserialize_newtype_struct(EXT_STRUCT_NAME, TimestampVisitor)
+        }
+    }
+}
+
+/// The special struct name used by `rmp_serde` to (de)serialize extension types.
+const EXT_STRUCT_NAME: &str = "_ExtStruct";
+
+struct ExtPayload(Vec<u8>);
+impl Serialize for ExtPayload {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+impl<'de> Deserialize<'de> for ExtPayload {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_bytes(ExtPayloadVisitor)
+    }
+}
+
+struct ExtPayloadVisitor;
+impl<'de> Visitor<'de> for ExtPayloadVisitor {
+    type Value = ExtPayload;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("bytes")
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(ExtPayload(v.to_owned()))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(ExtPayload(v))
+    }
+}
+
+struct TimestampVisitor;
+impl<'de> Visitor<'de> for TimestampVisitor {
+    type Value = Timestamp;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a MessagePack timestamp extension")
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (ext_type, payload): (i8, ExtPayload) = Deserialize::deserialize(deserializer)?;
+        if ext_type != Timestamp::EXT_TYPE {
+            return Err(de::Error::custom(format!(
+                "Unexpected extension type: {}",
+                ext_type
+            )));
+        }
+        Timestamp::from_ext_payload(&payload.0)
+            .ok_or_else(|| de::Error::invalid_length(payload.0.len(), &"a valid timestamp payload"))
+    }
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_impls {
+    use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+    use std::convert::TryFrom;
+    use toml::value::{Date, Offset, Time};
+
+    use super::Datetime;
+    use {Error, ErrorKind, Result};
+
+    fn to_naive_date(date: Option<Date>) -> Result<NaiveDate> {
+        let date = track_assert_some!(date, ErrorKind::Invalid, "No date part");
+        let date = NaiveDate::from_ymd_opt(
+            i32::from(date.year),
+            u32::from(date.month),
+            u32::from(date.day),
+        );
+        Ok(track_assert_some!(date, ErrorKind::Invalid, "Invalid date"))
+    }
+
+    fn to_naive_time(time: Option<Time>) -> Result<NaiveTime> {
+        let time = track_assert_some!(time, ErrorKind::Invalid, "No time part");
+        track_assert!(
+            time.nanosecond < 1_000_000_000,
+            ErrorKind::Invalid,
+            "Invalid nanosecond: {}",
+            time.nanosecond
+        );
+        // `chrono` represents a leap second as the 59th second with an extra second of nanoseconds.
+        let (second, nanosecond) = if time.second == 60 {
+            (59, time.nanosecond + 1_000_000_000)
+        } else {
+            (u32::from(time.second), time.nanosecond)
+        };
+        let time = NaiveTime::from_hms_nano_opt(
+            u32::from(time.hour),
+            u32::from(time.minute),
+            second,
+            nanosecond,
+        );
+        Ok(track_assert_some!(time, ErrorKind::Invalid, "Invalid time"))
+    }
+
+    fn from_naive_date(date: NaiveDate) -> Result<Date> {
+        track_assert!(
+            (0..=9999).contains(&date.year()),
+            ErrorKind::Invalid,
+            "Year out of range: {}",
+            date.year()
+        );
+        Ok(Date {
+            year: date.year() as u16,
+            month: date.month() as u8,
+            day: date.day() as u8,
+        })
+    }
+
+    fn from_naive_time(time: NaiveTime) -> Time {
+        // A leap second (whose nanoseconds are `1_000_000_000` or more) is folded into `second = 60`.
+        let (second, nanosecond) = if time.nanosecond() >= 1_000_000_000 {
+            (60, time.nanosecond() - 1_000_000_000)
+        } else {
+            (time.second() as u8, time.nanosecond())
+        };
+        Time {
+            hour: time.hour() as u8,
+            minute: time.minute() as u8,
+            second,
+            nanosecond,
+        }
+    }
+
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate chrono;
+    /// extern crate serdeconv;
+    ///
+    /// use chrono::{DateTime, FixedOffset};
+    /// use serdeconv::value::Datetime;
+    /// use std::convert::TryFrom;
+    ///
+    /// # fn main() {
+    /// let datetime = Datetime("1979-05-27T07:32:00-08:00".parse().unwrap());
+    /// let chrono = DateTime::<FixedOffset>::try_from(datetime).unwrap();
+    /// assert_eq!(chrono.to_rfc3339(), "1979-05-27T07:32:00-08:00");
+    /// assert_eq!(Datetime::try_from(chrono).unwrap(), datetime);
+    ///
+    /// // A local date-time has no offset.
+    /// let local = Datetime("1979-05-27T07:32:00".parse().unwrap());
+    /// let error = DateTime::<FixedOffset>::try_from(local).unwrap_err();
+    /// assert!(error.is_invalid());
+    /// # }
+    /// ```
+    impl TryFrom<Datetime> for DateTime<FixedOffset> {
+        type Error = Error;
+
+        fn try_from(f: Datetime) -> Result<Self> {
+            let date = track!(to_naive_date(f.0.date))?;
+            let time = track!(to_naive_time(f.0.time))?;
+            let offset = match track_assert_some!(f.0.offset, ErrorKind::Invalid, "No offset") {
+                Offset::Z => 0,
+                Offset::Custom { minutes } => i32::from(minutes) * 60,
+            };
+            let offset = track_assert_some!(
+                FixedOffset::east_opt(offset),
+                ErrorKind::Invalid,
+                "Invalid offset"
+            );
+            let datetime = track_assert_some!(
+                date.and_time(time).and_local_timezone(offset).single(),
+                ErrorKind::Invalid,
+                "Invalid datetime"
+            );
+            Ok(datetime)
+        }
+    }
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate chrono;
+    /// extern crate serdeconv;
+    ///
+    /// use chrono::{NaiveDate, NaiveDateTime};
+    /// use serdeconv::value::Datetime;
+    /// use std::convert::TryFrom;
+    ///
+    /// # fn main() {
+    /// // A leap second is represented as `second = 60` in TOML.
+    /// let chrono = NaiveDate::from_ymd_opt(2016, 12, 31)
+    ///     .unwrap()
+    ///     .and_hms_milli_opt(23, 59, 59, 1_500)
+    ///     .unwrap();
+    /// let datetime = Datetime::try_from(chrono).unwrap();
+    /// assert_eq!(datetime.to_string(), "2016-12-31T23:59:60.5");
+    /// assert_eq!(NaiveDateTime::try_from(datetime).unwrap(), chrono);
+    ///
+    /// // Years out of the TOML range cannot be converted.
+    /// let chrono = NaiveDate::from_ymd_opt(10000, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+    /// assert!(Datetime::try_from(chrono).unwrap_err().is_invalid());
+    /// # }
+    /// ```
+    impl TryFrom<Datetime> for NaiveDateTime {
+        type Error = Error;
+
+        fn try_from(f: Datetime) -> Result<Self> {
+            track_assert!(
+                f.0.offset.is_none(),
+                ErrorKind::Invalid,
+                "Unexpected offset"
+            );
+            let date = track!(to_naive_date(f.0.date))?;
+            let time = track!(to_naive_time(f.0.time))?;
+            Ok(date.and_time(time))
+        }
+    }
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate chrono;
+    /// extern crate serdeconv;
+    ///
+    /// use chrono::{NaiveDate, NaiveTime};
+    /// use serdeconv::value::Datetime;
+    /// use std::convert::TryFrom;
+    ///
+    /// # fn main() {
+    /// let date = Datetime("1979-05-27".parse().unwrap());
+    /// let chrono = NaiveDate::try_from(date).unwrap();
+    /// assert_eq!(chrono, NaiveDate::from_ymd_opt(1979, 5, 27).unwrap());
+    /// assert_eq!(Datetime::try_from(chrono).unwrap(), date);
+    ///
+    /// let time = Datetime("07:32:00.25".parse().unwrap());
+    /// let chrono = NaiveTime::try_from(time).unwrap();
+    /// assert_eq!(chrono, NaiveTime::from_hms_milli_opt(7, 32, 0, 250).unwrap());
+    /// assert_eq!(Datetime::from(chrono), time);
+    ///
+    /// // A date has no time part, and vice versa.
+    /// assert!(NaiveTime::try_from(date).unwrap_err().is_invalid());
+    /// assert!(NaiveDate::try_from(time).unwrap_err().is_invalid());
+    /// # }
+    /// ```
+    impl TryFrom<Datetime> for NaiveDate {
+        type Error = Error;
+
+        fn try_from(f: Datetime) -> Result<Self> {
+            track_assert!(
+                f.0.time.is_none(),
+                ErrorKind::Invalid,
+                "Unexpected time part"
+            );
+            track!(to_naive_date(f.0.date))
+        }
+    }
+    impl TryFrom<Datetime> for NaiveTime {
+        type Error = Error;
+
+        fn try_from(f: Datetime) -> Result<Self> {
+            track_assert!(
+                f.0.date.is_none(),
+                ErrorKind::Invalid,
+                "Unexpected date part"
+            );
+            track!(to_naive_time(f.0.time))
+        }
+    }
+    impl TryFrom<DateTime<FixedOffset>> for Datetime {
+        type Error = Error;
+
+        fn try_from(f: DateTime<FixedOffset>) -> Result<Self> {
+            let mut datetime = track!(Datetime::try_from(f.naive_local()))?;
+            datetime.0.offset = Some(Offset::Custom {
+                minutes: (f.offset().local_minus_utc() / 60) as i16,
+            });
+            Ok(datetime)
+        }
+    }
+    impl TryFrom<NaiveDateTime> for Datetime {
+        type Error = Error;
+
+        fn try_from(f: NaiveDateTime) -> Result<Self> {
+            let mut datetime = track!(Datetime::try_from(f.date()))?;
+            datetime.0.time = Some(from_naive_time(f.time()));
+            Ok(datetime)
+        }
+    }
+    impl TryFrom<NaiveDate> for Datetime {
+        type Error = Error;
+
+        fn try_from(f: NaiveDate) -> Result<Self> {
+            let date = track!(from_naive_date(f))?;
+            Ok(Datetime(toml::value::Datetime {
+                date: Some(date),
+                time: None,
+                offset: None,
+            }))
+        }
+    }
+    impl From<NaiveTime> for Datetime {
+        fn from(f: NaiveTime) -> Self {
+            Datetime(toml::value::Datetime {
+                date: None,
+                time: Some(from_naive_time(f)),
+                offset: None,
+            })
+        }
+    }
+}